@@ -19,7 +19,6 @@ use powdr_analysis::utils::parse_pil_statement;
 use powdr_number::BigUint;
 
 const MAIN_MACHINE: &str = "::Main";
-const MAIN_FUNCTION: &str = "main";
 
 pub fn compile(input: AnalysisASMFile) -> MachineInstanceGraph {
     let main_location = Location::main();
@@ -185,11 +184,11 @@ pub fn compile(input: AnalysisASMFile) -> MachineInstanceGraph {
         call_selectors: main_ty.call_selectors.clone(),
     };
     let entry_points = main_ty
-        .operations()
+        .operation_definitions()
         .map(|o| Operation {
-            name: MAIN_FUNCTION.to_string(),
-            id: o.id.id.clone(),
-            params: o.params.clone(),
+            name: o.name.to_string(),
+            id: o.operation.id.id.clone(),
+            params: o.operation.params.clone(),
         })
         .collect();
 
@@ -355,6 +354,7 @@ impl<'a> ASMPILConverter<'a> {
             latch: input.latch,
             call_selectors: input.call_selectors,
             has_pc: input.pc.is_some(),
+            publics: vec![],
         }
     }
 
@@ -378,6 +378,7 @@ impl<'a> ASMPILConverter<'a> {
             params,
             instr_flag,
             link_flag,
+            operation_id: None,
         };
 
         // get the type name for this submachine from the submachine declarations and parameters
@@ -432,6 +433,7 @@ impl<'a> ASMPILConverter<'a> {
                     },
                     // this will be set later, after compatible links are merged
                     selector_idx: None,
+                    alternative_operations: vec![],
                 })
                 .unwrap()
                 .clone(),
@@ -8,7 +8,7 @@ pub fn convert_asm_to_pil<T: FieldElement>(
     file: ASMProgram,
 ) -> Result<AnalysisASMFile, Vec<String>> {
     let file = analyze(file)?;
-    Ok(powdr_asm_to_pil::compile::<T>(file))
+    powdr_asm_to_pil::compile::<T>(file)
 }
 
 pub fn analyze(file: ASMProgram) -> Result<AnalysisASMFile, Vec<String>> {
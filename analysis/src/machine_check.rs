@@ -50,6 +50,7 @@ impl TypeChecker {
                         Some(RegisterFlag::IsAssignment) => RegisterTy::Assignment,
                         Some(RegisterFlag::IsPC) => RegisterTy::Pc,
                         Some(RegisterFlag::IsReadOnly) => RegisterTy::ReadOnly,
+                        Some(RegisterFlag::IsBool) => RegisterTy::Bool,
                         None => RegisterTy::Write,
                     };
                     registers.push(RegisterDeclarationStatement { source, name, ty });
@@ -378,7 +379,9 @@ impl TypeChecker {
             .iter()
             .filter_map(|s| {
                 // TODO this could be a function call that returns an identity including a selector in the future.
-                if let powdr_ast::parsed::PilStatement::Expression(_, _) = s {
+                if let powdr_ast::parsed::PilStatement::Expression(_, _)
+                | powdr_ast::parsed::PilStatement::PolynomialConstantDefinition(_, _, _) = s
+                {
                     None
                 } else {
                     Some(format!("Statement not allowed in instruction body: {s}"))
@@ -1,5 +1,6 @@
 //! Infer assignment registers in asm statements
 
+use itertools::Itertools;
 use powdr_ast::{
     asm_analysis::{AnalysisASMFile, Expression, FunctionStatement, Machine},
     parsed::asm::AssignmentRegister,
@@ -26,38 +27,52 @@ pub fn infer(mut file: AnalysisASMFile) -> Result<AnalysisASMFile, Vec<String>>
 fn infer_machine(machine: &mut Machine) -> Result<(), Vec<String>> {
     let mut errors = vec![];
 
+    // If the machine has a single assignment register, it is used wherever
+    // the assignment register cannot be inferred from the right hand side.
+    let single_assignment_register = machine
+        .assignment_register_names()
+        .exactly_one()
+        .ok()
+        .map(str::to_string);
+
     for f in machine.callable.functions_mut() {
         for s in f.body.statements.iter_mut() {
             if let FunctionStatement::Assignment(a) = s {
                 // Map function calls to the list of assignment registers and all other expressions to a list of None.
                 let expr_regs = match &*a.rhs {
                     Expression::FunctionCall(_, c) => {
-                        let instr_name =
-                            if let Expression::Reference(_, reference) = c.function.as_ref() {
-                                reference.try_to_identifier().unwrap()
-                            } else {
-                                panic!("Only instructions allowed.");
-                            };
-                        let def = machine
-                            .instructions
-                            .iter()
-                            .find(|i| i.name == *instr_name)
-                            .unwrap_or_else(|| panic!("invalid instruction: {instr_name}"));
+                        let Some(instr_name) = (match c.function.as_ref() {
+                            Expression::Reference(_, reference) => reference.try_to_identifier(),
+                            _ => None,
+                        }) else {
+                            errors.push(format!("Only instructions allowed, found `{}`", a.rhs));
+                            continue;
+                        };
+                        let Some(def) = machine.instructions.iter().find(|i| i.name == *instr_name)
+                        else {
+                            errors.push(format!("Invalid instruction: {instr_name}"));
+                            continue;
+                        };
 
                         def.instruction
                             .params
                             .outputs
                             .iter()
-                            .map(|o| {
-                                assert!(o.ty.is_none());
-                                AssignmentRegister::Register(o.name.clone())
+                            .map(|o| match &o.ty {
+                                // Lookup outputs can be read into any assignment register,
+                                // so the caller needs to specify one.
+                                Some(_) => AssignmentRegister::Wildcard,
+                                None => AssignmentRegister::Register(o.name.clone()),
                             })
                             .collect::<Vec<_>>()
                     }
                     _ => vec![AssignmentRegister::Wildcard; a.lhs_with_reg.len()],
                 };
 
-                assert_eq!(expr_regs.len(), a.lhs_with_reg.len());
+                if expr_regs.len() != a.lhs_with_reg.len() {
+                    errors.push(format!("Mismatched number of registers for assignment {a}"));
+                    continue;
+                }
 
                 for ((w, reg), expr_reg) in a.lhs_with_reg.iter_mut().zip(expr_regs) {
                     match (&reg, expr_reg) {
@@ -73,6 +88,10 @@ fn infer_machine(machine: &mut Machine) -> Result<(), Vec<String>> {
                             *reg = AssignmentRegister::Register(expr_reg);
                         }
                         (AssignmentRegister::Wildcard, AssignmentRegister::Wildcard) => {
+                            if let Some(single) = &single_assignment_register {
+                                *reg = AssignmentRegister::Register(single.clone());
+                                continue;
+                            }
                             errors.push(format!("Impossible to infer the assignment register to write to register `{w}`"));
                         }
                     }
@@ -194,6 +213,42 @@ mod tests {
         assert_eq!(infer_str(file).unwrap_err(), vec!["Assignment register `Y` is incompatible with `foo()`. Try using `<==` with no explicit assignment registers."]);
     }
 
+    #[test]
+    fn single_assignment_register() {
+        let file = r#"
+            machine Machine {
+                reg pc[@pc];
+                reg X[<=];
+                reg A;
+
+                function main {
+                    A <== A + 1;
+                }
+            }
+        "#;
+
+        let file = infer_str(file).unwrap();
+
+        let machine = &file.get_machine(&parse_absolute_path("::Machine")).unwrap();
+        if let FunctionStatement::Assignment(AssignmentStatement { lhs_with_reg, .. }) = machine
+            .functions()
+            .next()
+            .unwrap()
+            .body
+            .statements
+            .iter()
+            .next()
+            .unwrap()
+        {
+            assert_eq!(
+                lhs_with_reg[0].1,
+                AssignmentRegister::Register("X".to_string())
+            );
+        } else {
+            panic!()
+        };
+    }
+
     #[test]
     fn unclear() {
         let file = r#"
@@ -0,0 +1,310 @@
+//! Recover the asm constructs the columns of a virtual machine were generated from
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use powdr_ast::{
+    asm_analysis::{AnalysisASMFile, Machine},
+    object::Location,
+    parsed::asm::{parse_absolute_path, AbsoluteSymbolPath},
+};
+
+use crate::{
+    common::{input_at, output_at, ControlInstructionNames, RETURN_NAME, SHARED_FREE_VALUE},
+    ROM_SUBMACHINE_NAME,
+};
+
+/// The name of the main machine if there are several machines, see `powdr_airgen`.
+const MAIN_MACHINE: &str = "::Main";
+
+/// The asm construct a column of a virtual machine originates from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColumnOrigin {
+    /// The column holding the value of a register
+    Register(String),
+    /// The flag which is set when an instruction is executed
+    InstructionFlag(String),
+    /// The column holding a parameter of an instruction
+    InstructionParam { instruction: String, param: String },
+    /// The column holding an output of an instruction, constrained in its body
+    InstructionOutput { instruction: String, output: String },
+    /// The flag which is set when an assignment register is written to a register
+    RegisterWrite {
+        assignment_register: String,
+        register: String,
+    },
+    /// The coefficient of a register read into an assignment register
+    RegisterRead {
+        assignment_register: String,
+        register: String,
+    },
+    /// The constant added to an assignment register
+    AssignmentConstant(String),
+    /// The coefficient of the free value read into an assignment register
+    FreeValueRead(String),
+    /// The free value which can be read into an assignment register
+    FreeValue(String),
+    /// The coefficient of the shared free value read into an assignment register
+    SharedFreeValueRead(String),
+    /// The free value shared by all assignment registers of a line
+    SharedFreeValue,
+    /// The fixed column holding the value of the given column for each line of the program
+    ProgramConstant(String),
+    /// Any other column, for example one declared in the machine's PIL
+    Other,
+}
+
+/// Classifies columns of virtual machines based on the names generated during the conversion to PIL
+#[derive(Default)]
+pub struct ColumnOriginResolver {
+    /// The registers and instructions of each machine instance with a pc, by namespace.
+    machines: BTreeMap<String, MachineColumns>,
+}
+
+/// The names the columns of a single virtual machine are generated from.
+struct MachineColumns {
+    registers: BTreeSet<String>,
+    instructions: BTreeSet<String>,
+}
+
+impl ColumnOriginResolver {
+    /// Collects the registers and instructions of all instances of machines with a pc in `file`,
+    /// which is expected to be the input of [crate::compile_with_control_names] with `control_names`.
+    /// Instances are found starting from the main machine like in the instantiation of the machines,
+    /// and are indexed by the namespace of their columns.
+    pub fn new(file: &AnalysisASMFile, control_names: &ControlInstructionNames) -> Self {
+        let mut resolver = Self::default();
+        let Some(main_ty) = main_machine_type(file) else {
+            return resolver;
+        };
+        let mut queue = vec![(Location::main(), main_ty)];
+        while let Some((location, ty)) = queue.pop() {
+            let Some(machine) = file.get_machine(&ty) else {
+                continue;
+            };
+            queue.extend(
+                machine
+                    .submachines
+                    .iter()
+                    .map(|def| (location.clone().join(def.name.clone()), def.ty.clone())),
+            );
+            if machine.has_pc() {
+                resolver.machines.insert(
+                    location.namespace(),
+                    MachineColumns::new(machine, control_names),
+                );
+            }
+        }
+        resolver
+    }
+
+    /// Returns true if the columns in `namespace` belong to a machine with a pc.
+    fn is_vm(&self, namespace: &str) -> bool {
+        self.machines.contains_key(namespace)
+    }
+
+    /// Returns the origin of the column `name` in `namespace`, which may be the namespace of
+    /// a machine with a pc or of its ROM.
+    pub fn origin(&self, namespace: &str, name: &str) -> ColumnOrigin {
+        if let Some(machine) = self.machines.get(namespace) {
+            return machine.origin(name);
+        }
+        let is_rom = namespace
+            .strip_suffix(ROM_SUBMACHINE_NAME)
+            .and_then(|vm| vm.strip_suffix('_'))
+            .is_some_and(|vm| self.is_vm(vm));
+        match name.strip_prefix("p_") {
+            Some(column) if is_rom => ColumnOrigin::ProgramConstant(column.to_string()),
+            _ => ColumnOrigin::Other,
+        }
+    }
+}
+
+/// Returns the type of the main machine, chosen like in the instantiation of the machines:
+/// the only machine outside of the standard library which is not a ROM, or the one called `Main`.
+fn main_machine_type(file: &AnalysisASMFile) -> Option<AbsoluteSymbolPath> {
+    let machines = file
+        .machines()
+        .map(|(ty, _)| ty)
+        .filter(|ty| ty.parts().next() != Some("std"))
+        .filter(|ty| !ty.parts().last().unwrap().ends_with("ROM"))
+        .collect::<Vec<_>>();
+    match &machines[..] {
+        [] => None,
+        [ty] => Some(ty.clone()),
+        _ => Some(parse_absolute_path(MAIN_MACHINE)),
+    }
+}
+
+impl MachineColumns {
+    fn new(machine: &Machine, control_names: &ControlInstructionNames) -> Self {
+        let input_count = machine.functions().map(|f| f.params.inputs.len()).max();
+        let output_count = machine.functions().map(|f| f.params.outputs.len()).max();
+        let registers = machine
+            .registers
+            .iter()
+            .map(|r| r.name.clone())
+            .chain((0..input_count.unwrap_or(0)).map(input_at))
+            .chain((0..output_count.unwrap_or(0)).map(output_at))
+            .collect();
+        let instructions = machine
+            .instructions
+            .iter()
+            .map(|i| i.name.clone())
+            .chain([
+                RETURN_NAME.to_string(),
+                control_names.reset.clone(),
+                control_names.loop_.clone(),
+                control_names.jump_to_operation.clone(),
+            ])
+            .collect();
+        Self {
+            registers,
+            instructions,
+        }
+    }
+
+    /// Returns the origin of the column `name` (without namespace) of this machine.
+    fn origin(&self, name: &str) -> ColumnOrigin {
+        if self.registers.contains(name) {
+            return ColumnOrigin::Register(name.to_string());
+        }
+        if let Some(flag) = name.strip_prefix("instr_") {
+            if self.instructions.contains(flag) {
+                return ColumnOrigin::InstructionFlag(flag.to_string());
+            }
+            for instruction in &self.instructions {
+                let Some(rest) = flag.strip_prefix(instruction.as_str()) else {
+                    continue;
+                };
+                if let Some(param) = rest.strip_prefix("_param_") {
+                    return ColumnOrigin::InstructionParam {
+                        instruction: instruction.clone(),
+                        param: param.to_string(),
+                    };
+                }
+                if let Some(output) = rest.strip_prefix("_output_") {
+                    return ColumnOrigin::InstructionOutput {
+                        instruction: instruction.clone(),
+                        output: output.to_string(),
+                    };
+                }
+            }
+        }
+        if let Some((assignment_register, register)) = self.register_pair(name, "reg_write_") {
+            return ColumnOrigin::RegisterWrite {
+                assignment_register,
+                register,
+            };
+        }
+        if let Some((assignment_register, register)) = self.register_pair(name, "read_") {
+            return ColumnOrigin::RegisterRead {
+                assignment_register,
+                register,
+            };
+        }
+        let register_with_suffix = |suffix| {
+            name.strip_suffix(suffix)
+                .filter(|register| self.registers.contains(*register))
+                .map(str::to_string)
+        };
+        if let Some(register) = register_with_suffix("_const") {
+            return ColumnOrigin::AssignmentConstant(register);
+        }
+        if let Some(register) = register_with_suffix("_read_free") {
+            return ColumnOrigin::FreeValueRead(register);
+        }
+        if let Some(register) = register_with_suffix("_free_value") {
+            return ColumnOrigin::FreeValue(register);
+        }
+        if let Some(register) = register_with_suffix("_read_shared_free") {
+            return ColumnOrigin::SharedFreeValueRead(register);
+        }
+        if name == SHARED_FREE_VALUE {
+            return ColumnOrigin::SharedFreeValue;
+        }
+        ColumnOrigin::Other
+    }
+
+    /// Splits `{prefix}{assignment_register}_{register}` into both register names.
+    fn register_pair(&self, name: &str, prefix: &str) -> Option<(String, String)> {
+        let rest = name.strip_prefix(prefix)?;
+        self.registers.iter().find_map(|assignment_register| {
+            let register = rest
+                .strip_prefix(assignment_register.as_str())?
+                .strip_prefix('_')?;
+            self.registers
+                .contains(register)
+                .then(|| (assignment_register.clone(), register.to_string()))
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use powdr_importer::load_dependencies_and_resolve_str;
+
+    use super::{ColumnOrigin, ColumnOriginResolver, ControlInstructionNames};
+
+    #[test]
+    fn origins_are_resolved_per_machine() {
+        let asm = r"
+machine Sub {
+  reg pc[@pc];
+  reg X[<=];
+  reg B;
+
+  instr bar { B' = B }
+
+  function f {
+    bar;
+    return;
+  }
+}
+
+machine Main {
+  reg pc[@pc];
+  reg X[<=];
+  reg A;
+
+  Sub sub;
+
+  instr foo { A' = A }
+
+  function main {
+    foo;
+    return;
+  }
+}
+";
+        let parsed = load_dependencies_and_resolve_str(asm);
+        let analyzed = powdr_analysis::analyze(parsed).unwrap();
+        let control_names = ControlInstructionNames {
+            reset: "_clear".into(),
+            ..Default::default()
+        };
+        let resolver = ColumnOriginResolver::new(&analyzed, &control_names);
+
+        assert_eq!(
+            resolver.origin("main", "instr_foo"),
+            ColumnOrigin::InstructionFlag("foo".into())
+        );
+        assert_eq!(
+            resolver.origin("main_sub", "instr_foo"),
+            ColumnOrigin::Other
+        );
+        assert_eq!(
+            resolver.origin("main_sub", "B"),
+            ColumnOrigin::Register("B".into())
+        );
+        assert_eq!(resolver.origin("main", "B"), ColumnOrigin::Other);
+        assert_eq!(
+            resolver.origin("main_sub__rom", "p_instr_bar"),
+            ColumnOrigin::ProgramConstant("instr_bar".into())
+        );
+        assert_eq!(
+            resolver.origin("main", "instr__clear"),
+            ColumnOrigin::InstructionFlag("_clear".into())
+        );
+        assert_eq!(resolver.origin("main", "instr__reset"), ColumnOrigin::Other);
+    }
+}
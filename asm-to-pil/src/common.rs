@@ -5,8 +5,41 @@ use crate::utils::parse_instruction;
 
 /// The name for the `return` keyword in the PIL constraints
 pub const RETURN_NAME: &str = "return";
-/// The name for the `reset` instruction in the PIL constraints
+/// The default name for the `reset` instruction in the PIL constraints
 pub const RESET_NAME: &str = "_reset";
+/// The default name for the `loop` instruction in the PIL constraints
+pub const LOOP_NAME: &str = "_loop";
+/// The default name for the `jump_to_operation` instruction in the PIL constraints
+pub const JUMP_TO_OPERATION_NAME: &str = "_jump_to_operation";
+/// The name of the column holding the free input shared by all assignment registers of a line
+pub const SHARED_FREE_VALUE: &str = "_shared_free_value";
+
+/// The names of the control instructions injected into every machine with a pc
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ControlInstructionNames {
+    pub reset: String,
+    pub loop_: String,
+    pub jump_to_operation: String,
+}
+
+impl Default for ControlInstructionNames {
+    fn default() -> Self {
+        Self {
+            reset: RESET_NAME.into(),
+            loop_: LOOP_NAME.into(),
+            jump_to_operation: JUMP_TO_OPERATION_NAME.into(),
+        }
+    }
+}
+
+impl ControlInstructionNames {
+    /// Returns true if `name` is one of the control instruction names
+    pub fn contains(&self, name: &str) -> bool {
+        [&self.reset, &self.loop_, &self.jump_to_operation]
+            .into_iter()
+            .any(|n| n == name)
+    }
+}
 
 pub fn instruction_flag(name: &str) -> String {
     format!("instr_{name}")
@@ -1,9 +1,14 @@
 use std::collections::BTreeMap;
 
-use powdr_ast::asm_analysis::{AnalysisASMFile, Module, StatementReference, SubmachineDeclaration};
+pub use column_origin::{ColumnOrigin, ColumnOriginResolver};
+pub use common::ControlInstructionNames;
+use powdr_ast::asm_analysis::{
+    AnalysisASMFile, Machine, Module, StatementReference, SubmachineDeclaration,
+};
 use powdr_number::FieldElement;
 use romgen::generate_machine_rom;
-use vm_to_constrained::ROM_SUBMACHINE_NAME;
+pub use vm_to_constrained::ROM_SUBMACHINE_NAME;
+mod column_origin;
 mod common;
 mod romgen;
 mod vm_to_constrained;
@@ -11,18 +16,28 @@ mod vm_to_constrained;
 pub const ROM_SUFFIX: &str = "ROM";
 
 /// Remove all ASM from the machine tree, leaving only constrained machines
-pub fn compile<T: FieldElement>(mut file: AnalysisASMFile) -> AnalysisASMFile {
+pub fn compile<T: FieldElement>(file: AnalysisASMFile) -> Result<AnalysisASMFile, Vec<String>> {
+    compile_with_control_names::<T>(file, &ControlInstructionNames::default())
+}
+
+/// Like [compile], using `control_names` for the internal control instructions.
+/// Returns an error if a user instruction uses one of these names.
+pub fn compile_with_control_names<T: FieldElement>(
+    mut file: AnalysisASMFile,
+    control_names: &ControlInstructionNames,
+) -> Result<AnalysisASMFile, Vec<String>> {
     for (path, module) in &mut file.modules {
         let mut new_machines = BTreeMap::default();
         let (mut machines, statements, ordering) = std::mem::take(module).into_inner();
         let ordering = ordering
             .into_iter()
-            .flat_map(|r| {
-                match r {
+            .map(|r| -> Result<Vec<_>, Vec<String>> {
+                Ok(match r {
                     StatementReference::MachineDeclaration(name) => {
                         let m = machines.remove(&name).unwrap();
-                        let (m, rom) = generate_machine_rom::<T>(m);
-                        let (mut m, rom_machine) = vm_to_constrained::convert_machine::<T>(m, rom);
+                        let (mut m, rom_machine) =
+                            convert_machine_with_control_names::<T>(m, control_names)
+                                .map_err(|e| vec![e])?;
 
                         match rom_machine {
                             // in the absence of ROM, simply return the machine
@@ -55,13 +70,34 @@ pub fn compile<T: FieldElement>(mut file: AnalysisASMFile) -> AnalysisASMFile {
                         .collect()
                     }
                     r => vec![r],
-                }
+                })
             })
+            .collect::<Result<Vec<_>, Vec<String>>>()?
+            .into_iter()
+            .flatten()
             .collect();
         machines.extend(new_machines);
         *module = Module::new(machines, statements, ordering);
     }
-    file
+    Ok(file)
+}
+
+/// Remove all ASM from a single machine, see [compile].
+/// Returns the constrained machine and, if the machine has a pc, the machine holding its ROM.
+/// Unlike [compile], this does not declare the ROM machine as a submachine.
+pub fn convert_machine<T: FieldElement>(
+    machine: Machine,
+) -> Result<(Machine, Option<Machine>), String> {
+    convert_machine_with_control_names::<T>(machine, &ControlInstructionNames::default())
+}
+
+/// Like [convert_machine], using `control_names` for the internal control instructions.
+pub fn convert_machine_with_control_names<T: FieldElement>(
+    machine: Machine,
+    control_names: &ControlInstructionNames,
+) -> Result<(Machine, Option<Machine>), String> {
+    let (machine, rom) = generate_machine_rom::<T>(machine, control_names)?;
+    vm_to_constrained::convert_machine::<T>(machine, rom, control_names)
 }
 
 pub mod utils {
@@ -175,6 +211,7 @@ pub mod utils {
                     Some(RegisterFlag::IsAssignment) => RegisterTy::Assignment,
                     Some(RegisterFlag::IsPC) => RegisterTy::Pc,
                     Some(RegisterFlag::IsReadOnly) => RegisterTy::ReadOnly,
+                    Some(RegisterFlag::IsBool) => RegisterTy::Bool,
                     None => RegisterTy::Write,
                 };
                 RegisterDeclarationStatement { source, name, ty }
@@ -183,3 +220,48 @@ pub mod utils {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use powdr_importer::load_dependencies_and_resolve_str;
+    use powdr_number::GoldilocksField;
+
+    #[test]
+    fn convert_single_machine() {
+        let asm = r"
+machine Main {
+  reg pc[@pc];
+  reg X[<=];
+  reg A;
+
+  instr incr { A' = A + 1 }
+  instr assert_zero X { X = 0 }
+
+  function main {
+    incr;
+    assert_zero A - 1;
+    return;
+  }
+}
+";
+        let parsed = load_dependencies_and_resolve_str(asm);
+        let analyzed = powdr_analysis::analyze(parsed).unwrap();
+        let (_, machine) = analyzed
+            .machines()
+            .find(|(path, _)| path.to_string() == "::Main")
+            .unwrap();
+        let (machine, rom) = super::convert_machine::<GoldilocksField>(machine.clone()).unwrap();
+        assert!(rom.is_some());
+        assert!(machine.instructions.is_empty());
+        let pil = machine
+            .pil
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert!(pil.contains(&"pol commit instr_incr;".to_string()));
+        assert!(pil.contains(
+            &"A' = reg_write_X_A * X + instr_incr * (A + 1) + (1 - (reg_write_X_A + instr_incr + instr__reset)) * A;"
+                .to_string()
+        ));
+    }
+}
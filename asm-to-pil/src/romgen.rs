@@ -17,7 +17,7 @@ use powdr_parser_util::SourceRef;
 
 use crate::common::{instruction_flag, RETURN_NAME};
 use crate::{
-    common::{input_at, output_at, RESET_NAME},
+    common::{input_at, output_at, ControlInstructionNames},
     utils::{
         parse_function_statement, parse_instruction_definition, parse_pil_statement,
         parse_register_declaration,
@@ -55,10 +55,15 @@ fn pad_return_arguments(s: &mut FunctionStatement, output_count: usize) {
     };
 }
 
-pub fn generate_machine_rom<T: FieldElement>(mut machine: Machine) -> (Machine, Option<Rom>) {
+/// Generates the ROM of `machine`, using `control_names` for the embedded control instructions.
+/// Returns an error if a user instruction uses one of these names.
+pub fn generate_machine_rom<T: FieldElement>(
+    mut machine: Machine,
+    control_names: &ControlInstructionNames,
+) -> Result<(Machine, Option<Rom>), String> {
     if !machine.has_pc() {
         // do nothing, there is no rom to be generated
-        (machine, None)
+        Ok((machine, None))
     } else {
         // all callables in the machine must be functions
         assert!(machine.callable.is_only_functions());
@@ -67,20 +72,41 @@ pub fn generate_machine_rom<T: FieldElement>(mut machine: Machine) -> (Machine,
 
         let pc = machine.pc().unwrap();
 
+        let ControlInstructionNames {
+            reset,
+            loop_,
+            jump_to_operation,
+        } = control_names;
+
+        // user instructions must not collide with the embedded ones
+        if let Some(instr) = machine
+            .instructions
+            .iter()
+            .find(|i| control_names.contains(&i.name))
+        {
+            return Err(instr
+                .source
+                .with_error(format!(
+                    "Instruction name `{}` is reserved for an internal control instruction",
+                    instr.name
+                ))
+                .to_string());
+        }
+
         // add the necessary embedded instructions
         let embedded_instructions = [
             parse_instruction_definition(&format!(
-                "instr _jump_to_operation {{ {pc}' = {operation_id} }}",
+                "instr {jump_to_operation} {{ {pc}' = {operation_id} }}",
             )),
             parse_instruction_definition(&format!(
-                "instr {RESET_NAME} {{ {} }}",
+                "instr {reset} {{ {} }}",
                 machine
                     .write_register_names()
                     .map(|w| format!("{w}' = 0"))
                     .collect::<Vec<_>>()
                     .join(", ")
             )),
-            parse_instruction_definition(&format!("instr _loop {{ {pc}' = {pc} }}")),
+            parse_instruction_definition(&format!("instr {loop_} {{ {pc}' = {pc} }}")),
         ];
 
         machine.instructions.extend(embedded_instructions);
@@ -94,11 +120,13 @@ pub fn generate_machine_rom<T: FieldElement>(mut machine: Machine) -> (Machine,
         rom.extend(vec![
             Batch::from(vec![
                 parse_function_statement("_powdr_start:"),
-                parse_function_statement(&format!("{RESET_NAME};")),
+                parse_function_statement(&format!("{reset};")),
             ])
             .reason(IncompatibleSet::from(Incompatible::Unimplemented)),
-            Batch::from(vec![parse_function_statement("_jump_to_operation;")])
-                .reason(IncompatibleSet::from(Incompatible::Label)),
+            Batch::from(vec![parse_function_statement(&format!(
+                "{jump_to_operation};"
+            ))])
+            .reason(IncompatibleSet::from(Incompatible::Label)),
         ]);
 
         // the number of inputs is the max of the number of inputs needed in each function
@@ -212,7 +240,7 @@ pub fn generate_machine_rom<T: FieldElement>(mut machine: Machine) -> (Machine,
 
         rom.extend(vec![Batch::from(vec![
             parse_function_statement("_sink:"),
-            parse_function_statement("_loop;"),
+            parse_function_statement(&format!("{loop_};")),
         ])]);
 
         let latch = instruction_flag(RETURN_NAME);
@@ -239,12 +267,12 @@ pub fn generate_machine_rom<T: FieldElement>(mut machine: Machine) -> (Machine,
 
         machine.operation_id = Some(operation_id.into());
 
-        (
+        Ok((
             machine,
             Some(Rom {
                 statements: rom.into_iter().collect(),
             }),
-        )
+        ))
     }
 }
 
@@ -264,7 +292,12 @@ mod tests {
         let checked = powdr_analysis::machine_check::check(parsed).unwrap();
         checked
             .into_machines()
-            .map(|(name, m)| (name, generate_machine_rom::<T>(m)))
+            .map(|(name, m)| {
+                (
+                    name,
+                    generate_machine_rom::<T>(m, &ControlInstructionNames::default()).unwrap(),
+                )
+            })
             .collect()
     }
 
@@ -405,6 +438,70 @@ return 0;
 _sink:
 _loop;
 // END BATCH
+"#
+            .replace('\t', "    ")
+            .trim()
+        );
+    }
+
+    #[test]
+    fn user_instruction_named_reset() {
+        let vm = r#"
+            machine VM {
+                reg pc[@pc];
+                reg A;
+
+                instr _reset { A' = 0 }
+
+                function main {
+                    return;
+                }
+            }
+        "#;
+
+        let parsed = powdr_parser::parse_asm(None, vm).unwrap();
+        let checked = powdr_analysis::machine_check::check(parsed).unwrap();
+        let (_, machine) = checked.into_machines().next().unwrap();
+        let error =
+            generate_machine_rom::<Bn254Field>(machine, &ControlInstructionNames::default())
+                .unwrap_err();
+        assert!(error.starts_with(
+            "Instruction name `_reset` is reserved for an internal control instruction"
+        ));
+    }
+
+    #[test]
+    fn custom_control_names() {
+        let vm = r#"
+            machine VM {
+                reg pc[@pc];
+                reg A;
+
+                instr _reset { A' = 0 }
+            }
+        "#;
+
+        let parsed = powdr_parser::parse_asm(None, vm).unwrap();
+        let checked = powdr_analysis::machine_check::check(parsed).unwrap();
+        let control_names = ControlInstructionNames {
+            reset: "_clear".into(),
+            loop_: "_spin".into(),
+            jump_to_operation: "_dispatch".into(),
+        };
+        let (_, machine) = checked.into_machines().next().unwrap();
+        let (_, rom) = generate_machine_rom::<Bn254Field>(machine, &control_names).unwrap();
+
+        assert_eq!(
+            rom.unwrap().statements.to_string().replace('\t', "    "),
+            r#"
+_powdr_start:
+_clear;
+// END BATCH Unimplemented
+_dispatch;
+// END BATCH Label
+_sink:
+_spin;
+// END BATCH
 "#
             .replace('\t', "    ")
             .trim()
@@ -25,24 +25,29 @@ use powdr_ast::{
         Pattern, PilStatement, PolynomialName, UnaryOperation, UnaryOperator,
     },
 };
-use powdr_number::{BigUint, FieldElement, LargeInt};
+use powdr_number::{BigUint, FieldElement};
 use powdr_parser_util::SourceRef;
 
 use crate::{
-    common::{instruction_flag, return_instruction, RETURN_NAME},
+    common::{
+        instruction_flag, return_instruction, ControlInstructionNames, RETURN_NAME,
+        SHARED_FREE_VALUE,
+    },
     utils::parse_pil_statement,
 };
 
 pub fn convert_machine<T: FieldElement>(
     machine: Machine,
     rom: Option<Rom>,
-) -> (Machine, Option<Machine>) {
+    control_names: &ControlInstructionNames,
+) -> Result<(Machine, Option<Machine>), String> {
     let output_count = machine
         .operations()
         .map(|f| f.params.outputs.len())
         .max()
         .unwrap_or_default();
-    VMConverter::<T>::with_output_count(output_count).convert_machine(machine, rom)
+    VMConverter::<T>::with_output_count(output_count, control_names.reset.clone())
+        .convert_machine(machine, rom)
 }
 
 pub enum Input {
@@ -50,6 +55,15 @@ pub enum Input {
     Literal(String, LiteralKind),
 }
 
+pub enum Output {
+    /// The value is output through the assignment register of the same name.
+    Register(String),
+    /// The value is held in the given witness column, which is constrained in the
+    /// instruction body (e.g. by a lookup). It is read into the assignment register
+    /// chosen by the caller.
+    Lookup(String),
+}
+
 pub enum LiteralKind {
     Label,
     SignedConstant,
@@ -127,15 +141,27 @@ struct VMConverter<T> {
     line_lookup: Vec<(String, String)>,
     /// Names of fixed columns that contain the rom.
     rom_constant_names: Vec<String>,
+    /// Names of witness columns holding instruction outputs constrained in instruction bodies.
+    lookup_output_columns: Vec<String>,
+    /// Fixed columns declared in instruction bodies, which are shared by all instructions.
+    fixed_column_definitions: Vec<(String, FunctionDefinition)>,
+    /// Conditions of register updates which are already constrained to be boolean.
+    boolean_conditions: Vec<Expression>,
+    /// Values of the constants defined by `let` statements in the machine,
+    /// which can be used in literal instruction arguments.
+    constants: BTreeMap<String, Expression>,
     /// the maximum number of inputs in all functions
     output_count: usize,
+    /// the name of the instruction resetting the registers
+    reset_name: String,
     _phantom: std::marker::PhantomData<T>,
 }
 
 impl<T: FieldElement> VMConverter<T> {
-    fn with_output_count(output_count: usize) -> Self {
+    fn with_output_count(output_count: usize, reset_name: String) -> Self {
         Self {
             output_count,
+            reset_name,
             ..Default::default()
         }
     }
@@ -144,10 +170,12 @@ impl<T: FieldElement> VMConverter<T> {
         mut self,
         mut input: Machine,
         rom: Option<Rom>,
-    ) -> (Machine, Option<Machine>) {
+    ) -> Result<(Machine, Option<Machine>), String> {
+        // machines without a pc (e.g. lookup tables) have no code to translate,
+        // they only consist of their declared columns and PIL
         if !input.has_pc() {
             assert!(rom.is_none());
-            return (input, None);
+            return Ok((input, None));
         }
 
         // store the names of all assignment registers: we need them to generate assignment columns for other registers.
@@ -164,7 +192,7 @@ impl<T: FieldElement> VMConverter<T> {
 
         // turn internal instructions into constraints and external ones into links
         for instr in std::mem::take(&mut input.instructions) {
-            self.handle_instruction_def(&mut input, instr);
+            self.handle_instruction_def(&mut input, instr)?;
         }
 
         // introduce `return` instruction
@@ -175,14 +203,40 @@ impl<T: FieldElement> VMConverter<T> {
                 name: RETURN_NAME.into(),
                 instruction: self.return_instruction(),
             },
-        );
+        )?;
+
+        self.constants = input
+            .pil
+            .iter()
+            .filter_map(|statement| match statement {
+                PilStatement::LetStatement(_, name, _, Some(value)) => {
+                    Some((name.clone(), value.clone()))
+                }
+                _ => None,
+            })
+            .collect();
+
+        for batch in rom.unwrap().statements.into_iter_batches() {
+            self.handle_batch(batch)?;
+        }
 
+        // only create read columns for the registers that are actually read into
+        // each assignment register somewhere in the code
+        let register_reads = self.register_reads();
+        let public_reads = self.public_reads();
+        let shared_free_input_reads = self.shared_free_input_reads();
         let assignment_registers = self
             .assignment_register_names()
             .cloned()
             .collect::<Vec<_>>();
         for reg in assignment_registers {
-            self.create_constraints_for_assignment_reg(reg);
+            let reads_shared_free_input = shared_free_input_reads.contains(&reg);
+            self.create_constraints_for_assignment_reg(
+                reg,
+                &register_reads,
+                &public_reads,
+                reads_shared_free_input,
+            );
         }
 
         // introduce `first_step` which is used for register updates
@@ -192,6 +246,7 @@ impl<T: FieldElement> VMConverter<T> {
             FunctionDefinition::Array(ArrayExpression::value(vec![1u32.into()]).pad_with_zeroes()),
         ));
 
+        let mut boolean_conditions = std::mem::take(&mut self.boolean_conditions);
         self.pil.extend(
             self.registers
                 .iter()
@@ -199,7 +254,7 @@ impl<T: FieldElement> VMConverter<T> {
                     reg.update_expression().map(|rhs| {
                         let lhs = next_reference(name);
                         use RegisterTy::*;
-                        match reg.ty {
+                        let mut statements = match reg.ty {
                             // Force pc to zero on first row.
                             Pc => {
                                 // introduce an intermediate witness polynomial to keep the degree of polynomial identities at 2
@@ -228,10 +283,10 @@ impl<T: FieldElement> VMConverter<T> {
                                     ),
                                 ]
                             }
-                            // Un-constrain read-only registers when calling `_reset`
+                            // Un-constrain read-only registers when calling the reset instruction
                             ReadOnly => {
-                                let not_reset: Expression =
-                                    Expression::from(1) - direct_reference("instr__reset");
+                                let not_reset: Expression = Expression::from(1)
+                                    - direct_reference(instruction_flag(&self.reset_name));
                                 vec![PilStatement::Expression(
                                     SourceRef::unknown(),
                                     build::identity(not_reset * (lhs - rhs), 0.into()),
@@ -243,19 +298,23 @@ impl<T: FieldElement> VMConverter<T> {
                                     build::identity(lhs, rhs),
                                 )]
                             }
-                        }
+                        };
+                        statements.extend(
+                            reg.condition_constraints(&mut boolean_conditions)
+                                .into_iter()
+                                .map(|constraint| {
+                                    PilStatement::Expression(SourceRef::unknown(), constraint)
+                                }),
+                        );
+                        statements
                     })
                 })
                 .flatten(),
         );
 
-        for batch in rom.unwrap().statements.into_iter_batches() {
-            self.handle_batch(batch);
-        }
-
         input.latch = Some(instruction_flag(RETURN_NAME));
 
-        self.translate_code_lines();
+        self.translate_code_lines()?;
 
         input.links.push(LinkDefinition {
             source: SourceRef::unknown(),
@@ -284,43 +343,73 @@ impl<T: FieldElement> VMConverter<T> {
 
         let rom_degree = Expression::from(self.code_lines.len().next_power_of_two() as u32).into();
 
-        (
+        Ok((
             input,
             Some(rom_machine(
                 rom_degree,
                 self.rom_pil,
                 self.line_lookup.iter().map(|(_, x)| x.as_ref()),
             )),
-        )
+        ))
     }
 
-    fn handle_batch(&mut self, batch: Batch) {
-        let code_line = batch
-            .statements
-            .into_iter()
-            .map(|s| self.handle_statement(s))
-            .reduce(|mut acc, e| {
-                // we write to the union of the target registers.
-                assert!(acc.write_regs.is_empty());
-                acc.write_regs.extend(e.write_regs);
-                // we write the union of the written values.
-                assert!(acc.value.is_empty());
-                acc.value.extend(e.value);
-                // we use the union of the used instructions.
-                assert!(acc.instructions.is_empty());
-                acc.instructions.extend(e.instructions);
-                // we use the union of the labels
-                acc.labels.extend(e.labels);
-                // we use the union of debug directives
-                acc.debug_directives.extend(e.debug_directives);
-                acc
-            })
-            .expect("unexpected empty batch");
-
-        self.code_lines.push(code_line);
+    fn handle_batch(&mut self, batch: Batch) -> Result<(), String> {
+        let mut code_line: Option<CodeLine<T>> = None;
+        for statement in batch.statements {
+            let statement_string = statement.to_string();
+            let e = self.handle_statement(statement)?;
+            let Some(acc) = code_line.as_mut() else {
+                code_line = Some(e);
+                continue;
+            };
+            // we write to the union of the target registers.
+            for (assign_reg, writes) in e.write_regs {
+                if acc.write_regs.contains_key(&assign_reg) {
+                    return Err(format!(
+                        "Conflicting writes through assignment register {assign_reg} in the same batch: {statement_string}"
+                    ));
+                }
+                acc.write_regs.insert(assign_reg, writes);
+            }
+            // we write the union of the written values.
+            for (assign_reg, value) in e.value {
+                if acc.value.contains_key(&assign_reg) {
+                    return Err(format!(
+                        "Conflicting values for assignment register {assign_reg} in the same batch: {statement_string}"
+                    ));
+                }
+                acc.value.insert(assign_reg, value);
+            }
+            // we use the union of the used instructions.
+            if !acc.instructions.is_empty() {
+                return Err(format!(
+                    "Statement after an instruction in the same batch: {statement_string}"
+                ));
+            }
+            acc.instructions.extend(e.instructions);
+            // we use the union of the labels
+            acc.labels.extend(e.labels);
+            // we use the union of debug directives
+            acc.debug_directives.extend(e.debug_directives);
+        }
+        let code_line = code_line.expect("unexpected empty batch");
+
+        // A line consisting only of labels and debug directives does not need its own
+        // row, we attach it to the following line, so that `label: instr` is a single line.
+        match self.code_lines.last_mut() {
+            Some(last) if last.is_only_labels_and_directives() => {
+                last.write_regs = code_line.write_regs;
+                last.value = code_line.value;
+                last.instructions = code_line.instructions;
+                last.labels.extend(code_line.labels);
+                last.debug_directives.extend(code_line.debug_directives);
+            }
+            _ => self.code_lines.push(code_line),
+        }
+        Ok(())
     }
 
-    fn handle_statement(&mut self, statement: FunctionStatement) -> CodeLine<T> {
+    fn handle_statement(&mut self, statement: FunctionStatement) -> Result<CodeLine<T>, String> {
         match statement {
             FunctionStatement::Assignment(AssignmentStatement {
                 source,
@@ -344,15 +433,24 @@ impl<T: FieldElement> VMConverter<T> {
                 instruction,
                 inputs,
                 ..
-            }) => self.handle_instruction(instruction, inputs),
-            FunctionStatement::Label(LabelStatement { name, .. }) => CodeLine {
+            }) => {
+                if self.instructions.get(&instruction).is_some_and(|instr| {
+                    instr.outputs.iter().any(|o| matches!(o, Output::Lookup(_)))
+                }) {
+                    return Err(format!(
+                        "Instruction {instruction} has lookup outputs and can only be used in an assignment."
+                    ));
+                }
+                self.handle_instruction(instruction, inputs)
+            }
+            FunctionStatement::Label(LabelStatement { name, .. }) => Ok(CodeLine {
                 labels: [name].into(),
                 ..Default::default()
-            },
-            FunctionStatement::DebugDirective(d) => CodeLine {
+            }),
+            FunctionStatement::DebugDirective(d) => Ok(CodeLine {
                 debug_directives: vec![d],
                 ..Default::default()
-            },
+            }),
             FunctionStatement::Return(r) => self.handle_instruction(RETURN_NAME.into(), r.values),
         }
     }
@@ -363,6 +461,7 @@ impl<T: FieldElement> VMConverter<T> {
     ) {
         let mut conditioned_updates = vec![];
         let mut default_update = None;
+        let is_bool = ty == RegisterTy::Bool;
         match ty {
             RegisterTy::Pc => {
                 assert_eq!(self.pc_name, None);
@@ -378,7 +477,7 @@ impl<T: FieldElement> VMConverter<T> {
                 // default update to be kept constant
                 default_update = Some(direct_reference(&name))
             }
-            RegisterTy::Write => {
+            RegisterTy::Write | RegisterTy::Bool => {
                 let assignment_regs = self
                     .assignment_register_names()
                     .cloned()
@@ -401,10 +500,21 @@ impl<T: FieldElement> VMConverter<T> {
                 ty,
             },
         );
-        self.pil.push(witness_column(source, name, None));
+        self.pil.push(witness_column(source.clone(), &name, None));
+        if is_bool {
+            let reg = direct_reference(&name);
+            self.pil.push(PilStatement::Expression(
+                source,
+                build::identity(reg.clone() * (Expression::from(1) - reg), 0.into()),
+            ));
+        }
     }
 
-    fn handle_instruction_def(&mut self, input: &mut Machine, s: InstructionDefinitionStatement) {
+    fn handle_instruction_def(
+        &mut self,
+        input: &mut Machine,
+        s: InstructionDefinitionStatement,
+    ) -> Result<(), String> {
         let instruction_name = s.name.clone();
         let instruction_flag = format!("instr_{instruction_name}");
         self.create_witness_fixed_pair(s.source.clone(), &instruction_flag);
@@ -412,9 +522,11 @@ impl<T: FieldElement> VMConverter<T> {
         let params = s.instruction.params;
 
         // validate instruction links and add to machine links
-        input.links.extend(s.instruction.links.into_iter().map(|l| {
-            self.handle_instruction_link(s.source.clone(), &instruction_flag, &params, l)
-        }));
+        for link in s.instruction.links {
+            let link =
+                self.handle_instruction_link(s.source.clone(), &instruction_flag, &params, link)?;
+            input.links.push(link);
+        }
 
         // validate instruction body
         self.handle_instruction_body(
@@ -423,8 +535,9 @@ impl<T: FieldElement> VMConverter<T> {
             &instruction_flag,
             &params,
             s.instruction.body,
-        );
+        )?;
 
+        // The parameter types were validated with the body.
         let inputs: Vec<_> = params
             .inputs
             .into_iter()
@@ -439,16 +552,24 @@ impl<T: FieldElement> VMConverter<T> {
                     Some(Some("unsigned")) => {
                         Input::Literal(param.name, LiteralKind::UnsignedConstant)
                     }
-                    Some(_) => panic!("Invalid param type: {}", param.ty.as_ref().unwrap()),
+                    Some(_) => unreachable!(),
                     None => Input::Register(param.name),
                 }
             })
             .collect();
 
-        let outputs = params.outputs.into_iter().map(|param| param.name).collect();
+        let outputs = params
+            .outputs
+            .into_iter()
+            .map(|param| match param.ty {
+                Some(_) => Output::Lookup(lookup_output_column(&instruction_name, &param.name)),
+                None => Output::Register(param.name),
+            })
+            .collect();
 
         let instruction = Instruction { inputs, outputs };
         self.instructions.insert(instruction_name, instruction);
+        Ok(())
     }
 
     /// check parameters are valid and extend PIL from the definition
@@ -459,52 +580,76 @@ impl<T: FieldElement> VMConverter<T> {
         flag: &str,
         params: &InstructionParams,
         mut body: InstructionBody,
-    ) {
+    ) -> Result<(), String> {
         // check inputs are literals or assignment registers
         let mut literal_arg_names = vec![];
         for param in &params.inputs {
-            assert!(
-                param.index.is_none(),
-                "Cannot use array elements for instruction parameters."
-            );
+            if param.index.is_some() {
+                return Err("Cannot use array elements for instruction parameters.".to_string());
+            }
             match param
                 .ty
                 .as_ref()
                 .map(|ty| ty.try_to_identifier().map(|s| s.as_str()))
             {
                 Some(Some("label" | "signed" | "unsigned")) => literal_arg_names.push(&param.name),
-                Some(_) => panic!("Invalid param type: {}", param.ty.as_ref().unwrap()),
+                Some(_) => {
+                    return Err(format!(
+                        "Invalid param type: {}",
+                        param.ty.as_ref().unwrap()
+                    ))
+                }
                 None => {
                     if !self
                         .registers
                         .get(&param.name)
                         .is_some_and(|r| r.ty.is_assignment())
                     {
-                        panic!(
+                        return Err(format!(
                             "Register '{}' used for instruction input is not an assignment register.",
                             &param.name
-                        );
+                        ));
                     }
                 }
             }
         }
 
-        // check outputs are assignment registers
+        // check outputs are assignment registers or lookup outputs
+        let mut lookup_output_names = vec![];
         for param in &params.outputs {
-            assert!(
-                param.index.is_none(),
-                "Cannot use array elements for instruction outputs."
-            );
-            assert!(
-                param.ty.is_none() && self.registers[&param.name].ty.is_assignment(),
-                "Register '{}' used for instruction output is not an assignment register.",
-                &param.name
-            );
+            if param.index.is_some() {
+                return Err("Cannot use array elements for instruction outputs.".to_string());
+            }
+            match param
+                .ty
+                .as_ref()
+                .map(|ty| ty.try_to_identifier().map(|s| s.as_str()))
+            {
+                Some(Some("lookup")) => lookup_output_names.push(&param.name),
+                Some(_) => {
+                    return Err(format!(
+                        "Invalid output type: {}",
+                        param.ty.as_ref().unwrap()
+                    ))
+                }
+                None => {
+                    if !self
+                        .registers
+                        .get(&param.name)
+                        .is_some_and(|r| r.ty.is_assignment())
+                    {
+                        return Err(format!(
+                            "Register '{}' used for instruction output is not an assignment register.",
+                            &param.name
+                        ));
+                    }
+                }
+            }
         }
 
         // generate PIL from instruction body
 
-        let substitutions = literal_arg_names
+        let mut substitutions = literal_arg_names
             .into_iter()
             .map(|arg_name| {
                 let param_col_name = format!("instr_{name}_param_{arg_name}");
@@ -512,6 +657,48 @@ impl<T: FieldElement> VMConverter<T> {
                 (arg_name.clone(), param_col_name)
             })
             .collect::<HashMap<_, _>>();
+        for output_name in lookup_output_names {
+            let output_col_name = lookup_output_column(name, output_name);
+            self.pil
+                .push(witness_column(source.clone(), &output_col_name, None));
+            self.lookup_output_columns.push(output_col_name.clone());
+            substitutions.insert(output_name.clone(), output_col_name);
+        }
+
+        // Emit fixed columns declared in the body only once. If an identical column was
+        // already declared (by this or another instruction), refer to that one instead.
+        let mut statements = vec![];
+        for statement in std::mem::take(&mut body.0) {
+            let PilStatement::PolynomialConstantDefinition(_, col_name, definition) = &statement
+            else {
+                statements.push(statement);
+                continue;
+            };
+            match self
+                .fixed_column_definitions
+                .iter()
+                .find(|(_, existing)| existing == definition)
+            {
+                Some((existing_name, _)) => {
+                    substitutions.insert(col_name.clone(), existing_name.clone());
+                }
+                None => {
+                    if self
+                        .fixed_column_definitions
+                        .iter()
+                        .any(|(existing_name, _)| existing_name == col_name)
+                    {
+                        return Err(format!(
+                            "Conflicting definitions of fixed column `{col_name}` in instruction bodies."
+                        ));
+                    }
+                    self.fixed_column_definitions
+                        .push((col_name.clone(), definition.clone()));
+                    self.pil.push(statement.clone());
+                }
+            }
+        }
+        body.0 = statements;
         body.0.iter_mut().for_each(|s| {
             s.post_visit_expressions_mut(&mut |e| {
                 if let Expression::Reference(_, r) = e {
@@ -524,10 +711,51 @@ impl<T: FieldElement> VMConverter<T> {
             });
         });
 
-        let instr_flag = direct_reference(flag);
+        // Guards restrict the instruction to the rows where they are one, so we
+        // multiply them into the instruction flag used as the condition below.
+        // The product is stored in a witness column constrained to be boolean,
+        // which keeps the degree of the constraints at 2 for a single linear guard.
+        let mut guards = vec![];
+        body.0
+            .retain(|statement| match try_extract_guard(statement) {
+                Some(guard) => {
+                    guards.push(guard.clone());
+                    false
+                }
+                None => true,
+            });
+        let condition = if guards.is_empty() {
+            direct_reference(flag)
+        } else {
+            let condition_name = format!("{flag}_condition");
+            let product = guards
+                .into_iter()
+                .enumerate()
+                .map(|(i, guard)| self.linearize(&format!("{flag}_guard_{i}"), guard))
+                .fold(direct_reference(flag), |condition, guard| condition * guard);
+            let condition = direct_reference(&condition_name);
+            self.pil.extend([
+                witness_column(SourceRef::unknown(), condition_name, None),
+                PilStatement::Expression(
+                    SourceRef::unknown(),
+                    build::identity(condition.clone(), product),
+                ),
+                PilStatement::Expression(
+                    SourceRef::unknown(),
+                    build::identity(
+                        condition.clone() * (Expression::from(1) - condition.clone()),
+                        0.into(),
+                    ),
+                ),
+            ]);
+            self.boolean_conditions.push(condition.clone());
+            condition
+        };
         for statement in body.0 {
             let PilStatement::Expression(source, expr) = statement else {
-                panic!("Invalid statement for instruction body: {statement}");
+                return Err(format!(
+                    "Invalid statement for instruction body: {statement}"
+                ));
             };
             if let Some((var, expr)) = try_extract_update(&expr) {
                 // Try to reduce the update to linear by introducing intermediate variables.
@@ -539,18 +767,19 @@ impl<T: FieldElement> VMConverter<T> {
                     .get_mut(&var)
                     .unwrap()
                     .conditioned_updates
-                    .push((instr_flag.clone(), expr));
+                    .push((condition.clone(), expr));
             } else {
                 let fun_call = Expression::FunctionCall(
                     source.clone(),
                     FunctionCall {
                         function: absolute_reference("::std::constraints::make_conditional").into(),
-                        arguments: vec![expr, instr_flag.clone()],
+                        arguments: vec![expr, condition.clone()],
                     },
                 );
                 self.pil.push(PilStatement::Expression(source, fun_call))
             }
         }
+        Ok(())
     }
 
     /// validade instruction link params and transform it into a link definition
@@ -560,7 +789,7 @@ impl<T: FieldElement> VMConverter<T> {
         instr_flag: &str,
         instr_params: &InstructionParams,
         link_decl: LinkDeclaration,
-    ) -> LinkDefinition {
+    ) -> Result<LinkDefinition, String> {
         let callable: CallableRef = link_decl.link;
         let lhs = instr_params;
         let rhs = &callable.params;
@@ -587,9 +816,7 @@ impl<T: FieldElement> VMConverter<T> {
                     if let Expression::Reference(_, poly) = e.as_ref() {
                         poly.try_to_identifier()
                             .and_then(|name| self.registers.get(name).map(|reg| (name, reg)))
-                            .filter(|(_, reg)| {
-                                [RegisterTy::Write, RegisterTy::Pc].contains(&reg.ty)
-                            })
+                            .filter(|(_, reg)| reg.ty.is_write() || reg.ty.is_pc())
                             .map(|(name, _)| rhs_next_write_registers.insert(name.clone()));
                     }
                 }
@@ -600,10 +827,11 @@ impl<T: FieldElement> VMConverter<T> {
         // any assignment register present on the rhs (input or output) must be
         // present on the instruction params
         for name in &rhs_assignment_registers {
-            assert!(
-                lhs.inputs_and_outputs().any(|p_lhs| p_lhs.name == *name),
-                "Assignment register '{name}' used in link definition must be present in instruction params"
-            );
+            if !lhs.inputs_and_outputs().any(|p_lhs| p_lhs.name == *name) {
+                return Err(format!(
+                    "Assignment register '{name}' used in link definition must be present in instruction params"
+                ));
+            }
         }
 
         let instr_flag = direct_reference(instr_flag);
@@ -618,13 +846,13 @@ impl<T: FieldElement> VMConverter<T> {
             reg.conditioned_updates.push((flag.clone(), value));
         }
 
-        LinkDefinition {
+        Ok(LinkDefinition {
             source,
             instr_flag: Some(instr_flag),
             link_flag: link_decl.flag,
             to: callable,
             is_permutation: link_decl.is_permutation,
-        }
+        })
     }
 
     fn handle_non_functional_assignment(
@@ -632,20 +860,51 @@ impl<T: FieldElement> VMConverter<T> {
         _source: SourceRef,
         lhs_with_reg: Vec<(String, String)>,
         value: Expression,
-    ) -> CodeLine<T> {
-        assert!(
-            lhs_with_reg.len() == 1,
-            "Multi assignments are only implemented for function calls."
-        );
-        let (write_regs, assign_reg) = lhs_with_reg.into_iter().next().unwrap();
-        let value = self.process_assignment_value(value);
-        CodeLine {
-            write_regs: [(assign_reg.clone(), vec![write_regs])]
-                .into_iter()
-                .collect(),
-            value: [(assign_reg, value)].into(),
-            ..Default::default()
+    ) -> Result<CodeLine<T>, String> {
+        let value = self.process_assignment_value(value)?;
+        if lhs_with_reg.len() == 1 {
+            let (write_regs, assign_reg) = lhs_with_reg.into_iter().next().unwrap();
+            return Ok(CodeLine {
+                write_regs: [(assign_reg.clone(), vec![write_regs])]
+                    .into_iter()
+                    .collect(),
+                value: [(assign_reg, value)].into(),
+                ..Default::default()
+            });
+        }
+        // All assignment registers receive the same value, so the free input is
+        // evaluated only once and shared among them.
+        if value
+            .iter()
+            .filter(|(_, item)| matches!(item, AffineExpressionComponent::FreeInput(_)))
+            .count()
+            > 1
+        {
+            return Err("Multi assignments can read at most one free input.".to_string());
         }
+        let value = value
+            .into_iter()
+            .map(|(coeff, item)| match item {
+                AffineExpressionComponent::FreeInput(expr) => {
+                    (coeff, AffineExpressionComponent::SharedFreeInput(expr))
+                }
+                item => (coeff, item),
+            })
+            .collect::<Vec<_>>();
+        let mut code_line = CodeLine::default();
+        for (write_reg, assign_reg) in lhs_with_reg {
+            if code_line
+                .write_regs
+                .insert(assign_reg.clone(), vec![write_reg])
+                .is_some()
+            {
+                return Err(format!(
+                    "Assignment register {assign_reg} used more than once in multi assignment."
+                ));
+            }
+            code_line.value.insert(assign_reg, value.clone());
+        }
+        Ok(code_line)
     }
 
     fn handle_functional_instruction(
@@ -653,139 +912,175 @@ impl<T: FieldElement> VMConverter<T> {
         lhs_with_regs: Vec<(String, String)>,
         function: Expression,
         mut args: Vec<Expression>,
-    ) -> CodeLine<T> {
-        let Expression::Reference(_, reference) = function else {
-            panic!("Expected instruction name");
-        };
-        let instr_name = reference.try_to_identifier().unwrap();
-        let instr = &self
+    ) -> Result<CodeLine<T>, String> {
+        let instr_name = match &function {
+            Expression::Reference(_, reference) => reference.try_to_identifier(),
+            _ => None,
+        }
+        .ok_or_else(|| format!("Expected instruction name, got {function}"))?;
+        let instr = self
             .instructions
             .get(instr_name)
-            .unwrap_or_else(|| panic!("Instruction not found: {instr_name}"));
-        let output = instr.outputs.clone();
-
-        for (o, (_, r)) in output.iter().zip(lhs_with_regs.iter()) {
-            assert!(
-                o == r,
-                "The instruction {instr_name} uses the output register {o}, but the caller uses {r} to further process the value.",
-            );
+            .ok_or_else(|| format!("Instruction not found: {instr_name}"))?;
+        if instr.outputs.len() != lhs_with_regs.len() {
+            return Err(format!(
+                "The instruction {instr_name} has {} outputs, but the caller assigns {} registers.",
+                instr.outputs.len(),
+                lhs_with_regs.len()
+            ));
+        }
+        // The registers on the left hand side are matched to the outputs by position.
+        let mut lookup_outputs = vec![];
+        for (o, (_, r)) in instr.outputs.iter().zip(lhs_with_regs.iter()) {
+            match o {
+                Output::Register(o) if o != r => return Err(format!(
+                    "The instruction {instr_name} uses the output register {o}, but the caller uses {r} to further process the value."
+                )),
+                Output::Register(_) => {}
+                Output::Lookup(col) => lookup_outputs.push((col.clone(), r.clone())),
+            }
         }
 
         args.extend(lhs_with_regs.iter().map(|(lhs, _)| direct_reference(lhs)));
-        self.handle_instruction(instr_name.clone(), args)
+        let mut code_line = self.handle_instruction(instr_name.clone(), args)?;
+
+        // Lookup outputs are written through the caller's assignment register,
+        // which reads the value from the output column.
+        for (col, reg) in lookup_outputs {
+            let writes = code_line.write_regs.remove(&col).unwrap();
+            if code_line.value.contains_key(&reg) || code_line.write_regs.contains_key(&reg) {
+                return Err(format!(
+                    "The instruction {instr_name} already uses the assignment register {reg}, it cannot also be used for an output."
+                ));
+            }
+            code_line.write_regs.insert(reg.clone(), writes);
+            code_line.value.insert(
+                reg,
+                vec![(1.into(), AffineExpressionComponent::Register(col))],
+            );
+        }
+        Ok(code_line)
     }
 
-    fn handle_instruction(&mut self, instr_name: String, args: Vec<Expression>) -> CodeLine<T> {
-        let instr = &self
+    fn handle_instruction(
+        &mut self,
+        instr_name: String,
+        args: Vec<Expression>,
+    ) -> Result<CodeLine<T>, String> {
+        let instr = self
             .instructions
             .get(&instr_name)
-            .unwrap_or_else(|| panic!("Instruction not found: {instr_name}"));
-        assert_eq!(
-            instr.inputs.len() + instr.outputs.len(),
-            args.len(),
-            "Called instruction {instr_name} with the wrong number of arguments"
-        );
+            .ok_or_else(|| format!("Instruction not found: {instr_name}"))?;
+        if instr.inputs.len() + instr.outputs.len() != args.len() {
+            return Err(format!(
+                "Called instruction {instr_name} with the wrong number of arguments"
+            ));
+        }
 
         let mut args = args.into_iter();
 
-        let (value, instruction_literal_args): (BTreeMap<_, _>, Vec<_>) =
-            instr.inputs.iter().zip(&mut args).fold(
-                Default::default(),
-                |(mut value, mut instruction_literal_arg), (input, a)| {
-                    match input {
-                        Input::Register(reg) => {
-                            // We read a value into the assignment register "reg".
-                            assert!(!value.contains_key(reg));
-                            value.insert(reg.clone(), self.process_assignment_value(a));
-                        }
-                        Input::Literal(_, LiteralKind::Label) => {
-                            if let Expression::Reference(_, r) = a {
-                                instruction_literal_arg.push(InstructionLiteralArg::LabelRef(
-                                    r.try_to_identifier().unwrap().clone(),
-                                ));
-                            } else {
-                                panic!();
-                            }
-                        }
-                        Input::Literal(_, LiteralKind::UnsignedConstant) => {
-                            // TODO evaluate expression
-                            if let Expression::Number(_, Number {value, ..}) = a {
-                                let half_modulus = T::modulus().to_arbitrary_integer() / BigUint::from(2u64);
-                                assert!(value < half_modulus, "Number passed to unsigned parameter is negative or too large: {value}");
-                                instruction_literal_arg.push(InstructionLiteralArg::Number(
-                                    T::from(value),
-                                ));
-                            } else {
-                                panic!("expected unsigned number, received {a}");
-                            }
-                        }
-                        Input::Literal(_, LiteralKind::SignedConstant) => {
-                            // TODO evaluate expression
-                            if let Expression::Number(_, Number {value, ..}) = a {
-                                instruction_literal_arg.push(InstructionLiteralArg::Number(
-                                    T::checked_from(value).unwrap(),
-                                ));
-                            } else if let Expression::UnaryOperation(_, UnaryOperation { op: UnaryOperator::Minus, expr }) = a
-                            {
-                                if let Expression::Number(_, Number {value, ..}) = *expr {
-                                    instruction_literal_arg.push(InstructionLiteralArg::Number(
-                                        -T::checked_from(value).unwrap(),
-                                    ))
-                                } else {
-                                    panic!();
-                                }
-                            } else {
-                                panic!();
-                            }
-                        }
-                    };
-                    (value, instruction_literal_arg)
-                },
-            );
+        let mut value = BTreeMap::new();
+        let mut instruction_literal_args = vec![];
+        for (input, a) in instr.inputs.iter().zip(&mut args) {
+            match input {
+                Input::Register(reg) => {
+                    // We read a value into the assignment register "reg".
+                    if value.contains_key(reg) {
+                        return Err(format!(
+                            "Assignment register {reg} used more than once in call to {instr_name}"
+                        ));
+                    }
+                    value.insert(reg.clone(), self.process_assignment_value(a)?);
+                }
+                Input::Literal(_, LiteralKind::Label) => {
+                    let label = match &a {
+                        Expression::Reference(_, r) => r.try_to_identifier(),
+                        _ => None,
+                    }
+                    .ok_or_else(|| {
+                        format!("Expected a label as argument to {instr_name}, got {a}")
+                    })?;
+                    instruction_literal_args.push(InstructionLiteralArg::LabelRef(label.clone()));
+                }
+                Input::Literal(_, LiteralKind::UnsignedConstant) => {
+                    let value = evaluate_constant::<T>(&a, &self.constants)
+                        .map_err(|e| format!("Invalid argument {a} for unsigned parameter: {e}"))?;
+                    if !value.is_in_lower_half() {
+                        return Err(format!(
+                            "Number passed to unsigned parameter is negative or too large: {a} (its signed value is {})",
+                            value.to_signed_integer()
+                        ));
+                    }
+                    instruction_literal_args.push(InstructionLiteralArg::Number(value));
+                }
+                Input::Literal(_, LiteralKind::SignedConstant) => {
+                    let value = evaluate_constant::<T>(&a, &self.constants)
+                        .map_err(|e| format!("Invalid argument {a} for signed parameter: {e}"))?;
+                    instruction_literal_args.push(InstructionLiteralArg::Number(value));
+                }
+            };
+        }
 
-        let write_regs: BTreeMap<_, _> = instr
+        let write_regs = instr
             .outputs
             .iter()
             .zip(&mut args)
-            .map(|(reg, a)| {
-                // Output a value trough assignment register "reg"
-                if let Expression::Reference(_, r) = a {
-                    (reg.clone(), vec![r.try_to_identifier().unwrap().clone()])
-                } else {
-                    panic!("Expected direct register to assign to in instruction call.");
+            .map(|(output, a)| {
+                // Output a value trough assignment register "reg". For lookup outputs,
+                // the caller replaces the column name by its assignment register.
+                let reg = match output {
+                    Output::Register(reg) | Output::Lookup(reg) => reg,
+                };
+                match &a {
+                    Expression::Reference(_, r) => r.try_to_identifier(),
+                    _ => None,
                 }
+                .map(|r| (reg.clone(), vec![r.clone()]))
+                .ok_or_else(|| {
+                    format!("Expected direct register to assign to in instruction call, got {a}")
+                })
             })
-            .collect();
+            .collect::<Result<BTreeMap<_, _>, _>>()?;
 
         assert_eq!(write_regs.len(), instr.outputs.len());
 
-        CodeLine {
+        Ok(CodeLine {
             write_regs,
             instructions: vec![(instr_name.to_string(), instruction_literal_args)],
             value,
             ..Default::default()
-        }
+        })
     }
 
-    fn process_assignment_value(&self, value: Expression) -> Vec<(T, AffineExpressionComponent)> {
-        match value {
-            Expression::PublicReference(_, _) => panic!(),
-            Expression::IndexAccess(_, _) => panic!(),
-            Expression::FunctionCall(_, _) => panic!(),
-            Expression::Reference(_, reference) => {
+    /// Splits the value assigned to an assignment register into its affine components.
+    /// Supported are registers, publics, number literals, free inputs and sums, differences and
+    /// negations of these, as well as their products and powers with constants.
+    /// Free inputs can contain any expression, e.g. `${ match ... }`, which is evaluated by the
+    /// prover. All other expressions (such as match expressions outside of free inputs)
+    /// are not supported.
+    fn process_assignment_value(
+        &self,
+        value: Expression,
+    ) -> Result<Vec<(T, AffineExpressionComponent)>, String> {
+        Ok(match value {
+            Expression::PublicReference(_, name) => {
+                vec![(1.into(), AffineExpressionComponent::Public(name))]
+            }
+            Expression::Reference(_, ref reference) => {
                 // TODO check it actually is a register
-                let name = reference.try_to_identifier().unwrap();
+                let name = reference
+                    .try_to_identifier()
+                    .ok_or_else(|| format!("Expected a register, got {value}"))?;
                 vec![(1.into(), AffineExpressionComponent::Register(name.clone()))]
             }
             Expression::Number(_, Number { value, .. }) => {
                 vec![(T::from(value), AffineExpressionComponent::Constant)]
             }
-            Expression::String(_, _) => panic!(),
-            Expression::Tuple(_, _) => panic!(),
-            Expression::ArrayLiteral(_, _) => panic!(),
-            Expression::MatchExpression(_, _) => panic!(),
-            Expression::IfExpression(_, _) => panic!(),
-            Expression::BlockExpression(_, _) => panic!(),
+            Expression::MatchExpression(_, _) => {
+                return Err(format!(
+                "Match expressions in assignments are only supported inside free inputs: {value}"
+            ))
+            }
             Expression::FreeInput(_, expr) => {
                 vec![(1.into(), AffineExpressionComponent::FreeInput(*expr))]
             }
@@ -794,16 +1089,16 @@ impl<T: FieldElement> VMConverter<T> {
             }
             Expression::BinaryOperation(_, BinaryOperation { left, op, right }) => match op {
                 BinaryOperator::Add => self.add_assignment_value(
-                    self.process_assignment_value(*left),
-                    self.process_assignment_value(*right),
+                    self.process_assignment_value(*left)?,
+                    self.process_assignment_value(*right)?,
                 ),
                 BinaryOperator::Sub => self.add_assignment_value(
-                    self.process_assignment_value(*left),
-                    self.negate_assignment_value(self.process_assignment_value(*right)),
+                    self.process_assignment_value(*left)?,
+                    self.negate_assignment_value(self.process_assignment_value(*right)?),
                 ),
                 BinaryOperator::Mul => {
-                    let left = self.process_assignment_value(*left);
-                    let right = self.process_assignment_value(*right);
+                    let left = self.process_assignment_value(*left)?;
+                    let right = self.process_assignment_value(*right)?;
                     if let [(f, AffineExpressionComponent::Constant)] = &left[..] {
                         // TODO overflow?
                         right
@@ -816,12 +1111,12 @@ impl<T: FieldElement> VMConverter<T> {
                             .map(|(coeff, comp)| (*f * coeff, comp))
                             .collect()
                     } else {
-                        panic!("Multiplication by non-constant.");
+                        return Err("Multiplication by non-constant.".to_string());
                     }
                 }
                 BinaryOperator::Pow => {
-                    let left = self.process_assignment_value(*left);
-                    let right = self.process_assignment_value(*right);
+                    let left = self.process_assignment_value(*left)?;
+                    let right = self.process_assignment_value(*right)?;
                     if let (
                         [(l, AffineExpressionComponent::Constant)],
                         [(r, AffineExpressionComponent::Constant)],
@@ -829,11 +1124,11 @@ impl<T: FieldElement> VMConverter<T> {
                     {
                         // TODO overflow?
                         if r.to_arbitrary_integer() > (u32::MAX).into() {
-                            panic!("Exponent too large");
+                            return Err("Exponent too large".to_string());
                         }
                         vec![(l.pow(r.to_integer()), AffineExpressionComponent::Constant)]
                     } else {
-                        panic!("Exponentiation of non-constants.");
+                        return Err("Exponentiation of non-constants.".to_string());
                     }
                 }
                 BinaryOperator::Div
@@ -856,15 +1151,29 @@ impl<T: FieldElement> VMConverter<T> {
                 | BinaryOperator::In
                 | BinaryOperator::Select
                 | BinaryOperator::Connect => {
-                    panic!("Invalid operation in expression {left} {op} {right}")
+                    return Err(format!(
+                        "Invalid operation in expression {left} {op} {right}"
+                    ))
                 }
             },
             Expression::UnaryOperation(_, UnaryOperation { op, expr }) => {
-                assert!(op == UnaryOperator::Minus);
-                self.negate_assignment_value(self.process_assignment_value(*expr))
+                if op != UnaryOperator::Minus {
+                    return Err(format!("Invalid operation in expression {op}{expr}"));
+                }
+                self.negate_assignment_value(self.process_assignment_value(*expr)?)
             }
-            Expression::StructExpression(_, _) => panic!(),
-        }
+            Expression::IndexAccess(_, _)
+            | Expression::FunctionCall(_, _)
+            | Expression::String(_, _)
+            | Expression::Tuple(_, _)
+            | Expression::ArrayLiteral(_, _)
+            | Expression::IfExpression(_, _)
+            | Expression::BlockExpression(_, _)
+            | Expression::StructExpression(_, _)
+            | Expression::Cast(_, _) => {
+                return Err(format!("Unsupported expression in assignment: {value}"))
+            }
+        })
     }
 
     fn add_assignment_value(
@@ -872,8 +1181,22 @@ impl<T: FieldElement> VMConverter<T> {
         mut left: Vec<(T, AffineExpressionComponent)>,
         right: Vec<(T, AffineExpressionComponent)>,
     ) -> Vec<(T, AffineExpressionComponent)> {
-        // TODO combine (or at least check for) same components.
-        left.extend(right);
+        // Registers, publics and constants are combined so that each of them
+        // appears at most once, free inputs are kept separate.
+        for (coeff, component) in right {
+            let existing = match &component {
+                AffineExpressionComponent::Register(_)
+                | AffineExpressionComponent::Public(_)
+                | AffineExpressionComponent::Constant => {
+                    left.iter_mut().find(|(_, c)| *c == component)
+                }
+                _ => None,
+            };
+            match existing {
+                Some((existing_coeff, _)) => *existing_coeff += coeff,
+                None => left.push((coeff, component)),
+            }
+        }
         left
     }
 
@@ -884,40 +1207,120 @@ impl<T: FieldElement> VMConverter<T> {
         expr.into_iter().map(|(v, c)| (-v, c)).collect()
     }
 
-    fn create_constraints_for_assignment_reg(&mut self, register: String) {
+    /// Returns the pairs of assignment register and register that are read
+    /// into the assignment register in at least one code line.
+    fn register_reads(&self) -> BTreeSet<(String, String)> {
+        self.code_lines
+            .iter()
+            .flat_map(|line| &line.value)
+            .flat_map(|(assign_reg, value)| {
+                value.iter().filter_map(move |(_, item)| match item {
+                    AffineExpressionComponent::Register(reg) => {
+                        Some((assign_reg.clone(), reg.clone()))
+                    }
+                    _ => None,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the pairs of assignment register and public that are read
+    /// into the assignment register in at least one code line.
+    fn public_reads(&self) -> BTreeSet<(String, String)> {
+        self.code_lines
+            .iter()
+            .flat_map(|line| &line.value)
+            .flat_map(|(assign_reg, value)| {
+                value.iter().filter_map(move |(_, item)| match item {
+                    AffineExpressionComponent::Public(name) => {
+                        Some((assign_reg.clone(), name.clone()))
+                    }
+                    _ => None,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the assignment registers that read the shared free input
+    /// in at least one code line.
+    fn shared_free_input_reads(&self) -> BTreeSet<String> {
+        self.code_lines
+            .iter()
+            .flat_map(|line| &line.value)
+            .filter(|(_, value)| {
+                value
+                    .iter()
+                    .any(|(_, item)| matches!(item, AffineExpressionComponent::SharedFreeInput(_)))
+            })
+            .map(|(assign_reg, _)| assign_reg.clone())
+            .collect()
+    }
+
+    fn create_constraints_for_assignment_reg(
+        &mut self,
+        register: String,
+        register_reads: &BTreeSet<(String, String)>,
+        public_reads: &BTreeSet<(String, String)>,
+        reads_shared_free_input: bool,
+    ) {
         let assign_const = format!("{register}_const");
         self.create_witness_fixed_pair(SourceRef::unknown(), &assign_const);
         let read_free = format!("{register}_read_free");
         self.create_witness_fixed_pair(SourceRef::unknown(), &read_free);
         let free_value = format!("{register}_free_value");
-        // we can read from write registers, pc and read-only registers
+        // we can read from write registers, pc, read-only registers and lookup outputs
         let read_registers = self
             .write_register_names()
             .chain(self.pc_register_names())
             .chain(self.read_only_register_names())
+            .chain(self.lookup_output_columns.iter())
+            .filter(|name| register_reads.contains(&(register.clone(), name.to_string())))
             .cloned()
             .collect::<Vec<_>>();
-        let assign_constraint: Expression = read_registers
+        let read_terms = read_registers
             .iter()
             .map(|name| {
                 let read_coefficient = format!("read_{register}_{name}");
                 self.create_witness_fixed_pair(SourceRef::unknown(), &read_coefficient);
                 direct_reference(read_coefficient) * direct_reference(name.clone())
             })
+            .collect::<Vec<_>>();
+        let shared_free_term = reads_shared_free_input.then(|| {
+            let read_shared_free = format!("{register}_read_shared_free");
+            self.create_witness_fixed_pair(SourceRef::unknown(), &read_shared_free);
+            direct_reference(read_shared_free) * direct_reference(SHARED_FREE_VALUE)
+        });
+        let public_terms = public_reads
+            .iter()
+            .filter(|(assign_reg, _)| *assign_reg == register)
+            .map(|(_, name)| {
+                let read_coefficient = format!("read_{register}_public_{name}");
+                self.create_witness_fixed_pair(SourceRef::unknown(), &read_coefficient);
+                direct_reference(read_coefficient)
+                    * Expression::PublicReference(SourceRef::unknown(), name.clone())
+            })
+            .collect::<Vec<_>>();
+        let assign_constraint: Expression = read_terms
+            .into_iter()
             .chain([
                 direct_reference(assign_const),
                 direct_reference(read_free) * direct_reference(free_value),
             ])
+            .chain(shared_free_term)
+            .chain(public_terms)
             .sum();
         self.pil.push(PilStatement::Expression(
             SourceRef::unknown(),
-            build::identity(direct_reference(register), assign_constraint),
+            build::identity(
+                direct_reference(register),
+                simplify_arithmetic(assign_constraint),
+            ),
         ));
     }
 
     /// Translates the code lines to fixed column but also fills
     /// the query hints for the free inputs.
-    fn translate_code_lines(&mut self) {
+    fn translate_code_lines(&mut self) -> Result<(), String> {
         self.rom_pil
             .push(PilStatement::PolynomialConstantDefinition(
                 SourceRef::unknown(),
@@ -942,8 +1345,10 @@ impl<T: FieldElement> VMConverter<T> {
             .assignment_register_names()
             .map(|r| (r.clone(), vec![]))
             .collect::<BTreeMap<_, _>>();
+        // The free inputs shared by all assignment registers of a line, by line.
+        let mut shared_free_value_query_arms = BTreeMap::new();
 
-        let label_positions = self.compute_label_positions();
+        let label_positions = self.compute_label_positions()?;
         for (i, line) in self.code_lines.iter().enumerate() {
             for (assign_reg, writes) in &line.write_regs {
                 for reg in writes {
@@ -960,6 +1365,11 @@ impl<T: FieldElement> VMConverter<T> {
                                 .get_mut(&format!("p_read_{assign_reg}_{reg}"))
                                 .unwrap()[i] += *coeff;
                         }
+                        AffineExpressionComponent::Public(name) => {
+                            rom_constants
+                                .get_mut(&format!("p_read_{assign_reg}_public_{name}"))
+                                .unwrap()[i] += *coeff;
+                        }
                         AffineExpressionComponent::Constant => {
                             rom_constants
                                 .get_mut(&format!("p_{assign_reg}_const"))
@@ -979,6 +1389,17 @@ impl<T: FieldElement> VMConverter<T> {
                                     value: expr.clone(),
                                 });
                         }
+                        AffineExpressionComponent::SharedFreeInput(expr) => {
+                            rom_constants
+                                .get_mut(&format!("p_{assign_reg}_read_shared_free"))
+                                .unwrap()[i] += *coeff;
+                            shared_free_value_query_arms
+                                .entry(i)
+                                .or_insert_with(|| MatchArm {
+                                    pattern: Pattern::Number(SourceRef::unknown(), i.into()),
+                                    value: expr.clone(),
+                                });
+                        }
                     }
                 }
             }
@@ -1013,59 +1434,24 @@ impl<T: FieldElement> VMConverter<T> {
                 }
             }
         }
-        let pc_name = self.pc_name.clone();
-        let free_value_pil = self
+        let pc_name = self.pc_name.as_ref().unwrap();
+        let mut free_value_pil = self
             .assignment_register_names()
             .flat_map(|reg| {
-                let free_value = format!("{reg}_free_value");
-                let prover_query_arms = free_value_query_arms.remove(reg).unwrap();
-                let mut statements = vec![witness_column(
-                    SourceRef::unknown(),
-                    free_value.clone(),
-                    None,
-                )];
-                if !prover_query_arms.is_empty() {
-                    let mut prover_query_arms = prover_query_arms;
-                    prover_query_arms.push(MatchArm {
-                        pattern: Pattern::CatchAll(SourceRef::unknown()),
-                        value: absolute_reference("::std::prelude::Query::None"),
-                    });
-
-                    let scrutinee = Box::new(
-                        FunctionCall {
-                            function: Box::new(absolute_reference("::std::prover::eval")),
-                            arguments: vec![direct_reference(pc_name.as_ref().unwrap())],
-                        }
-                        .into(),
-                    );
-
-                    let call_to_handle_query = FunctionCall {
-                        function: Box::new(absolute_reference("::std::prover::handle_query")),
-                        arguments: vec![
-                            direct_reference(&free_value),
-                            direct_reference("__i"),
-                            MatchExpression {
-                                scrutinee,
-                                arms: prover_query_arms,
-                            }
-                            .into(),
-                        ],
-                    };
-                    let prover_function = LambdaExpression {
-                        kind: FunctionKind::Query,
-                        params: vec![Pattern::Variable(SourceRef::unknown(), "__i".to_string())],
-                        body: Box::new(call_to_handle_query.into()),
-                        param_types: vec![],
-                    };
-
-                    statements.push(PilStatement::Expression(
-                        SourceRef::unknown(),
-                        prover_function.into(),
-                    ));
-                }
-                statements
+                free_value_statements(
+                    pc_name,
+                    &format!("{reg}_free_value"),
+                    free_value_query_arms.remove(reg).unwrap(),
+                )
             })
             .collect::<Vec<_>>();
+        if !shared_free_value_query_arms.is_empty() {
+            free_value_pil.extend(free_value_statements(
+                pc_name,
+                SHARED_FREE_VALUE,
+                shared_free_value_query_arms.into_values().collect(),
+            ));
+        }
         self.pil.extend(free_value_pil);
         for (name, values) in rom_constants {
             let array_expression = if values.iter().all(|v| v == &values[0]) {
@@ -1090,16 +1476,19 @@ impl<T: FieldElement> VMConverter<T> {
                     FunctionDefinition::Array(array_expression),
                 ));
         }
+        Ok(())
     }
 
-    fn compute_label_positions(&self) -> HashMap<String, usize> {
+    fn compute_label_positions(&self) -> Result<HashMap<String, usize>, String> {
         self.code_lines
             .iter()
             .enumerate()
             .flat_map(|(i, line)| line.labels.iter().map(|l| (l, i)).collect::<Vec<_>>())
-            .fold(HashMap::new(), |mut r, (l, i)| {
-                assert!(r.insert(l.clone(), i).is_none(), "Duplicate label: {l}");
-                r
+            .try_fold(HashMap::new(), |mut r, (l, i)| {
+                match r.insert(l.clone(), i) {
+                    None => Ok(r),
+                    Some(_) => Err(format!("Duplicate label: {l}")),
+                }
             })
     }
 
@@ -1194,7 +1583,10 @@ impl<T: FieldElement> VMConverter<T> {
 struct Register {
     /// Constraints to update this register, first item being the
     /// condition, second item the value.
-    /// TODO check that condition is bool
+    /// The condition is an instruction flag or, for guarded instructions,
+    /// a column holding the product of the flag and the guards.
+    /// The conditions are constrained to be boolean and mutually exclusive,
+    /// see [Register::condition_constraints].
     conditioned_updates: Vec<(Expression, Expression)>,
     default_update: Option<Expression>,
     ty: RegisterTy,
@@ -1203,18 +1595,20 @@ struct Register {
 impl Register {
     /// Returns the expression assigned to this register in the next row.
     pub fn update_expression(&self) -> Option<Expression> {
-        // TODO conditions need to be all boolean
         let updates = self
             .conditioned_updates
             .iter()
             .map(|(cond, value)| cond.clone() * value.clone())
             .sum();
 
-        // TODO for computing the default condition, we need to ensure
-        // that the conditions all exclude each other
+        // The default condition relies on the conditions excluding each other,
+        // which is enforced by `condition_constraints`.
+        // Since the conditions include the guards of their instruction, the
+        // default update also applies when an instruction is not executed
+        // because of its guard.
         match (self.conditioned_updates.len(), &self.default_update) {
             (0, update) => update.clone(),
-            (_, None) => Some(updates),
+            (_, None) => Some(simplify_arithmetic(updates)),
             (_, Some(def)) => {
                 let default_condition = Expression::from(1)
                     - self
@@ -1222,15 +1616,42 @@ impl Register {
                         .iter()
                         .map(|(cond, _value)| cond.clone())
                         .sum();
-                Some(updates + (default_condition * def.clone()))
+                Some(simplify_arithmetic(
+                    updates + (default_condition * def.clone()),
+                ))
+            }
+        }
+    }
+
+    /// Returns the constraints enforcing that each condition of the updates is
+    /// boolean and that at most one of them is active in any row.
+    /// Conditions in `boolean_conditions` are not constrained again, the newly
+    /// constrained ones are added to it.
+    fn condition_constraints(&self, boolean_conditions: &mut Vec<Expression>) -> Vec<Expression> {
+        let is_boolean =
+            |e: Expression| build::identity(e.clone() * (Expression::from(1) - e), 0.into());
+        let conditions = self
+            .conditioned_updates
+            .iter()
+            .map(|(cond, _value)| cond.clone())
+            .collect::<Vec<_>>();
+        let mut constraints = vec![];
+        for cond in &conditions {
+            if !boolean_conditions.contains(cond) {
+                boolean_conditions.push(cond.clone());
+                constraints.push(is_boolean(cond.clone()));
             }
         }
+        if conditions.len() > 1 {
+            constraints.push(is_boolean(conditions.into_iter().sum()));
+        }
+        constraints
     }
 }
 
 struct Instruction {
     inputs: Vec<Input>,
-    outputs: Vec<String>,
+    outputs: Vec<Output>,
 }
 
 impl Instruction {
@@ -1256,10 +1677,23 @@ struct CodeLine<T> {
     debug_directives: Vec<DebugDirective>,
 }
 
+impl<T> CodeLine<T> {
+    /// Returns true iff this line does not do anything apart from defining labels
+    /// and debug directives.
+    fn is_only_labels_and_directives(&self) -> bool {
+        self.write_regs.is_empty() && self.value.is_empty() && self.instructions.is_empty()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 enum AffineExpressionComponent {
     Register(String),
+    /// The value of the public with the given name.
+    Public(String),
     Constant,
     FreeInput(Expression),
+    /// A free input shared by all assignment registers of the code line.
+    SharedFreeInput(Expression),
 }
 
 enum InstructionLiteralArg<T> {
@@ -1267,26 +1701,195 @@ enum InstructionLiteralArg<T> {
     Number(T),
 }
 
-fn witness_column<S: Into<String>>(
-    source: SourceRef,
-    name: S,
-    def: Option<FunctionDefinition>,
-) -> PilStatement {
-    PilStatement::PolynomialCommitDeclaration(
-        source,
-        None,
-        vec![PolynomialName {
-            name: name.into(),
-            array_size: None,
-        }],
-        def,
-    )
-}
+/// Declares the witness column `free_value` and, if there are any query arms,
+/// a prover function providing its value by matching on the pc.
+fn free_value_statements(
+    pc_name: &str,
+    free_value: &str,
+    mut prover_query_arms: Vec<MatchArm>,
+) -> Vec<PilStatement> {
+    let mut statements = vec![witness_column(SourceRef::unknown(), free_value, None)];
+    if !prover_query_arms.is_empty() {
+        prover_query_arms.push(MatchArm {
+            pattern: Pattern::CatchAll(SourceRef::unknown()),
+            value: absolute_reference("::std::prelude::Query::None"),
+        });
 
-/// If the expression is of the form "x' = expr", returns x and expr.
-fn try_extract_update(expr: &Expression) -> Option<(String, Expression)> {
-    let Expression::BinaryOperation(
-        _,
+        let scrutinee = Box::new(
+            FunctionCall {
+                function: Box::new(absolute_reference("::std::prover::eval")),
+                arguments: vec![direct_reference(pc_name)],
+            }
+            .into(),
+        );
+
+        let call_to_handle_query = FunctionCall {
+            function: Box::new(absolute_reference("::std::prover::handle_query")),
+            arguments: vec![
+                direct_reference(free_value),
+                direct_reference("__i"),
+                MatchExpression {
+                    scrutinee,
+                    arms: prover_query_arms,
+                }
+                .into(),
+            ],
+        };
+        let prover_function = LambdaExpression {
+            kind: FunctionKind::Query,
+            params: vec![Pattern::Variable(SourceRef::unknown(), "__i".to_string())],
+            param_defaults: vec![],
+            body: Box::new(call_to_handle_query.into()),
+            param_types: vec![],
+        };
+
+        statements.push(PilStatement::Expression(
+            SourceRef::unknown(),
+            prover_function.into(),
+        ));
+    }
+    statements
+}
+
+/// Evaluates an expression consisting only of numbers, arithmetic operators
+/// and references to `constants` to a field element.
+fn evaluate_constant<T: FieldElement>(
+    expr: &Expression,
+    constants: &BTreeMap<String, Expression>,
+) -> Result<T, String> {
+    match expr {
+        Expression::Number(_, Number { value, .. }) => T::checked_from(value.clone())
+            .ok_or_else(|| format!("Number {value} does not fit into the field.")),
+        Expression::UnaryOperation(
+            _,
+            UnaryOperation {
+                op: UnaryOperator::Minus,
+                expr,
+            },
+        ) => Ok(-evaluate_constant::<T>(expr, constants)?),
+        Expression::BinaryOperation(_, BinaryOperation { left, op, right }) => {
+            let left = evaluate_constant::<T>(left, constants)?;
+            let right = evaluate_constant::<T>(right, constants)?;
+            match op {
+                BinaryOperator::Add => Ok(left + right),
+                BinaryOperator::Sub => Ok(left - right),
+                BinaryOperator::Mul => Ok(left * right),
+                BinaryOperator::Pow => {
+                    if right.to_arbitrary_integer() > u32::MAX.into() {
+                        return Err(format!("Exponent too large: {right}"));
+                    }
+                    Ok(left.pow(right.to_integer()))
+                }
+                _ => Err(format!(
+                    "Operator {op} is not supported in constant expressions."
+                )),
+            }
+        }
+        Expression::Reference(_, reference) => {
+            match reference
+                .try_to_identifier()
+                .and_then(|name| constants.get_key_value(name))
+            {
+                Some((name, value)) => {
+                    // Remove the constant while evaluating its value to reject cyclic definitions.
+                    let mut constants = constants.clone();
+                    constants.remove(name);
+                    evaluate_constant::<T>(value, &constants)
+                }
+                None => Err(format!("Expected a constant expression, but found {expr}.")),
+            }
+        }
+        _ => Err(format!("Expected a constant expression, but found {expr}.")),
+    }
+}
+
+/// Removes additions of zero and multiplications by zero or one from sums, differences
+/// and products, without reordering any operands.
+fn simplify_arithmetic(expr: Expression) -> Expression {
+    let is_number = |e: &Expression, n: u32| match e {
+        Expression::Number(_, Number { value, .. }) => *value == BigUint::from(n),
+        _ => false,
+    };
+    match expr {
+        Expression::BinaryOperation(source, BinaryOperation { left, op, right })
+            if matches!(
+                op,
+                BinaryOperator::Add | BinaryOperator::Sub | BinaryOperator::Mul
+            ) =>
+        {
+            let left = simplify_arithmetic(*left);
+            let right = simplify_arithmetic(*right);
+            match op {
+                BinaryOperator::Add if is_number(&left, 0) => right,
+                BinaryOperator::Add if is_number(&right, 0) => left,
+                BinaryOperator::Mul if is_number(&left, 0) => left,
+                BinaryOperator::Mul if is_number(&right, 0) => right,
+                BinaryOperator::Mul if is_number(&left, 1) => right,
+                BinaryOperator::Mul if is_number(&right, 1) => left,
+                _ => Expression::BinaryOperation(
+                    source,
+                    BinaryOperation {
+                        left: Box::new(left),
+                        op,
+                        right: Box::new(right),
+                    },
+                ),
+            }
+        }
+        expr => expr,
+    }
+}
+
+/// The name of the witness column holding the lookup output `output` of instruction `instr`.
+fn lookup_output_column(instr: &str, output: &str) -> String {
+    format!("instr_{instr}_output_{output}")
+}
+
+fn witness_column<S: Into<String>>(
+    source: SourceRef,
+    name: S,
+    def: Option<FunctionDefinition>,
+) -> PilStatement {
+    PilStatement::PolynomialCommitDeclaration(
+        source,
+        None,
+        vec![PolynomialName {
+            name: name.into(),
+            array_size: None,
+        }],
+        def,
+    )
+}
+
+/// If the statement is a call to `std::constraints::guard`, returns the guard condition.
+fn try_extract_guard(statement: &PilStatement) -> Option<&Expression> {
+    let PilStatement::Expression(
+        _,
+        Expression::FunctionCall(
+            _,
+            FunctionCall {
+                function,
+                arguments,
+            },
+        ),
+    ) = statement
+    else {
+        return None;
+    };
+    match (function.as_ref(), &arguments[..]) {
+        (Expression::Reference(_, r), [condition])
+            if r.path.to_string() == "std::constraints::guard" =>
+        {
+            Some(condition)
+        }
+        _ => None,
+    }
+}
+
+/// If the expression is of the form "x' = expr", returns x and expr.
+fn try_extract_update(expr: &Expression) -> Option<(String, Expression)> {
+    let Expression::BinaryOperation(
+        _,
         BinaryOperation {
             left,
             op: BinaryOperator::Identity,
@@ -1319,20 +1922,50 @@ fn try_extract_update(expr: &Expression) -> Option<(String, Expression)> {
 mod test {
     use powdr_ast::asm_analysis::AnalysisASMFile;
     use powdr_importer::load_dependencies_and_resolve_str;
-    use powdr_number::{FieldElement, GoldilocksField};
+    use powdr_number::{FieldElement, GoldilocksField, LargeInt};
 
     use crate::compile;
 
+    use powdr_ast::{
+        asm_analysis::{AssignmentStatement, InstructionStatement},
+        parsed::asm::AssignmentRegister,
+    };
+    use powdr_parser_util::SourceRef;
+
+    use super::{
+        direct_reference, AffineExpressionComponent, Expression, FunctionCall, FunctionStatement,
+        Instruction, MatchArm, MatchExpression, Pattern, PilStatement, VMConverter,
+    };
+
     fn parse_analyze_and_compile<T: FieldElement>(input: &str) -> AnalysisASMFile {
         let parsed = load_dependencies_and_resolve_str(input);
         let analyzed = powdr_analysis::analyze(parsed).unwrap();
-        compile::<T>(analyzed)
+        compile::<T>(analyzed).unwrap()
+    }
+
+    fn parse_analyze_and_compile_err<T: FieldElement>(input: &str) -> Vec<String> {
+        let parsed = load_dependencies_and_resolve_str(input);
+        let analyzed = powdr_analysis::analyze(parsed).unwrap();
+        compile::<T>(analyzed).unwrap_err()
+    }
+
+    /// Returns the values of the ROM column `name` up to the repeated suffix.
+    fn rom_values(pil: &str, name: &str) -> Vec<u64> {
+        let prefix = format!("pol constant {name} = [");
+        let values = pil
+            .lines()
+            .find_map(|l| l.trim().strip_prefix(&prefix))
+            .unwrap_or_else(|| panic!("{name} not found"));
+        values
+            .split(']')
+            .next()
+            .unwrap()
+            .split(", ")
+            .map(|v| v.parse().unwrap())
+            .collect()
     }
 
     #[test]
-    #[should_panic(
-        expected = "Assignment register 'Y' used in link definition must be present in instruction params"
-    )]
     fn instr_external_rhs_register_not_on_lhs() {
         let asm = r"
 machine Main {
@@ -1348,6 +1981,696 @@ machine Main {
   }
 }
 ";
-        parse_analyze_and_compile::<GoldilocksField>(asm);
+        assert_eq!(
+            parse_analyze_and_compile_err::<GoldilocksField>(asm),
+            vec![
+                "Assignment register 'Y' used in link definition must be present in instruction params"
+                    .to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn convert_machine_without_pc() {
+        let asm = r"
+machine Table with latch: latch, operation_id: operation_id {
+    operation square<0> x -> y;
+
+    col fixed latch = [1]*;
+    col fixed operation_id = [0]*;
+    col fixed x(i) { i };
+    col fixed y(i) { i * i };
+}
+
+machine Main {
+    Table t;
+}
+";
+        let parsed = load_dependencies_and_resolve_str(asm);
+        let analyzed = powdr_analysis::analyze(parsed).unwrap();
+        let (_, table) = analyzed
+            .machines()
+            .find(|(path, _)| path.to_string() == "::Table")
+            .unwrap();
+        let (machine, rom) = crate::convert_machine::<GoldilocksField>(table.clone()).unwrap();
+        assert!(rom.is_none());
+        assert_eq!(machine.pil, table.pil);
+        let pil = machine
+            .pil
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert!(!pil.iter().any(|statement| statement.contains("p_line")));
+    }
+
+    #[test]
+    fn only_read_registers_get_read_columns() {
+        let asm = r"
+machine Main {
+  reg pc[@pc];
+  reg X[<=];
+  reg Y[<=];
+  reg A;
+  reg B;
+  reg C;
+  reg D;
+
+  function main {
+    A <=X= B;
+    C <=Y= D + 1;
+  }
+}
+";
+        let pil = parse_analyze_and_compile::<GoldilocksField>(asm).to_string();
+        assert!(pil.contains("X = read_X_B * B + X_const + X_read_free * X_free_value;"));
+        assert!(pil.contains("Y = read_Y_D * D + Y_const + Y_read_free * Y_free_value;"));
+        for unused in [
+            "read_X_A",
+            "read_X_C",
+            "read_X_D",
+            "read_X_pc",
+            "read_Y_A",
+            "read_Y_B",
+            "read_Y_C",
+            "read_Y_pc",
+        ] {
+            assert!(!pil.contains(unused), "{unused} should not be generated");
+        }
+    }
+
+    #[test]
+    fn shared_free_input() {
+        let asm = r"
+machine Main {
+  reg pc[@pc];
+  reg X[<=];
+  reg Y[<=];
+  reg A;
+  reg B;
+
+  function main {
+    A, B <=X, Y= ${ std::prelude::Query::Input(0, 1) };
+  }
+}
+";
+        let pil = parse_analyze_and_compile::<GoldilocksField>(asm).to_string();
+        assert!(pil.contains("pol commit _shared_free_value;"));
+        assert!(pil.contains(
+            "X = X_const + X_read_free * X_free_value + X_read_shared_free * _shared_free_value;"
+        ));
+        assert!(pil.contains(
+            "Y = Y_const + Y_read_free * Y_free_value + Y_read_shared_free * _shared_free_value;"
+        ));
+        // The free input is only queried once, for the shared column.
+        assert_eq!(pil.matches("Input(0, 1)").count(), 1);
+        assert!(pil.contains("handle_query(_shared_free_value, __i"));
+        assert!(!pil.contains("handle_query(X_free_value"));
+        assert!(!pil.contains("handle_query(Y_free_value"));
+    }
+
+    #[test]
+    fn public_in_assignment() {
+        let asm = r"
+machine Main {
+  reg pc[@pc];
+  reg X[<=];
+  reg A;
+
+  col witness w;
+  public N = w(0);
+
+  function main {
+    A <=X= :N + 1;
+    A <=X= 2 * :N - A;
+    return;
+  }
+}
+";
+        let pil = parse_analyze_and_compile::<GoldilocksField>(asm).to_string();
+        assert!(pil.contains("pol commit read_X_public_N;"));
+        assert!(pil.contains(
+            "X = read_X_A * A + X_const + X_read_free * X_free_value + read_X_public_N * :N;"
+        ));
+        let non_zero = |name| {
+            rom_values(&pil, name)
+                .into_iter()
+                .filter(|v| *v != 0)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(non_zero("p_read_X_public_N"), [1, 2]);
+        assert_eq!(non_zero("p_X_const"), [1]);
+    }
+
+    #[test]
+    fn match_in_free_input() {
+        let asm = r"
+machine Main {
+  reg pc[@pc];
+  reg X[<=];
+  reg A;
+
+  function main {
+    A <=X= ${ match std::prover::eval(A) { 0 => std::prelude::Query::Input(0, 1), _ => std::prelude::Query::Input(0, 2) } };
+    return;
+  }
+}
+";
+        let file = parse_analyze_and_compile::<GoldilocksField>(asm);
+        let (_, machine) = file
+            .machines()
+            .find(|(path, _)| path.to_string() == "::Main")
+            .unwrap();
+        // The query of `X_free_value` is a match on the pc, with an arm for the assignment.
+        let query_arms = machine
+            .pil
+            .iter()
+            .find_map(|statement| {
+                let PilStatement::Expression(_, Expression::LambdaExpression(_, query)) = statement
+                else {
+                    return None;
+                };
+                let Expression::FunctionCall(_, FunctionCall { arguments, .. }) =
+                    query.body.as_ref()
+                else {
+                    return None;
+                };
+                match &arguments[..] {
+                    [free_value, _, Expression::MatchExpression(_, MatchExpression { arms, .. })]
+                        if free_value.to_string() == "X_free_value" =>
+                    {
+                        Some(arms)
+                    }
+                    _ => None,
+                }
+            })
+            .unwrap();
+        let [MatchArm {
+            value: Expression::MatchExpression(_, MatchExpression { scrutinee, arms }),
+            ..
+        }, MatchArm {
+            pattern: Pattern::CatchAll(_),
+            ..
+        }] = &query_arms[..]
+        else {
+            panic!("Unexpected query arms: {query_arms:?}");
+        };
+        assert!(scrutinee.to_string().ends_with("prover::eval(A)"));
+        assert_eq!(arms.len(), 2);
+    }
+
+    #[test]
+    fn match_outside_free_input() {
+        let asm = r"
+machine Main {
+  reg pc[@pc];
+  reg X[<=];
+  reg A;
+
+  function main {
+    A <=X= match 1 { 1 => 2, _ => 3 };
+    return;
+  }
+}
+";
+        let errors = parse_analyze_and_compile_err::<GoldilocksField>(asm);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0]
+            .starts_with("Match expressions in assignments are only supported inside free inputs"));
+    }
+
+    #[test]
+    fn lookup_output_used_functionally() {
+        let asm = r"
+machine Main {
+  reg pc[@pc];
+  reg X[<=];
+  reg Y[<=];
+  reg A;
+  reg B;
+
+  col fixed INPUT(i) { i };
+  col fixed SQUARE(i) { i * i };
+
+  instr square X -> y: lookup { [X, y] in [INPUT, SQUARE] }
+
+  function main {
+    A <=Y= square(B);
+  }
+}
+";
+        let pil = parse_analyze_and_compile::<GoldilocksField>(asm).to_string();
+        assert!(pil.contains("pol commit instr_square_output_y;"));
+        assert!(pil.contains("[X, instr_square_output_y] in [INPUT, SQUARE]"));
+        assert!(pil.contains(
+            "Y = read_Y_instr_square_output_y * instr_square_output_y + Y_const + Y_read_free * Y_free_value;"
+        ));
+    }
+
+    #[test]
+    fn label_on_instruction_line() {
+        let asm = r"
+machine Main with degree: 8 {
+  reg pc[@pc];
+  reg X[<=];
+  reg A;
+
+  instr jmp l: label { pc' = l }
+
+  function main {
+    A <=X= 1;
+    loop: jmp loop;
+  }
+}
+";
+        let pil = parse_analyze_and_compile::<GoldilocksField>(asm).to_string();
+        let jmp_line = rom_values(&pil, "p_instr_jmp")
+            .iter()
+            .position(|v| *v == 1)
+            .unwrap();
+        assert_eq!(
+            rom_values(&pil, "p_instr_jmp_param_l")[jmp_line],
+            jmp_line as u64
+        );
+    }
+
+    #[test]
+    fn constant_expressions_as_literal_args() {
+        let asm = r"
+machine Main with degree: 8 {
+  reg pc[@pc];
+  reg X[<=];
+  reg A;
+
+  instr addu x: unsigned { A' = A + x }
+  instr adds x: signed { A' = A + x }
+
+  function main {
+    addu 2 + 3;
+    adds 2 - 3 * 4;
+  }
+}
+";
+        let pil = parse_analyze_and_compile::<GoldilocksField>(asm).to_string();
+        let line_of = |instr: &str| {
+            rom_values(&pil, &format!("p_instr_{instr}"))
+                .iter()
+                .position(|v| *v == 1)
+                .unwrap()
+        };
+        assert_eq!(rom_values(&pil, "p_instr_addu_param_x")[line_of("addu")], 5);
+        assert_eq!(
+            rom_values(&pil, "p_instr_adds_param_x")[line_of("adds")],
+            (-GoldilocksField::from(10u64))
+                .to_integer()
+                .try_into_u64()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn named_constants_as_literal_args() {
+        let asm = r"
+machine Main with degree: 8 {
+  reg pc[@pc];
+  reg X[<=];
+  reg A;
+
+  let WORD_SIZE: int = 4;
+  let DOUBLE_WORD_SIZE: int = 2 * WORD_SIZE;
+
+  instr addu x: unsigned { A' = A + x }
+
+  function main {
+    addu DOUBLE_WORD_SIZE + WORD_SIZE * 4;
+  }
+}
+";
+        let pil = parse_analyze_and_compile::<GoldilocksField>(asm).to_string();
+        let line = rom_values(&pil, "p_instr_addu")
+            .iter()
+            .position(|v| *v == 1)
+            .unwrap();
+        assert_eq!(rom_values(&pil, "p_instr_addu_param_x")[line], 24);
+    }
+
+    #[test]
+    fn non_constant_literal_arg() {
+        let asm = r"
+machine Main {
+  reg pc[@pc];
+  reg X[<=];
+  reg A;
+
+  instr addu x: unsigned { A' = A + x }
+
+  function main {
+    addu A + 1;
+  }
+}
+";
+        assert_eq!(
+            parse_analyze_and_compile_err::<GoldilocksField>(asm),
+            vec!["Invalid argument A + 1 for unsigned parameter: Expected a constant expression, but found A.".to_string()]
+        );
+    }
+
+    #[test]
+    fn signed_value_in_unsigned_error() {
+        let asm = r"
+machine Main {
+  reg pc[@pc];
+  reg X[<=];
+  reg A;
+
+  instr addu x: unsigned { A' = A + x }
+
+  function main {
+    addu 9223372034707292161;
+  }
+}
+";
+        assert_eq!(
+            parse_analyze_and_compile_err::<GoldilocksField>(asm),
+            vec!["Number passed to unsigned parameter is negative or too large: 9223372034707292161 (its signed value is -9223372034707292160)".to_string()]
+        );
+    }
+
+    #[test]
+    fn identical_fixed_columns_in_instruction_bodies() {
+        let asm = r"
+machine Main {
+  reg pc[@pc];
+  reg X[<=];
+
+  instr check_byte X { col fixed BYTE(i) { i & 0xff }, [X] in [BYTE] }
+  instr check_other_byte X { col fixed BYTE(i) { i & 0xff }, [X] in [BYTE] }
+  instr check_low X { col fixed LOW(i) { i & 0xff }, [X] in [LOW] }
+
+  function main {
+    check_byte 1;
+    check_other_byte 2;
+    check_low 3;
+  }
+}
+";
+        let pil = parse_analyze_and_compile::<GoldilocksField>(asm).to_string();
+        assert_eq!(pil.matches("pol constant BYTE(i)").count(), 1);
+        assert!(!pil.contains("pol constant LOW"));
+        assert_eq!(pil.matches("[X] in [BYTE]").count(), 3);
+    }
+
+    #[test]
+    fn conflicting_fixed_columns_in_instruction_bodies() {
+        let asm = r"
+machine Main {
+  reg pc[@pc];
+  reg X[<=];
+
+  instr check_byte X { col fixed BYTE(i) { i & 0xff }, [X] in [BYTE] }
+  instr check_nibble X { col fixed BYTE(i) { i & 0xf }, [X] in [BYTE] }
+
+  function main {
+    check_byte 1;
+    check_nibble 2;
+  }
+}
+";
+        assert_eq!(
+            parse_analyze_and_compile_err::<GoldilocksField>(asm),
+            vec![
+                "Conflicting definitions of fixed column `BYTE` in instruction bodies.".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn guarded_instructions() {
+        let asm = r"
+machine Main {
+  reg pc[@pc];
+  reg X[<=];
+  reg Y[<=];
+  reg A;
+
+  instr jmp_if X, l: label { std::constraints::guard(X), pc' = l }
+  instr assert_if X, Y { std::constraints::guard(X), Y = 7 }
+
+  function main {
+    A <=X= 1;
+    jmp_if A, end;
+    assert_if A, 7;
+    end:
+  }
+}
+";
+        let pil = parse_analyze_and_compile::<GoldilocksField>(asm).to_string();
+        assert!(pil.contains("instr_jmp_if_condition = instr_jmp_if * X;"));
+        assert!(pil.contains("instr_jmp_if_condition * instr_jmp_if_param_l"));
+        assert!(pil.contains("(1 - (instr_jmp_if_condition + "));
+        assert!(pil.contains("make_conditional(Y = 7, instr_assert_if_condition)"));
+        assert!(!pil.contains("guard(X)"));
+    }
+
+    #[test]
+    fn bool_register() {
+        let asm = r"
+machine Main {
+  reg pc[@pc];
+  reg X[<=];
+  reg F[@bool];
+  reg A;
+
+  function main {
+    F <=X= 1;
+    A <=X= F;
+  }
+}
+";
+        let pil = parse_analyze_and_compile::<GoldilocksField>(asm).to_string();
+        assert_eq!(pil.matches("F * (1 - F) = 0;").count(), 1);
+        assert!(pil.contains("reg_write_X_F"));
+    }
+
+    #[test]
+    fn conditioned_updates_are_boolean_and_exclusive() {
+        let asm = r"
+machine Main {
+  reg pc[@pc];
+  reg X[<=];
+  reg A;
+  reg B;
+
+  instr inc_if X { std::constraints::guard(X), A' = A + 1, B' = B + 1 }
+  instr inc_A { A' = A + 1 }
+
+  function main {
+    A <=X= 1;
+    inc_if A;
+    inc_A;
+  }
+}
+";
+        let pil = parse_analyze_and_compile::<GoldilocksField>(asm).to_string();
+        assert!(pil.contains("instr_inc_if_condition = instr_inc_if * X;"));
+        // Each condition is constrained to be boolean once, even if it is shared by
+        // the updates of several registers.
+        for condition in [
+            "reg_write_X_A",
+            "instr_inc_if_condition",
+            "instr_inc_A",
+            "instr__reset",
+        ] {
+            assert_eq!(
+                pil.matches(&format!("{condition} * (1 - {condition}) = 0;"))
+                    .count(),
+                1
+            );
+        }
+        // At most one of the conditions of a register is active.
+        assert!(pil.contains(
+            "(reg_write_X_A + instr_inc_if_condition + instr_inc_A + instr__reset) * (1 - (reg_write_X_A + instr_inc_if_condition + instr_inc_A + instr__reset)) = 0;"
+        ));
+        assert!(pil.contains(
+            "(instr_inc_if_condition + instr__reset) * (1 - (instr_inc_if_condition + instr__reset)) = 0;"
+        ));
+    }
+
+    #[test]
+    fn simplify_arithmetic() {
+        let [a, b, c, d] = ["a", "b", "c", "d"].map(direct_reference);
+        let expr =
+            a.clone() * 1.into() + b * 0.into() + 0.into() + (c.clone() + 0.into()) * d.clone()
+                - Expression::from(0);
+        // Subtractions are kept, and so is the order of the operands.
+        assert_eq!(
+            super::simplify_arithmetic(expr).to_string(),
+            "a + c * d - 0"
+        );
+
+        let asm = r"
+machine Main {
+  reg pc[@pc];
+  reg X[<=];
+  reg A;
+
+  function main {
+    A <=X= 1;
+    return;
+  }
+}
+";
+        let pil = parse_analyze_and_compile::<GoldilocksField>(asm).to_string();
+        assert!(!pil.contains("* 0"));
+        // The reset flag is still part of the default condition.
+        assert!(pil.contains("A' = reg_write_X_A * X + (1 - (reg_write_X_A + instr__reset)) * A;"));
+    }
+
+    #[test]
+    fn same_components_are_combined() {
+        let converter = VMConverter::<GoldilocksField>::with_output_count(0, "_reset".to_string());
+        let value = converter
+            .process_assignment_value(
+                direct_reference("A") + direct_reference("A") + Expression::from(1) + 2.into(),
+            )
+            .unwrap();
+        assert_eq!(
+            value,
+            vec![
+                (
+                    2.into(),
+                    AffineExpressionComponent::Register("A".to_string())
+                ),
+                (3.into(), AffineExpressionComponent::Constant),
+            ]
+        );
+    }
+
+    #[test]
+    fn multiple_outputs() {
+        let asm = r"
+machine Main with degree: 8 {
+  reg pc[@pc];
+  reg X[<=];
+  reg Y[<=];
+  reg Z[<=];
+  reg W[<=];
+  reg A;
+  reg B;
+  reg Q;
+  reg R;
+
+  instr divmod X, Y -> Z, W { X = Z * Y + W }
+
+  function main {
+    Q, R <== divmod(A, B);
+  }
+}
+";
+        let pil = parse_analyze_and_compile::<GoldilocksField>(asm).to_string();
+        let divmod_line = rom_values(&pil, "p_instr_divmod")
+            .iter()
+            .position(|v| *v == 1)
+            .unwrap();
+        assert_eq!(rom_values(&pil, "p_reg_write_Z_Q")[divmod_line], 1);
+        assert_eq!(rom_values(&pil, "p_reg_write_W_R")[divmod_line], 1);
+        assert!(!rom_values(&pil, "p_reg_write_Z_R").contains(&1));
+        assert!(!rom_values(&pil, "p_reg_write_W_Q").contains(&1));
+        assert_eq!(rom_values(&pil, "p_read_X_A")[divmod_line], 1);
+        assert_eq!(rom_values(&pil, "p_read_Y_B")[divmod_line], 1);
+    }
+
+    #[test]
+    fn conflicting_writes_in_batch() {
+        let assignment = |reg: &str, value: u32| {
+            FunctionStatement::Assignment(AssignmentStatement {
+                source: SourceRef::unknown(),
+                lhs_with_reg: vec![(
+                    reg.to_string(),
+                    AssignmentRegister::Register("X".to_string()),
+                )],
+                rhs: Box::new(value.into()),
+            })
+        };
+        let mut converter =
+            VMConverter::<GoldilocksField>::with_output_count(0, "_reset".to_string());
+        assert_eq!(
+            converter.handle_batch(vec![assignment("A", 1), assignment("B", 2)].into()),
+            Err(
+                "Conflicting writes through assignment register X in the same batch: B <=X= 2;"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn two_instructions_in_batch() {
+        let instruction = |name: &str| {
+            FunctionStatement::Instruction(InstructionStatement {
+                source: SourceRef::unknown(),
+                instruction: name.to_string(),
+                inputs: vec![],
+            })
+        };
+        let mut converter =
+            VMConverter::<GoldilocksField>::with_output_count(0, "_reset".to_string());
+        for name in ["foo", "bar"] {
+            converter.instructions.insert(
+                name.to_string(),
+                Instruction {
+                    inputs: vec![],
+                    outputs: vec![],
+                },
+            );
+        }
+        assert_eq!(
+            converter.handle_batch(vec![instruction("foo"), instruction("bar")].into()),
+            Err("Statement after an instruction in the same batch: bar;".to_string())
+        );
+    }
+
+    #[test]
+    fn invalid_param_type() {
+        let asm = r"
+machine Main {
+  reg pc[@pc];
+  reg A;
+
+  instr foo x: bar { A' = x }
+
+  function main {
+    foo 1;
+  }
+}
+";
+        assert_eq!(
+            parse_analyze_and_compile_err::<GoldilocksField>(asm),
+            vec!["Invalid param type: bar".to_string()]
+        );
+    }
+
+    #[test]
+    fn duplicate_labels() {
+        let asm = r"
+machine Main {
+  reg pc[@pc];
+  reg X[<=];
+  reg A;
+
+  instr jmp l: label { pc' = l }
+
+  function main {
+    start:
+    A <=X= 1;
+    start:
+    jmp start;
+  }
+}
+";
+        let parsed = load_dependencies_and_resolve_str(asm);
+        let analyzed = powdr_analysis::analyze(parsed).unwrap();
+        assert_eq!(
+            compile::<GoldilocksField>(analyzed).unwrap_err(),
+            vec!["Duplicate label: start".to_string()]
+        );
     }
 }
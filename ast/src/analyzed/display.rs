@@ -307,7 +307,9 @@ fn try_to_simple_expression(
 
 fn format_outer_function(e: &Expression, f: &mut Formatter<'_>) -> Result {
     match e {
-        parsed::Expression::LambdaExpression(_, lambda) if lambda.params.len() == 1 => {
+        parsed::Expression::LambdaExpression(_, lambda)
+            if lambda.params.len() == 1 && lambda.param_defaults.is_empty() =>
+        {
             let body = if lambda.kind == FunctionKind::Pure
                 && !matches!(lambda.body.as_ref(), Expression::BlockExpression(_, _))
             {
@@ -231,6 +231,7 @@ impl Display for RegisterTy {
             Self::Write => write!(f, ""),
             Self::ReadOnly => write!(f, "[@r]"),
             Self::Pc => write!(f, "[@pc]"),
+            Self::Bool => write!(f, "[@bool]"),
         }
     }
 }
@@ -37,11 +37,13 @@ pub enum RegisterTy {
     Assignment,
     Write,
     ReadOnly,
+    /// A write register which is constrained to be 0 or 1.
+    Bool,
 }
 
 impl RegisterTy {
     pub fn is_write(&self) -> bool {
-        self == &Self::Write
+        matches!(self, Self::Write | Self::Bool)
     }
 
     pub fn is_assignment(&self) -> bool {
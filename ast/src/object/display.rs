@@ -1,12 +1,14 @@
 use std::fmt::{Display, Formatter, Result};
 
+use itertools::Itertools;
+
 use crate::{asm_analysis::combine_flags, write_items_indented};
 
 use super::{Link, LinkFrom, LinkTo, Location, Machine, MachineInstanceGraph, Object, Operation};
 
 impl Display for Location {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        write!(f, "{}", self.limbs.join("_"))
+        write!(f, "{}", self.limbs.join("."))
     }
 }
 
@@ -33,6 +35,9 @@ impl Display for Object {
         for s in &self.pil {
             writeln!(f, "{s}")?;
         }
+        for (column, row) in &self.publics {
+            writeln!(f, "// Public {column}({row})")?;
+        }
         if !self.links.is_empty() {
             writeln!(f, "// Links:")?;
             for link in &self.links {
@@ -52,13 +57,26 @@ impl Display for Link {
 impl Display for LinkFrom {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         let flag = combine_flags(self.instr_flag.clone(), self.link_flag.clone());
-        write!(f, "{flag} {}", self.params)
+        write!(f, "{flag} {}", self.params)?;
+        if let Some(operation_id) = &self.operation_id {
+            write!(f, " with operation id {operation_id}")?;
+        }
+        Ok(())
     }
 }
 
 impl Display for LinkTo {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        write!(f, "{} in {}", self.operation, self.machine)
+        if self.alternative_operations.is_empty() {
+            write!(f, "{} in {}", self.operation, self.machine)
+        } else {
+            write!(
+                f,
+                "one of {} in {}",
+                self.operations().format(", "),
+                self.machine
+            )
+        }
     }
 }
 
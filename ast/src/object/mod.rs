@@ -1,4 +1,4 @@
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, iter::once, str::FromStr};
 
 use powdr_number::BigUint;
 
@@ -12,6 +12,10 @@ use crate::{
 
 mod display;
 
+/// The location of a machine instance in the machine hierarchy.
+/// It is displayed as its limbs joined by `.`, for example `main.vm.memory`,
+/// see [Location::from_str] for the inverse. The root location is displayed as
+/// the empty string.
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub struct Location {
     limbs: Vec<String>,
@@ -37,6 +41,55 @@ impl Location {
         self.limbs.push(limb.into());
         self
     }
+
+    /// Creates a location from its limbs, starting at the root.
+    pub fn from_parts(limbs: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            limbs: limbs.into_iter().collect(),
+        }
+    }
+
+    /// Returns the limbs of this location, starting at the root.
+    pub fn limbs(&self) -> impl Iterator<Item = &str> {
+        self.limbs.iter().map(String::as_str)
+    }
+
+    /// Returns the number of limbs of this location.
+    pub fn depth(&self) -> usize {
+        self.limbs.len()
+    }
+
+    /// Returns true if `other` is this location or one of its ancestors.
+    pub fn starts_with(&self, other: &Location) -> bool {
+        self.limbs.starts_with(&other.limbs)
+    }
+
+    /// Returns the name of the PIL namespace of the machine instance at this location,
+    /// which is its limbs joined by `_`, for example `main_vm_memory`.
+    pub fn namespace(&self) -> String {
+        self.limbs.join("_")
+    }
+}
+
+impl FromStr for Location {
+    type Err = String;
+
+    /// Parses a location in the format produced by its `Display` implementation.
+    fn from_str(s: &str) -> Result<Self, String> {
+        if s.is_empty() {
+            return Ok(Self::from_parts(vec![]));
+        }
+        s.split('.')
+            .map(|limb| {
+                if limb.is_empty() {
+                    Err(format!("Invalid location {s}: empty limb"))
+                } else {
+                    Ok(limb.to_string())
+                }
+            })
+            .collect::<Result<_, _>>()
+            .map(Self::from_parts)
+    }
 }
 
 #[derive(Clone)]
@@ -49,6 +102,65 @@ pub struct MachineInstanceGraph {
     pub statements: BTreeMap<AbsoluteSymbolPath, Vec<PilStatement>>,
 }
 
+impl MachineInstanceGraph {
+    /// Checks that the main machine and all machines called by links exist in
+    /// [MachineInstanceGraph::objects], and that called operations have an operation id
+    /// if and only if their machine has an operation id column.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut errors = vec![];
+        if !self.objects.contains_key(&self.main.location) {
+            errors.push(format!("Main machine {} not found", self.main.location));
+        }
+        errors.extend(operation_id_errors(
+            &self.main,
+            &self.entry_points,
+            "an entry point",
+        ));
+        for (location, object) in &self.objects {
+            for link in &object.links {
+                let target = &link.to.machine.location;
+                if !self.objects.contains_key(target) {
+                    errors.push(format!(
+                        "Machine {target} called by a link in machine {location} not found"
+                    ));
+                }
+                errors.extend(operation_id_errors(
+                    &link.to.machine,
+                    link.to.operations(),
+                    &format!("a link in machine {location}"),
+                ));
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Returns an error for each of the `operations` of `machine` which has an operation id
+/// although the machine does not have an operation id column, or the other way around.
+fn operation_id_errors<'a>(
+    machine: &'a Machine,
+    operations: impl IntoIterator<Item = &'a Operation> + 'a,
+    caller: &'a str,
+) -> impl Iterator<Item = String> + 'a {
+    operations.into_iter().filter_map(move |operation| {
+        match (&machine.operation_id, &operation.id) {
+            (Some(_), None) => Some(format!(
+                "Operation {} of machine {} called by {caller} has no operation id",
+                operation.name, machine.location
+            )),
+            (None, Some(_)) => Some(format!(
+                "Operation {} of machine {} called by {caller} has an operation id, but the machine has none",
+                operation.name, machine.location
+            )),
+            _ => None,
+        }
+    })
+}
+
 #[derive(Default, Clone)]
 pub struct Object {
     pub degree: MachineDegree,
@@ -62,6 +174,16 @@ pub struct Object {
     pub call_selectors: Option<String>,
     /// true if this machine has a PC
     pub has_pc: bool,
+    /// the columns exposed as public values, with the row of the exposed value
+    pub publics: Vec<(String, usize)>,
+}
+
+impl Object {
+    /// Exposes the values of the given columns at the given rows as publics.
+    pub fn with_publics(mut self, publics: impl IntoIterator<Item = (String, usize)>) -> Self {
+        self.publics.extend(publics);
+        self
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -83,6 +205,8 @@ pub struct LinkFrom {
     pub link_flag: Expression,
     /// lhs arguments of the link
     pub params: CallableParams,
+    /// the id of the called operation, if it is selected dynamically (see [LinkTo::alternative_operations])
+    pub operation_id: Option<Expression>,
 }
 
 #[derive(Clone, Debug, PartialOrd, Ord, Eq, PartialEq)]
@@ -93,6 +217,16 @@ pub struct LinkTo {
     pub operation: Operation,
     /// index into the permutation selector (None if lookup)
     pub selector_idx: Option<u64>,
+    /// further operations which can be called through this link if the operation id is
+    /// given by [LinkFrom::operation_id]. They have the same parameters as `operation`.
+    pub alternative_operations: Vec<Operation>,
+}
+
+impl LinkTo {
+    /// Returns all operations which can be called through this link.
+    pub fn operations(&self) -> impl Iterator<Item = &Operation> {
+        once(&self.operation).chain(&self.alternative_operations)
+    }
 }
 
 #[derive(Clone, Debug, PartialOrd, Ord, Eq, PartialEq)]
@@ -116,3 +250,165 @@ pub struct Operation {
     /// the parameters
     pub params: OperationParams,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn nested_locations() {
+        let location = Location::main().join("vm").join("memory");
+        assert_eq!(
+            location,
+            Location::from_parts(["main", "vm", "memory"].map(String::from))
+        );
+        assert_eq!(location.depth(), 3);
+        assert_eq!(
+            location.limbs().collect::<Vec<_>>(),
+            vec!["main", "vm", "memory"]
+        );
+        assert_eq!(location.parent().unwrap().depth(), 2);
+        assert_eq!(Location::from_parts(vec![]).depth(), 0);
+        assert_eq!(location.namespace(), "main_vm_memory");
+        assert_eq!(Location::main().join("_rom").namespace(), "main__rom");
+    }
+
+    #[test]
+    fn parse_locations() {
+        for location in [
+            Location::main(),
+            Location::main().join("foo"),
+            Location::main().join("vm").join("memory"),
+            Location::main().join("_rom"),
+            Location::main().join("vm").join("_rom"),
+            Location::main().join("byte_binary"),
+            Location::main().join("byte").join("binary"),
+            Location::main().join("no_degree").join("_rom"),
+            Location::from_parts(vec![]),
+        ] {
+            assert_eq!(Location::from_str(&location.to_string()), Ok(location));
+        }
+        assert_eq!(
+            Location::main().join("foo").to_string(),
+            "main.foo".to_string()
+        );
+        assert_eq!(Location::from_parts(vec![]).to_string(), "");
+        for invalid in ["main.", ".", "main..foo", ".main"] {
+            assert_eq!(
+                Location::from_str(invalid),
+                Err(format!("Invalid location {invalid}: empty limb"))
+            );
+        }
+    }
+
+    fn graph(objects: impl IntoIterator<Item = (Location, Object)>) -> MachineInstanceGraph {
+        MachineInstanceGraph {
+            main: Machine {
+                location: Location::main(),
+                latch: None,
+                call_selectors: None,
+                operation_id: None,
+            },
+            entry_points: vec![],
+            objects: objects.into_iter().collect(),
+            statements: Default::default(),
+        }
+    }
+
+    fn link_to(location: Location) -> Link {
+        Link {
+            from: LinkFrom {
+                instr_flag: None,
+                link_flag: 1u32.into(),
+                params: CallableParams::new(vec![], vec![]),
+                operation_id: None,
+            },
+            to: LinkTo {
+                machine: Machine {
+                    location,
+                    latch: Some("latch".to_string()),
+                    call_selectors: None,
+                    operation_id: Some("operation_id".to_string()),
+                },
+                operation: Operation {
+                    name: "get".to_string(),
+                    id: Some(1u32.into()),
+                    params: Default::default(),
+                },
+                selector_idx: None,
+                alternative_operations: vec![],
+            },
+            is_permutation: false,
+        }
+    }
+
+    #[test]
+    fn valid_graph() {
+        let sub = Location::main().join("sub");
+        let main = Object {
+            links: vec![link_to(sub.clone())],
+            ..Default::default()
+        };
+        let graph = graph([(Location::main(), main), (sub, Object::default())]);
+        assert_eq!(graph.validate(), Ok(()));
+    }
+
+    #[test]
+    fn dangling_link() {
+        let main = Object {
+            links: vec![link_to(Location::main().join("renamed"))],
+            ..Default::default()
+        };
+        let graph = graph([(Location::main(), main)]);
+        assert_eq!(
+            graph.validate(),
+            Err(vec![
+                "Machine main.renamed called by a link in machine main not found".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn missing_main() {
+        let graph = graph([(Location::main().join("sub"), Object::default())]);
+        assert_eq!(
+            graph.validate(),
+            Err(vec!["Main machine main not found".to_string()])
+        );
+    }
+
+    #[test]
+    fn missing_operation_id() {
+        let sub = Location::main().join("sub");
+        let mut link = link_to(sub.clone());
+        link.to.operation.id = None;
+        let main = Object {
+            links: vec![link],
+            ..Default::default()
+        };
+        let graph = graph([(Location::main(), main), (sub, Object::default())]);
+        assert_eq!(
+            graph.validate(),
+            Err(vec![
+                "Operation get of machine main.sub called by a link in machine main has no operation id"
+                    .to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn prefixes() {
+        let vm = Location::main().join("vm");
+        let memory = vm.clone().join("memory");
+        assert!(memory.starts_with(&vm));
+        assert!(memory.starts_with(&Location::main()));
+        assert!(memory.starts_with(&memory));
+        assert!(memory.starts_with(&Location::from_parts(vec![])));
+        assert!(!vm.starts_with(&memory));
+        assert!(!memory.starts_with(&Location::main().join("other")));
+        // Prefixes are checked limb by limb, not on the display name.
+        assert!(!Location::main()
+            .join("vm_memory")
+            .starts_with(&Location::main().join("vm")));
+    }
+}
@@ -759,6 +759,7 @@ pub enum RegisterFlag {
     IsPC,
     IsAssignment,
     IsReadOnly,
+    IsBool,
 }
 
 #[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Clone)]
@@ -141,7 +141,9 @@ impl Display for InstructionBody {
 }
 
 fn format_instruction_statement(stmt: &PilStatement) -> String {
-    if let PilStatement::Expression(_, _) = stmt {
+    if let PilStatement::Expression(_, _) | PilStatement::PolynomialConstantDefinition(_, _, _) =
+        stmt
+    {
         // statements inside instruction definition don't end in semicolon
         let mut s = format!("{stmt}");
         assert_eq!(s.pop(), Some(';'));
@@ -323,6 +325,7 @@ impl Display for RegisterFlag {
             RegisterFlag::IsPC => write!(f, "@pc"),
             RegisterFlag::IsAssignment => write!(f, "<="),
             RegisterFlag::IsReadOnly => write!(f, "@r"),
+            RegisterFlag::IsBool => write!(f, "@bool"),
         }
     }
 }
@@ -375,7 +378,8 @@ impl Display for Pattern {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         match self {
             Pattern::CatchAll(_) => write!(f, "_"),
-            Pattern::Ellipsis(_) => write!(f, ".."),
+            Pattern::Ellipsis(_, None) => write!(f, ".."),
+            Pattern::Ellipsis(_, Some(name)) => write!(f, "..{name}"),
             Pattern::Number(_, n) => write!(f, "{n}"),
             Pattern::String(_, s) => write!(f, "{}", quote(s)),
             Pattern::Tuple(_, t) => write!(f, "({})", t.iter().format(", ")),
@@ -541,7 +545,7 @@ impl Display for FunctionDefinition {
                 write!(f, " = {array_expression}")
             }
             FunctionDefinition::Expression(Expression::LambdaExpression(_, lambda))
-                if lambda.params.len() == 1 =>
+                if lambda.params.len() == 1 && lambda.param_defaults.is_empty() =>
             {
                 write!(
                     f,
@@ -740,6 +744,7 @@ impl<Ref: Display> Display for Expression<Ref> {
                 write!(f, "{block_expr}")
             }
             Expression::StructExpression(_, s) => write!(f, "{s}"),
+            Expression::Cast(_, cast) => write!(f, "{cast}"),
         }
     }
 }
@@ -789,7 +794,21 @@ where
             FunctionKind::Pure => "".into(),
             _ => format!("{} ", &self.kind),
         };
-        let params = format_list(&self.params);
+        let default_count = self.param_defaults.len();
+        let required_count = self.params.len() - default_count;
+        let params = self.params[..required_count]
+            .iter()
+            .map(|p| p.to_string())
+            .chain(
+                self.params[required_count..]
+                    .iter()
+                    .zip(&self.param_defaults)
+                    .map(|(p, d)| match d.precedence() {
+                        Some(_) => format!("{p} = ({d})"),
+                        None => format!("{p} = {d}"),
+                    }),
+            )
+            .join(", ");
 
         if self.body.precedence() < self.precedence() {
             write!(f, "{}|{}| {}", prefix, params, self.body)
@@ -923,6 +942,20 @@ where
     }
 }
 
+impl<E> Display for CastExpression<E>
+where
+    E: Display + Precedence,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self.expr.precedence() {
+            Some(inner_precedence) if inner_precedence > self.precedence().unwrap() => {
+                write!(f, "({}) as {}", self.expr, self.ty)
+            }
+            _ => write!(f, "{} as {}", self.expr, self.ty),
+        }
+    }
+}
+
 impl Display for UnaryOperator {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         write!(
@@ -1053,15 +1086,29 @@ pub fn format_type_scheme_around_name<E: Display, N: Display>(
 ) -> String {
     if let Some(type_scheme) = type_scheme {
         format!(
-            "{} {name}: {}",
+            "{} {name}: {}{}",
             type_scheme.type_vars_to_string(),
-            type_scheme.ty
+            type_scheme.ty,
+            where_clause_to_string(&type_scheme.vars)
         )
     } else {
         format!(" {name}")
     }
 }
 
+fn where_clause_to_string(type_vars: &TypeBounds) -> String {
+    let equalities = type_vars
+        .equalities()
+        .map(|(a, b)| format!("{a} = {b}"))
+        .format(", ")
+        .to_string();
+    if equalities.is_empty() {
+        Default::default()
+    } else {
+        format!(" where {equalities}")
+    }
+}
+
 pub fn type_vars_to_string(type_vars: &TypeBounds) -> String {
     if type_vars.is_empty() {
         Default::default()
@@ -590,6 +590,7 @@ pub enum Expression<Ref = NamespacedPolynomialReference> {
     IfExpression(SourceRef, IfExpression<Self>),
     BlockExpression(SourceRef, BlockExpression<Self>),
     StructExpression(SourceRef, StructExpression<Ref>),
+    Cast(SourceRef, CastExpression<Self>),
 }
 pub trait SourceReference {
     fn source_reference(&self) -> &SourceRef;
@@ -644,7 +645,8 @@ impl_source_reference!(
     MatchExpression,
     IfExpression,
     BlockExpression,
-    StructExpression
+    StructExpression,
+    Cast
 );
 
 impl_source_reference!(
@@ -687,6 +689,31 @@ impl<E> Children<E> for UnaryOperation<E> {
     }
 }
 
+/// A conversion of a value to a (primitive) type, e.g. `x as fe`.
+#[derive(
+    Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize, JsonSchema, Hash,
+)]
+pub struct CastExpression<E = Expression<NamespacedPolynomialReference>> {
+    pub expr: Box<E>,
+    pub ty: Type,
+}
+
+impl<Ref> From<CastExpression<Expression<Ref>>> for Expression<Ref> {
+    fn from(cast: CastExpression<Expression<Ref>>) -> Self {
+        Expression::Cast(SourceRef::unknown(), cast)
+    }
+}
+
+impl<E> Children<E> for CastExpression<E> {
+    fn children(&self) -> Box<dyn Iterator<Item = &E> + '_> {
+        Box::new(once(self.expr.as_ref()))
+    }
+
+    fn children_mut(&mut self) -> Box<dyn Iterator<Item = &mut E> + '_> {
+        Box::new(once(self.expr.as_mut()))
+    }
+}
+
 #[derive(
     Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize, JsonSchema, Hash,
 )]
@@ -899,6 +926,7 @@ impl<R> Expression<R> {
             Expression::IfExpression(_, if_expr) => if_expr.children(),
             Expression::BlockExpression(_, block_expr) => block_expr.children(),
             Expression::StructExpression(_, struct_expr) => struct_expr.children(),
+            Expression::Cast(_, cast) => cast.children(),
         }
     }
 
@@ -925,6 +953,7 @@ impl<R> Expression<R> {
             Expression::IfExpression(_, if_expr) => if_expr.children_mut(),
             Expression::BlockExpression(_, block_expr) => block_expr.children_mut(),
             Expression::StructExpression(_, struct_expr) => struct_expr.children_mut(),
+            Expression::Cast(_, cast) => cast.children_mut(),
         }
     }
 
@@ -997,6 +1026,9 @@ impl NamespacedPolynomialReference {
 pub struct LambdaExpression<E = Expression<NamespacedPolynomialReference>> {
     pub kind: FunctionKind,
     pub params: Vec<Pattern>,
+    /// Default values of the trailing parameters. If the lambda defines a symbol,
+    /// calls to the symbol can omit these parameters.
+    pub param_defaults: Vec<E>,
     pub body: Box<E>,
     /// Type of the parameters, filled in during type inference.
     pub param_types: Vec<Type>,
@@ -1010,11 +1042,15 @@ impl<Ref> From<LambdaExpression<Expression<Ref>>> for Expression<Ref> {
 
 impl<E> Children<E> for LambdaExpression<E> {
     fn children(&self) -> Box<dyn Iterator<Item = &E> + '_> {
-        Box::new(once(self.body.as_ref()))
+        Box::new(self.param_defaults.iter().chain(once(self.body.as_ref())))
     }
 
     fn children_mut(&mut self) -> Box<dyn Iterator<Item = &mut E> + '_> {
-        Box::new(once(self.body.as_mut()))
+        Box::new(
+            self.param_defaults
+                .iter_mut()
+                .chain(once(self.body.as_mut())),
+        )
     }
 }
 
@@ -1131,37 +1167,44 @@ impl Precedence for BinaryOperator {
             // Unary Oprators
             // **
             Pow => 3,
+            // as (see CastExpression)
             // * / %
-            Mul | Div | Mod => 4,
+            Mul | Div | Mod => 5,
             // + -
-            Add | Sub => 5,
+            Add | Sub => 6,
             // << >>
-            ShiftLeft | ShiftRight => 6,
+            ShiftLeft | ShiftRight => 7,
             // &
-            BinaryAnd => 7,
+            BinaryAnd => 8,
             // ^
-            BinaryXor => 8,
+            BinaryXor => 9,
             // |
-            BinaryOr => 9,
+            BinaryOr => 10,
             // = == != < > <= >=
-            Identity | Equal | NotEqual | Less | Greater | LessEqual | GreaterEqual => 10,
+            Identity | Equal | NotEqual | Less | Greater | LessEqual | GreaterEqual => 11,
             // &&
-            LogicalAnd => 11,
+            LogicalAnd => 12,
             // ||
-            LogicalOr => 12,
+            LogicalOr => 13,
             // $
-            Select => 14,
+            Select => 15,
             // in is connect
-            In | Is | Connect => 15,
+            In | Is | Connect => 16,
         };
 
         Some(precedence)
     }
 }
 
+impl<E> Precedence for CastExpression<E> {
+    fn precedence(&self) -> Option<ExpressionPrecedence> {
+        Some(4)
+    }
+}
+
 impl<E> Precedence for LambdaExpression<E> {
     fn precedence(&self) -> Option<ExpressionPrecedence> {
-        Some(13)
+        Some(14)
     }
 }
 
@@ -1171,6 +1214,7 @@ impl<E> Precedence for Expression<E> {
             Expression::UnaryOperation(_, operation) => operation.op.precedence(),
             Expression::BinaryOperation(_, operation) => operation.op.precedence(),
             Expression::LambdaExpression(_, lambda) => lambda.precedence(),
+            Expression::Cast(_, cast) => cast.precedence(),
             _ => None,
         }
     }
@@ -1568,7 +1612,9 @@ impl<Ref> Children<Expression<Ref>> for ArrayExpression<Ref> {
 )]
 pub enum Pattern {
     CatchAll(SourceRef), // "_", matches a single value
-    Ellipsis(SourceRef), // "..", matches a series of values, only valid inside array patterns
+    // "..", matches a series of values, only valid inside array patterns.
+    // "..rest" additionally binds the matched values as an array to "rest".
+    Ellipsis(SourceRef, Option<String>),
     #[schemars(skip)]
     Number(SourceRef, BigInt),
     String(SourceRef, String),
@@ -1585,7 +1631,7 @@ impl Pattern {
     /// Returns an iterator over all variables in this pattern.
     pub fn variables(&self) -> Box<dyn Iterator<Item = &String> + '_> {
         match self {
-            Pattern::Variable(_, v) => Box::new(once(v)),
+            Pattern::Variable(_, v) | Pattern::Ellipsis(_, Some(v)) => Box::new(once(v)),
             _ => Box::new(self.children().flat_map(|p| p.variables())),
         }
     }
@@ -1593,12 +1639,12 @@ impl Pattern {
     /// Return true if the pattern is irrefutable, i.e. matches all possible values of its type.
     pub fn is_irrefutable(&self) -> bool {
         match self {
-            Pattern::Ellipsis(_) => unreachable!(),
+            Pattern::Ellipsis(_, _) => unreachable!(),
             Pattern::CatchAll(_) | Pattern::Variable(_, _) => true,
             Pattern::Number(_, _) | Pattern::String(_, _) | Pattern::Enum(_, _, _) => false,
             Pattern::Array(_, items) => {
-                // Only "[..]"" is irrefutable
-                matches!(&items[..], [Pattern::Ellipsis(_)])
+                // Only "[..]"" (or "[..rest]") is irrefutable
+                matches!(&items[..], [Pattern::Ellipsis(_, _)])
             }
             Pattern::Tuple(_, p) => p.iter().all(|p| p.is_irrefutable()),
         }
@@ -1609,7 +1655,7 @@ impl Children<Pattern> for Pattern {
     fn children(&self) -> Box<dyn Iterator<Item = &Pattern> + '_> {
         match self {
             Pattern::CatchAll(_)
-            | Pattern::Ellipsis(_)
+            | Pattern::Ellipsis(_, _)
             | Pattern::Number(_, _)
             | Pattern::String(_, _)
             | Pattern::Variable(_, _) => Box::new(empty()),
@@ -1621,7 +1667,7 @@ impl Children<Pattern> for Pattern {
     fn children_mut(&mut self) -> Box<dyn Iterator<Item = &mut Pattern> + '_> {
         match self {
             Pattern::CatchAll(_)
-            | Pattern::Ellipsis(_)
+            | Pattern::Ellipsis(_, _)
             | Pattern::Number(_, _)
             | Pattern::String(_, _)
             | Pattern::Variable(_, _) => Box::new(empty()),
@@ -1635,7 +1681,7 @@ impl SourceReference for Pattern {
     fn source_reference(&self) -> &SourceRef {
         match self {
             Pattern::CatchAll(s)
-            | Pattern::Ellipsis(s)
+            | Pattern::Ellipsis(s, _)
             | Pattern::Number(s, _)
             | Pattern::String(s, _)
             | Pattern::Variable(s, _)
@@ -1647,7 +1693,7 @@ impl SourceReference for Pattern {
     fn source_reference_mut(&mut self) -> &mut SourceRef {
         match self {
             Pattern::CatchAll(s)
-            | Pattern::Ellipsis(s)
+            | Pattern::Ellipsis(s, _)
             | Pattern::Number(s, _)
             | Pattern::String(s, _)
             | Pattern::Variable(s, _)
@@ -132,9 +132,16 @@ impl<E> Type<E> {
         self.contained_type_vars_with_repetitions().unique()
     }
 
+    /// The empty tuple `()`, which is used as the unit type, e.g. as the return
+    /// type of functions that do not return anything meaningful.
     pub fn empty_tuple() -> Type<E> {
         Type::Tuple(TupleType { items: vec![] })
     }
+
+    /// Returns true if this is the unit type `()`, i.e. the empty tuple.
+    pub fn is_unit(&self) -> bool {
+        matches!(self, Type::Tuple(TupleType { items }) if items.is_empty())
+    }
 }
 
 impl<E: ExpressionInArrayLength> Type<E> {
@@ -462,10 +469,44 @@ impl<E: Clone> TypeScheme<E> {
                 self.vars
                     .bounds()
                     .map(|(v, b)| (name_substitutions[v].clone(), b.clone())),
+            )
+            .with_equalities(
+                self.vars
+                    .equalities()
+                    .map(|(a, b)| (name_substitutions[a].clone(), name_substitutions[b].clone())),
             ),
             ty,
         }
     }
+
+    /// Returns an equivalent type scheme without `where` clause, i.e. type variables
+    /// that are required to be equal are replaced by a single one.
+    pub fn apply_type_var_equalities(self) -> TypeScheme<E> {
+        if self.vars.equalities.is_empty() {
+            return self;
+        }
+        let representatives = self.vars.representatives();
+        let mut bounds: Vec<(String, BTreeSet<String>)> = vec![];
+        for (var, var_bounds) in self.vars.bounds() {
+            let representative = &representatives[var];
+            match bounds.iter_mut().find(|(v, _)| v == representative) {
+                Some((_, b)) => b.extend(var_bounds.iter().cloned()),
+                None => bounds.push((representative.clone(), var_bounds.clone())),
+            }
+        }
+        let mut ty = self.ty;
+        ty.substitute_type_vars(
+            &representatives
+                .into_iter()
+                .filter(|(v, r)| v != r)
+                .map(|(v, r)| (v, Type::TypeVar(r)))
+                .collect(),
+        );
+        TypeScheme {
+            vars: TypeBounds::new(bounds.into_iter()),
+            ty,
+        }
+    }
 }
 impl<E> TypeScheme<E> {
     pub fn type_vars_to_string(&self) -> String {
@@ -486,31 +527,87 @@ impl From<Type> for TypeScheme {
     Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Default, Serialize, Deserialize, JsonSchema, Hash,
 )]
 // TODO bounds should be SymbolPaths in the future.
-pub struct TypeBounds(Vec<(String, BTreeSet<String>)>);
+pub struct TypeBounds {
+    vars: Vec<(String, BTreeSet<String>)>,
+    /// Pairs of type variables that are required to be equal (`where A = B`).
+    equalities: Vec<(String, String)>,
+}
 
 impl TypeBounds {
     pub fn new<J: Into<BTreeSet<String>>, I: Iterator<Item = (String, J)>>(vars: I) -> Self {
-        Self(vars.map(|(n, x)| (n, x.into())).collect::<Vec<_>>())
+        Self {
+            vars: vars.map(|(n, x)| (n, x.into())).collect::<Vec<_>>(),
+            equalities: vec![],
+        }
+    }
+
+    /// Adds the requirement that the given pairs of type variables are equal.
+    pub fn with_equalities(
+        mut self,
+        equalities: impl IntoIterator<Item = (String, String)>,
+    ) -> Self {
+        self.equalities.extend(equalities);
+        self
+    }
+
+    /// Removes all type variables for which `keep` returns false, together with
+    /// the equalities they are part of.
+    pub fn retain_vars(&mut self, mut keep: impl FnMut(&String) -> bool) {
+        self.vars.retain(|(n, _)| keep(n));
+        let vars = self.vars().cloned().collect::<BTreeSet<_>>();
+        self.equalities
+            .retain(|(a, b)| vars.contains(a) && vars.contains(b));
     }
 
     pub fn is_empty(&self) -> bool {
-        self.0.is_empty()
+        self.vars.is_empty()
     }
 
     pub fn len(&self) -> usize {
-        self.0.len()
+        self.vars.len()
     }
 
     pub fn vars(&self) -> impl Iterator<Item = &String> {
-        self.0.iter().map(|(n, _)| n)
+        self.vars.iter().map(|(n, _)| n)
     }
 
     pub fn bounds(&self) -> impl Iterator<Item = (&String, &BTreeSet<String>)> {
-        self.0.iter().map(|(n, x)| (n, x))
+        self.vars.iter().map(|(n, x)| (n, x))
+    }
+
+    pub fn equalities(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.equalities.iter().map(|(a, b)| (a, b))
+    }
+
+    /// Returns, for each type variable, the first declared type variable it is
+    /// required to be equal to (which might be the variable itself).
+    pub fn representatives(&self) -> HashMap<String, String> {
+        let mut representatives: HashMap<String, String> =
+            self.vars().map(|v| (v.clone(), v.clone())).collect();
+        fn find<'a>(representatives: &'a HashMap<String, String>, mut v: &'a String) -> String {
+            while representatives[v] != *v {
+                v = &representatives[v];
+            }
+            v.clone()
+        }
+        for (a, b) in &self.equalities {
+            let (a, b) = (find(&representatives, a), find(&representatives, b));
+            let position = |v: &String| self.vars().position(|x| x == v).unwrap();
+            // Keep the variable declared first as representative.
+            let (representative, other) = if position(&a) <= position(&b) {
+                (a, b)
+            } else {
+                (b, a)
+            };
+            representatives.insert(other, representative);
+        }
+        self.vars()
+            .map(|v| (v.clone(), find(&representatives, v)))
+            .collect()
     }
 
     pub fn format_vars_with_nonempty_bounds(&self) -> String {
-        self.0
+        self.vars
             .iter()
             .filter(|(_, b)| !b.is_empty())
             .map(|(var, b)| Self::format_var_bound(var, b))
@@ -15,7 +15,7 @@ use powdr_ast::analyzed::Analyzed;
 use powdr_executor::{constant_evaluator::VariablySizedColumn, witgen::WitgenCallback};
 use powdr_number::{DegreeType, FieldElement};
 use std::{io, path::PathBuf, sync::Arc};
-use strum::{Display, EnumString, EnumVariantNames};
+use strum::{Display, EnumString, EnumVariantNames, VariantNames};
 
 #[derive(Clone, EnumString, EnumVariantNames, Display, Copy)]
 pub enum BackendType {
@@ -70,7 +70,56 @@ pub const DEFAULT_HALO2_OPTIONS: &str = "poseidon";
 pub const DEFAULT_HALO2_MOCK_OPTIONS: &str = "";
 pub const DEFAULT_ESTARK_OPTIONS: &str = "stark_gl";
 
+/// The names of all backends, including the ones not enabled in this build.
+/// Kept in sync with the variants of [BackendType] by a test.
+const ALL_BACKEND_NAMES: &[&str] = &[
+    "mock",
+    "halo2",
+    "halo2-composite",
+    "halo2-mock",
+    "halo2-mock-composite",
+    "estark-polygon",
+    "estark-polygon-composite",
+    "estark-starky",
+    "estark-starky-composite",
+    "estark-dump",
+    "estark-dump-composite",
+    "plonky3",
+    "plonky3-composite",
+    "stwo",
+    "stwo-composite",
+];
+
+/// The error returned when selecting a backend by name which cannot be used.
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum BackendUnavailable {
+    #[error("the backend {0} is not enabled in this build, the corresponding feature is required")]
+    NotEnabled(String),
+    #[error("unknown backend {0}")]
+    Unknown(String),
+}
+
 impl BackendType {
+    /// Returns all backends enabled in this build.
+    pub fn available() -> Vec<BackendType> {
+        Self::VARIANTS
+            .iter()
+            .map(|name| name.parse().unwrap())
+            .collect()
+    }
+
+    /// Selects the backend called `name`, failing if it is unknown or was not enabled
+    /// in this build.
+    pub fn from_name(name: &str) -> Result<BackendType, BackendUnavailable> {
+        name.parse().map_err(|_| {
+            if ALL_BACKEND_NAMES.contains(&name) {
+                BackendUnavailable::NotEnabled(name.to_string())
+            } else {
+                BackendUnavailable::Unknown(name.to_string())
+            }
+        })
+    }
+
     pub fn factory<T: FieldElement>(&self) -> Box<dyn BackendFactory<T>> {
         match self {
             BackendType::Mock => Box::new(mock::MockBackendFactory),
@@ -151,6 +200,8 @@ impl From<String> for Error {
     }
 }
 
+/// A proof in the serialization format of the backend that generated it. The bytes
+/// can be stored as they are and verified later by the same backend.
 pub type Proof = Vec<u8>;
 
 /*
@@ -230,3 +281,32 @@ pub trait Backend<F: FieldElement>: Send {
         Err(Error::NoEthereumVerifierAvailable)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use strum::VariantNames;
+
+    use super::{BackendType, BackendUnavailable, ALL_BACKEND_NAMES};
+
+    #[test]
+    fn all_backend_names() {
+        for name in BackendType::VARIANTS {
+            assert!(
+                ALL_BACKEND_NAMES.contains(name),
+                "Backend {name} is missing in ALL_BACKEND_NAMES"
+            );
+        }
+        #[cfg(all(
+            feature = "halo2",
+            feature = "estark-polygon",
+            feature = "estark-starky",
+            feature = "plonky3",
+            feature = "stwo"
+        ))]
+        assert_eq!(BackendType::VARIANTS, ALL_BACKEND_NAMES);
+        assert_eq!(
+            BackendType::from_name("foo").err(),
+            Some(BackendUnavailable::Unknown("foo".to_string()))
+        );
+    }
+}
@@ -696,6 +696,7 @@ fn run_pil<F: FieldElement>(
             .with_linker_params(LinkerParams {
                 mode: linker_mode.unwrap_or_default(),
                 degree_mode: degree_mode.unwrap_or_default(),
+                ..Default::default()
             }),
         inputs.clone(),
         PathBuf::from(output_directory),
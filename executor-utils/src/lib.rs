@@ -39,7 +39,7 @@ impl<T: FieldElement> WitgenCallback<T> {
     }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct VariablySizedColumn<F> {
     column_by_size: BTreeMap<DegreeType, Vec<F>>,
     /// If this is Some(x), then all sizes of this column have this value
@@ -14,8 +14,8 @@ use powdr_ast::parsed::{
     folder::Folder,
     types::{ExpressionInArrayLength, Type, TypeScheme},
     visitor::{Children, ExpressionVisitable},
-    ArrayLiteral, BinaryOperation, BlockExpression, EnumDeclaration, EnumVariant, Expression,
-    FunctionCall, IndexAccess, LambdaExpression, LetStatementInsideBlock, MatchArm,
+    ArrayLiteral, BinaryOperation, BlockExpression, CastExpression, EnumDeclaration, EnumVariant,
+    Expression, FunctionCall, IndexAccess, LambdaExpression, LetStatementInsideBlock, MatchArm,
     MatchExpression, NamedExpression, NamedType, Pattern, PilStatement, StatementInsideBlock,
     StructDeclaration, StructExpression, TraitDeclaration, UnaryOperation,
 };
@@ -181,15 +181,19 @@ fn free_inputs_in_expression<'a>(
             free_inputs_in_expression(function)
                 .chain(arguments.iter().flat_map(|e| free_inputs_in_expression(e))),
         ),
-        // These should really not appear in assembly statements.
-        Expression::Tuple(_, _) => todo!(),
-        Expression::LambdaExpression(_, _) => todo!(),
-        Expression::ArrayLiteral(_, _) => todo!(),
-        Expression::IndexAccess(_, _) => todo!(),
-        Expression::MatchExpression(_, _) => todo!(),
-        Expression::IfExpression(_, _) => todo!(),
-        Expression::BlockExpression(_, _) => todo!(),
-        Expression::StructExpression(_, _) => todo!(),
+        // These should really not appear in assembly statements, but they are
+        // rejected later with a proper error.
+        Expression::Tuple(_, _)
+        | Expression::LambdaExpression(_, _)
+        | Expression::ArrayLiteral(_, _)
+        | Expression::IndexAccess(_, _)
+        | Expression::MatchExpression(_, _)
+        | Expression::IfExpression(_, _)
+        | Expression::BlockExpression(_, _)
+        | Expression::StructExpression(_, _)
+        | Expression::Cast(_, _) => {
+            Box::new(expr.children().flat_map(|e| free_inputs_in_expression(e)))
+        }
     }
 }
 
@@ -222,15 +226,20 @@ fn free_inputs_in_expression_mut<'a>(
                     .flat_map(|e| free_inputs_in_expression_mut(e)),
             ),
         ),
-        // These should really not appear in assembly statements.
-        Expression::Tuple(_, _) => todo!(),
-        Expression::LambdaExpression(_, _) => todo!(),
-        Expression::ArrayLiteral(_, _) => todo!(),
-        Expression::IndexAccess(_, _) => todo!(),
-        Expression::MatchExpression(_, _) => todo!(),
-        Expression::IfExpression(_, _) => todo!(),
-        Expression::BlockExpression(_, _) => todo!(),
-        Expression::StructExpression(_, _) => todo!(),
+        // These should really not appear in assembly statements, but they are
+        // rejected later with a proper error.
+        Expression::Tuple(_, _)
+        | Expression::LambdaExpression(_, _)
+        | Expression::ArrayLiteral(_, _)
+        | Expression::IndexAccess(_, _)
+        | Expression::MatchExpression(_, _)
+        | Expression::IfExpression(_, _)
+        | Expression::BlockExpression(_, _)
+        | Expression::StructExpression(_, _)
+        | Expression::Cast(_, _) => Box::new(
+            expr.children_mut()
+                .flat_map(|e| free_inputs_in_expression_mut(e)),
+        ),
     }
 }
 
@@ -804,6 +813,15 @@ fn check_machine(
                         .inputs_and_outputs()
                         .filter_map(|p| p.ty.as_ref().map(|_| p.name.clone())),
                 );
+                // Fixed columns defined in the body can be referenced in the body.
+                local_variables.extend(
+                    instr
+                        .body
+                        .0
+                        .iter()
+                        .flat_map(|s| s.symbol_definition_names())
+                        .map(|(name, _)| name.clone()),
+                );
                 for e in instr.children() {
                     check_expression(
                         &module_location,
@@ -857,10 +875,12 @@ fn check_expression(
             LambdaExpression {
                 kind: _,
                 params,
+                param_defaults,
                 body,
                 ..
             },
         ) => {
+            check_expressions(location, param_defaults, state, type_vars, local_variables)?;
             // Add the local variables, ignore collisions.
             let mut local_variables = local_variables.clone();
             local_variables.extend(check_patterns(location, params, state)?);
@@ -877,7 +897,8 @@ fn check_expression(
             check_expression(location, b.as_ref(), state, type_vars, local_variables)
         }
         Expression::UnaryOperation(_, UnaryOperation { expr, .. })
-        | Expression::FreeInput(_, expr) => {
+        | Expression::FreeInput(_, expr)
+        | Expression::Cast(_, CastExpression { expr, .. }) => {
             check_expression(location, expr, state, type_vars, local_variables)
         }
         Expression::FunctionCall(
@@ -971,7 +992,9 @@ fn check_pattern<'b>(
     state: &mut State<'_>,
 ) -> Result<Box<dyn Iterator<Item = String> + 'b>, Error> {
     match pattern {
-        Pattern::Variable(_, n) => return Ok(Box::new(once(n.clone()))),
+        Pattern::Variable(_, n) | Pattern::Ellipsis(_, Some(n)) => {
+            return Ok(Box::new(once(n.clone())))
+        }
         Pattern::Enum(source_ref, name, fields) => {
             // The parser cannot distinguish between Enum and Variable patterns.
             // So if "name" is a single identifier that does not resolve to an enum variant,
@@ -515,6 +515,12 @@ fn check_pattern(value_name: &str, pattern: &Pattern) -> Result<(String, String)
             )
         }
         Pattern::Array(_, items) => {
+            if items
+                .iter()
+                .any(|i| matches!(i, Pattern::Ellipsis(_, Some(_))))
+            {
+                return Err(format!("Implement rest binding in pattern {pattern}"));
+            }
             let mut vars = vec![];
             let mut ellipsis_seen = false;
             // This will be code to check the individual items in the array pattern.
@@ -522,7 +528,7 @@ fn check_pattern(value_name: &str, pattern: &Pattern) -> Result<(String, String)
                 .iter()
                 .enumerate()
                 .filter_map(|(i, item)| {
-                    if matches!(item, Pattern::Ellipsis(_)) {
+                    if matches!(item, Pattern::Ellipsis(_, _)) {
                         ellipsis_seen = true;
                         return None;
                     }
@@ -586,7 +592,7 @@ fn check_pattern(value_name: &str, pattern: &Pattern) -> Result<(String, String)
                 ),
             )
         }
-        Pattern::Ellipsis(_) => unreachable!(),
+        Pattern::Ellipsis(_, _) => unreachable!(),
     })
 }
 
@@ -1,29 +1,284 @@
+use itertools::Itertools;
 use powdr_analysis::utils::parse_pil_statement;
 use powdr_ast::{
     asm_analysis::{combine_flags, MachineDegree},
-    object::{Link, Location, MachineInstanceGraph, Object},
+    indent,
+    object::{Link, LinkTo, Location, Machine, MachineInstanceGraph, Object, Operation},
     parsed::{
-        asm::{AbsoluteSymbolPath, Part, SymbolPath},
-        build::{index_access, lookup, namespaced_reference, permutation, selected},
-        visitor::{ExpressionVisitable, VisitOrder},
-        ArrayLiteral, Expression, FunctionCall, NamespaceDegree, Number, PILFile, PilStatement,
+        asm::{AbsoluteSymbolPath, Param, Part, SymbolPath},
+        build::{
+            direct_reference, identity, index_access, lookup, namespaced_reference, permutation,
+            selected,
+        },
+        visitor::{AllChildren, ExpressionVisitable, VisitOrder},
+        ArrayExpression, ArrayLiteral, Expression, FunctionCall, FunctionDefinition,
+        NamespaceDegree, Number, PILFile, PilStatement, PolynomialName, SourceReference,
+        UnaryOperation, UnaryOperator,
     },
 };
 use powdr_parser_util::SourceRef;
-use std::{collections::BTreeMap, iter::once, ops::ControlFlow, str::FromStr};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    iter::once,
+    ops::ControlFlow,
+    str::FromStr,
+};
 use strum::{Display, EnumString, EnumVariantNames};
 
 const MAIN_OPERATION_NAME: &str = "main";
 
+/// The degree of machines which specify neither a minimum nor a maximum degree.
+pub const DEFAULT_DEGREE: u32 = 1 << 10;
+
 /// Link the objects into a single PIL file, using the specified mode.
-pub fn link(graph: MachineInstanceGraph, params: LinkerParams) -> Result<PILFile, Vec<String>> {
+/// Links with [Link::is_permutation] set become permutations whose right-hand side is
+/// selected by the latch and the call selector at [powdr_ast::object::LinkTo::selector_idx],
+/// unless [LinkerParams::lookups_only] is set. All other links become lookups selected by
+/// the latch.
+pub fn link(
+    graph: MachineInstanceGraph,
+    params: LinkerParams,
+) -> Result<PILFile, Vec<LinkerError>> {
+    link_with_report(graph, params).map(|(pil, _)| pil)
+}
+
+/// Link the objects into a single PIL file like [link] and also return a [LinkReport]
+/// describing the interactions generated for the links.
+pub fn link_with_report(
+    graph: MachineInstanceGraph,
+    params: LinkerParams,
+) -> Result<(PILFile, LinkReport), Vec<LinkerError>> {
     Linker::new(params).link(graph)
 }
 
-#[derive(Clone, Copy, Default)]
+/// The interactions generated by the linker, see [link_with_report].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LinkReport {
+    /// For each machine, the interactions generated for the links from this machine,
+    /// in the order of the links.
+    pub interactions: BTreeMap<Location, Vec<LinkInteraction>>,
+}
+
+/// The interaction generated for a single link.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkInteraction {
+    /// Whether the link became a lookup or a permutation.
+    pub interaction_type: InteractionType,
+    /// The id of the interaction, used by the bus in [LinkerMode::Bus].
+    pub interaction_id: u32,
+    /// The machine called by the link.
+    pub target: Location,
+    /// The name of the operation called by the link.
+    pub operation: String,
+    /// The fully qualified names of the columns referenced in the left-hand side.
+    pub lhs_columns: Vec<String>,
+    /// The fully qualified names of the columns referenced in the right-hand side.
+    pub rhs_columns: Vec<String>,
+    /// The names and declared types of the input parameters of the called operation.
+    pub inputs: Vec<(String, Option<String>)>,
+    /// The names and declared types of the output parameters of the called operation.
+    pub outputs: Vec<(String, Option<String>)>,
+}
+
+/// Link the objects into a single PIL file like [link] and apply `transform` to the result.
+/// Errors returned by `transform` are propagated and attributed to the main machine.
+pub fn link_then(
+    graph: MachineInstanceGraph,
+    params: LinkerParams,
+    transform: impl FnOnce(PILFile) -> Result<PILFile, Vec<String>>,
+) -> Result<PILFile, Vec<LinkerError>> {
+    let main = graph.main.location.clone();
+    transform(link(graph, params)?).map_err(|errors| {
+        errors
+            .into_iter()
+            .map(|message| LinkerError::new(main.clone(), None, message))
+            .collect()
+    })
+}
+
+/// An error reported by the linker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkerError {
+    /// The location of the machine the error refers to.
+    pub location: Location,
+    /// The source of the offending declaration, if known.
+    pub source_ref: Option<SourceRef>,
+    pub message: String,
+}
+
+impl LinkerError {
+    fn new(location: Location, source_ref: Option<SourceRef>, message: String) -> Self {
+        Self {
+            location,
+            source_ref,
+            message,
+        }
+    }
+}
+
+impl fmt::Display for LinkerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Link the objects into a single PIL file like [link], but use the machine at
+/// `main` as the top-level machine instead of `graph.main`. The boot constraint
+/// then initializes the operation id of that machine to its `main` operation.
+pub fn link_with_main(
+    mut graph: MachineInstanceGraph,
+    main: Location,
+    params: LinkerParams,
+) -> Result<PILFile, Vec<LinkerError>> {
+    let error = |message| Err(vec![LinkerError::new(main.clone(), None, message)]);
+    if main != graph.main.location {
+        if !graph.objects.contains_key(&main) {
+            return error(format!("Machine {main} not found"));
+        }
+        // The machine instance graph only stores the interface of the main machine,
+        // so we recover the interface of the new main machine from the links to it.
+        let incoming = graph
+            .objects
+            .values()
+            .flat_map(|object| &object.links)
+            .map(|link| &link.to)
+            .filter(|to| to.machine.location == main)
+            .collect::<Vec<_>>();
+        let Some(machine) = incoming.first().map(|to| to.machine.clone()) else {
+            return error(format!(
+                "Machine {main} is not called by any other machine, cannot determine its operations"
+            ));
+        };
+        if machine.operation_id.is_none() {
+            return error(format!(
+                "Machine {main} does not have an operation id and cannot be used as main"
+            ));
+        }
+        let entry_points = incoming
+            .into_iter()
+            .flat_map(|to| to.operations().cloned())
+            .filter(|operation| params.entry_point.selects(&operation.name))
+            .unique_by(|operation| operation.name.clone())
+            .collect::<Vec<_>>();
+        if entry_points.is_empty() {
+            return error(format!(
+                "Machine {main} does not have an operation called {}",
+                params.entry_point.operation_name().unwrap()
+            ));
+        }
+        graph.main = machine;
+        graph.entry_points = entry_points;
+    }
+    link(graph, params)
+}
+
+/// Formats the output of the linker like its [fmt::Display] implementation, but groups the
+/// statements of each namespace into definitions, fixed columns, witness columns and
+/// constraints, each introduced by a comment. The grouping does not change the meaning
+/// of the PIL, so the result can be parsed again.
+pub fn format_pil_annotated(pil: &PILFile) -> String {
+    let sections = [
+        "Definitions",
+        "Fixed columns",
+        "Witness columns",
+        "Constraints",
+    ];
+    let section_of = |statement: &PilStatement| match statement {
+        PilStatement::PolynomialConstantDefinition(..) => 1,
+        // A `let` without a value declares a witness column.
+        PilStatement::PolynomialCommitDeclaration(..)
+        | PilStatement::LetStatement(_, _, _, None) => 2,
+        PilStatement::Expression(..) => 3,
+        _ => 0,
+    };
+
+    // The statements before the first namespace, followed by one group per namespace.
+    let mut groups: Vec<(Option<&PilStatement>, Vec<&PilStatement>)> = vec![(None, vec![])];
+    for statement in &pil.0 {
+        match statement {
+            PilStatement::Namespace(..) => groups.push((Some(statement), vec![])),
+            _ => groups.last_mut().unwrap().1.push(statement),
+        }
+    }
+
+    let mut result = String::new();
+    for (namespace, statements) in groups {
+        if let Some(namespace @ PilStatement::Namespace(_, name, _)) = namespace {
+            result.push_str(&format!("// ===== Machine {name} =====\n{namespace}\n"));
+        }
+        let indentation = usize::from(namespace.is_some());
+        for (index, section) in sections.iter().enumerate() {
+            let statements = statements
+                .iter()
+                .filter(|&&statement| section_of(statement) == index)
+                .collect::<Vec<_>>();
+            if statements.is_empty() {
+                continue;
+            }
+            result.push_str(&indent(format!("// {section}\n"), indentation));
+            for statement in statements {
+                result.push_str(&indent(format!("{statement}\n"), indentation));
+            }
+        }
+    }
+    result
+}
+
+#[derive(Clone, Default)]
 pub struct LinkerParams {
     pub mode: LinkerMode,
     pub degree_mode: DegreeMode,
+    /// How the operation of the main machine which is called in the first step is chosen.
+    pub entry_point: EntryPoint,
+    /// The degree of machines which specify neither a minimum nor a maximum degree,
+    /// [DEFAULT_DEGREE] if not set. Must be a power of two.
+    pub default_degree: Option<u32>,
+    /// The degree of the main machine, overriding the one declared in the source.
+    /// Must be a power of two.
+    pub main_degree: Option<u32>,
+    /// If set, submachines with at most this many PIL statements are inlined into their callers
+    /// instead of being linked by lookups, see [inlinable_machines].
+    pub inline_threshold: Option<usize>,
+    /// If set, links with [Link::is_permutation] set are linked by lookups like all other links.
+    pub lookups_only: bool,
+}
+
+impl LinkerParams {
+    fn default_degree(&self) -> u32 {
+        self.default_degree.unwrap_or(DEFAULT_DEGREE)
+    }
+}
+
+/// The operation of the main machine which is called in the first step.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EntryPoint {
+    /// Always call the operation with the given name.
+    Fixed(String),
+    /// Call any of the entry points, selected at proof time by the operation id exposed as the
+    /// public with the given name.
+    Public(String),
+}
+
+impl Default for EntryPoint {
+    fn default() -> Self {
+        EntryPoint::Fixed(MAIN_OPERATION_NAME.to_string())
+    }
+}
+
+impl EntryPoint {
+    /// Returns the name of the called operation if it is fixed.
+    pub fn operation_name(&self) -> Option<&str> {
+        match self {
+            EntryPoint::Fixed(name) => Some(name),
+            EntryPoint::Public(_) => None,
+        }
+    }
+
+    /// Returns true if the operation called `operation` can be called in the first step.
+    fn selects(&self, operation: &str) -> bool {
+        self.operation_name().is_none_or(|name| name == operation)
+    }
 }
 
 #[derive(Clone, EnumString, EnumVariantNames, Display, Copy, Default)]
@@ -53,6 +308,12 @@ struct Linker {
     /// for each namespace, we store the statements resulting from processing the links separately, because we need to make sure they do not come first.
     namespaces: BTreeMap<String, (Vec<PilStatement>, Vec<PilStatement>)>,
     next_interaction_id: u32,
+    report: LinkReport,
+    /// The machines which are inlined into their callers.
+    inlined: BTreeMap<Location, Object>,
+    /// For each calling and inlined namespace, the number of copies of the inlined machine
+    /// already created in the calling namespace.
+    inlined_copies: BTreeMap<(String, String), usize>,
 }
 
 impl Linker {
@@ -69,8 +330,43 @@ impl Linker {
         id
     }
 
-    fn link(mut self, graph: MachineInstanceGraph) -> Result<PILFile, Vec<String>> {
+    fn link(
+        mut self,
+        mut graph: MachineInstanceGraph,
+    ) -> Result<(PILFile, LinkReport), Vec<LinkerError>> {
+        graph.validate().map_err(|errors| {
+            errors
+                .into_iter()
+                .map(|message| LinkerError::new(graph.main.location.clone(), None, message))
+                .collect::<Vec<_>>()
+        })?;
         let main_machine = graph.main;
+        let default_degree = self.params.default_degree();
+        if !default_degree.is_power_of_two() {
+            return Err(vec![LinkerError::new(
+                main_machine.location,
+                None,
+                format!("The default degree {default_degree} is not a power of two"),
+            )]);
+        }
+        if let Some(main_degree) = self.params.main_degree {
+            if !main_degree.is_power_of_two() {
+                return Err(vec![LinkerError::new(
+                    main_machine.location,
+                    None,
+                    format!("The main degree {main_degree} is not a power of two"),
+                )]);
+            }
+            let degree = Expression::from(main_degree);
+            graph
+                .objects
+                .get_mut(&main_machine.location)
+                .unwrap()
+                .degree = MachineDegree {
+                min: Some(degree.clone()),
+                max: Some(degree),
+            };
+        }
         self.max_degree = match self.params.degree_mode {
             DegreeMode::Monolithic => Some(graph
                 .objects
@@ -78,44 +374,33 @@ impl Linker {
                 .filter_map(|(_, object)| object.degree.max.clone()).map(|e| match e {
                     Expression::Number(_, n) => n,
                     _ => unimplemented!("Only constant max degrees are supported when using monolithic degree mode"),
-                }).max().unwrap()),
+                }).max().unwrap_or_else(|| Number { value: default_degree.into(), type_: None })),
             DegreeMode::Vadcop => None,
         };
 
+        if let Some(threshold) = self.params.inline_threshold {
+            self.inlined = inlinable_machines(&graph.objects, &main_machine.location, threshold)
+                .into_iter()
+                .map(|location| {
+                    let object = graph.objects.remove(&location).unwrap();
+                    (location, object)
+                })
+                .collect();
+        }
+
         let common_definitions = process_definitions(graph.statements);
 
         for (location, object) in graph.objects {
-            self.process_object(location.clone(), object);
+            self.process_object(location.clone(), object)
+                .map_err(|error| vec![error])?;
 
-            if location == Location::main() {
-                if let Some(main_operation) = graph
-                    .entry_points
-                    .iter()
-                    .find(|f| f.name == MAIN_OPERATION_NAME)
-                {
-                    let main_operation_id = main_operation.id.clone();
-                    let operation_id = main_machine.operation_id.clone();
-                    match (operation_id, main_operation_id) {
-                        (Some(operation_id), Some(main_operation_id)) => {
-                            // call the main operation by initializing `operation_id` to that of the main operation
-                            let linker_first_step = "_linker_first_step";
-                            self.namespaces.get_mut(&location.to_string()).unwrap().1.extend([
-                                parse_pil_statement(&format!(
-                                    "col fixed {linker_first_step}(i) {{ if i == 0 {{ 1 }} else {{ 0 }} }};"
-                                )),
-                                parse_pil_statement(&format!(
-                                    "{linker_first_step} * ({operation_id} - {main_operation_id}) = 0;"
-                                )),
-                            ]);
-                        }
-                        (None, None) => {}
-                        _ => unreachable!(),
-                    }
-                }
+            if location == main_machine.location {
+                self.process_entry_points(&location, &main_machine, &graph.entry_points)
+                    .map_err(|error| vec![error])?;
             }
         }
 
-        Ok(PILFile(
+        let pil = PILFile(
             common_definitions
                 .into_iter()
                 .chain(
@@ -124,19 +409,93 @@ impl Linker {
                         .flat_map(|(_, (statements, links))| statements.into_iter().chain(links)),
                 )
                 .collect(),
-        ))
+        );
+        Ok((pil, self.report))
+    }
+
+    /// Constrains `operation_id` of the main machine in the first step to that of the entry point,
+    /// or to that of any of the entry points if it is selected by a public.
+    fn process_entry_points(
+        &mut self,
+        location: &Location,
+        main_machine: &Machine,
+        entry_points: &[Operation],
+    ) -> Result<(), LinkerError> {
+        let ids = entry_points
+            .iter()
+            .filter(|operation| self.params.entry_point.selects(&operation.name))
+            .map(|operation| operation.id.clone())
+            .collect::<Vec<_>>();
+        if ids.is_empty() {
+            // Machines without a `main` operation are not booted, but a custom entry point
+            // must exist.
+            return match self.params.entry_point.operation_name() {
+                Some(name) if name != MAIN_OPERATION_NAME => Err(LinkerError::new(
+                    location.clone(),
+                    None,
+                    format!("Machine {location} does not have an operation called {name}"),
+                )),
+                _ => Ok(()),
+            };
+        }
+        let operation_id = match (&main_machine.operation_id, ids.iter().all(Option::is_some)) {
+            (Some(operation_id), true) => operation_id,
+            (None, false) => return Ok(()),
+            _ => unreachable!(),
+        };
+        let ids = match &self.params.entry_point {
+            // call the main operation by initializing `operation_id` to that of the main operation
+            EntryPoint::Fixed(_) => ids.into_iter().take(1).collect::<Vec<_>>(),
+            EntryPoint::Public(_) => ids,
+        };
+        let selection = ids
+            .into_iter()
+            .map(|id| format!("({operation_id} - {})", id.unwrap()))
+            .join(" * ");
+        let linker_first_step = "_linker_first_step";
+        let statements = &mut self.namespaces.get_mut(&location.namespace()).unwrap().1;
+        statements.extend([
+            parse_pil_statement(&format!(
+                "col fixed {linker_first_step}(i) {{ if i == 0 {{ 1 }} else {{ 0 }} }};"
+            )),
+            parse_pil_statement(&format!("{linker_first_step} * {selection} = 0;")),
+        ]);
+        if let EntryPoint::Public(public) = &self.params.entry_point {
+            statements.push(parse_pil_statement(&format!(
+                "public {public} = {operation_id}(0);"
+            )));
+        }
+        Ok(())
     }
 
-    fn process_object(&mut self, location: Location, object: Object) {
+    fn process_object(&mut self, location: Location, object: Object) -> Result<(), LinkerError> {
         let namespace_degree = match &self.params.degree_mode {
             DegreeMode::Monolithic => {
                 Expression::Number(SourceRef::unknown(), self.max_degree.clone().unwrap()).into()
             }
-            DegreeMode::Vadcop => try_into_namespace_degree(object.degree)
-                .unwrap_or_else(|| panic!("machine at {location} must have an explicit degree")),
+            DegreeMode::Vadcop => match object.degree {
+                MachineDegree {
+                    min: None,
+                    max: None,
+                } => Expression::from(self.params.default_degree()).into(),
+                degree => {
+                    let source_ref = degree
+                        .min
+                        .as_ref()
+                        .or(degree.max.as_ref())
+                        .map(|e| e.source_reference().clone());
+                    try_into_namespace_degree(degree).ok_or_else(|| {
+                        LinkerError::new(
+                            location.clone(),
+                            source_ref,
+                            format!("machine at {location} must have both a min and a max degree"),
+                        )
+                    })?
+                }
+            },
         };
 
-        let namespace = location.to_string();
+        let namespace = location.namespace();
 
         let (pil, _) = self.namespaces.entry(namespace.clone()).or_default();
 
@@ -148,30 +507,85 @@ impl Linker {
         ));
 
         pil.extend(object.pil);
+        pil.extend(object.publics.iter().map(|(column, row)| {
+            parse_pil_statement(&format!("public _public_{column}_{row} = {column}({row});"))
+        }));
         for link in object.links {
-            self.process_link(link, namespace.clone());
+            self.process_link(link, &location)?;
         }
+
+        Ok(())
     }
 
-    fn process_link(&mut self, link: Link, from_namespace: String) {
+    fn process_link(&mut self, link: Link, from_location: &Location) -> Result<(), LinkerError> {
         let from = link.from;
         let to = link.to;
 
-        let to_namespace = to.machine.location.clone().to_string();
-
-        let op_id = to.operation.id.iter().cloned().map(|n| n.into());
+        let from_namespace = from_location.namespace();
+        let to_location = to.machine.location.clone();
+        let to_namespace = to_location.namespace();
+        let operation = to.operation.name.clone();
+
+        let error = |message| Err(LinkerError::new(from_location.clone(), None, message));
+        let flag = combine_flags(from.instr_flag, from.link_flag);
+        let op_id = match from.operation_id {
+            // the operation is selected dynamically by the caller
+            Some(operation_id) => {
+                if to.machine.operation_id.is_none() {
+                    return error(format!(
+                        "link to {to_namespace} selects the operation dynamically, but the machine does not have an operation id"
+                    ));
+                }
+                if let Some(operation) = to
+                    .alternative_operations
+                    .iter()
+                    .find(|operation| operation.params != to.operation.params)
+                {
+                    return error(format!(
+                        "operations {} and {} of {to_namespace} have different parameters and cannot be selected dynamically",
+                        to.operation.name, operation.name
+                    ));
+                }
+                // `flag * (operation_id - id_1) * ... * (operation_id - id_n) = 0` restricts the
+                // caller to the selectable operations
+                let selection = once(&to.operation)
+                    .chain(&to.alternative_operations)
+                    .map(|operation| operation.id.clone().unwrap())
+                    .fold(flag.clone(), |acc, id| {
+                        acc * (operation_id.clone() - Expression::from(id))
+                    });
+                self.namespaces
+                    .entry(from_namespace.clone())
+                    .or_default()
+                    .1
+                    .push(PilStatement::Expression(
+                        SourceRef::unknown(),
+                        identity(selection, 0u32.into()),
+                    ));
+                Some(operation_id)
+            }
+            None => {
+                if !to.alternative_operations.is_empty() {
+                    return error(format!(
+                        "link to several operations of {to_namespace} requires an operation id"
+                    ));
+                }
+                to.operation.id.clone().map(|n| n.into())
+            }
+        }
+        .into_iter();
+
+        let lhs_items = op_id
+            .chain(from.params.inputs)
+            .chain(from.params.outputs)
+            .collect();
+        if self.inlined.contains_key(&to_location) {
+            self.inline_link(from_namespace, to, flag, lhs_items);
+            return Ok(());
+        }
 
         // lhs is `flag { operation_id, inputs, outputs }`
-        let lhs = selected(
-            combine_flags(from.instr_flag, from.link_flag),
-            ArrayLiteral {
-                items: op_id
-                    .chain(from.params.inputs)
-                    .chain(from.params.outputs)
-                    .collect(),
-            }
-            .into(),
-        );
+        let lhs = selected(flag, ArrayLiteral { items: lhs_items }.into());
 
         let op_id = to
             .machine
@@ -191,10 +605,9 @@ impl Linker {
         }
         .into();
 
-        if link.is_permutation {
+        let latch = namespaced_reference(to_namespace.clone(), to.machine.latch.unwrap());
+        let (interaction_type, rhs) = if link.is_permutation && !self.params.lookups_only {
             // permutation rhs is `(latch * selector[idx]) { operation_id, inputs, outputs }`
-
-            let latch = namespaced_reference(to_namespace.clone(), to.machine.latch.unwrap());
             let rhs_selector = if let Some(call_selectors) = to.machine.call_selectors {
                 let call_selector_array =
                     namespaced_reference(to_namespace.clone(), call_selectors);
@@ -205,31 +618,112 @@ impl Linker {
                 latch.clone()
             };
 
-            let rhs = selected(rhs_selector, rhs_list);
-
-            self.insert_interaction(
+            (
                 InteractionType::Permutation,
-                from_namespace,
-                to_namespace,
-                lhs,
-                rhs,
-                latch,
-            );
+                selected(rhs_selector, rhs_list),
+            )
         } else {
-            let latch = namespaced_reference(to_namespace.clone(), to.machine.latch.unwrap());
-
             // plookup rhs is `latch $ [ operation_id, inputs, outputs ]`
-            let rhs = selected(latch.clone(), rhs_list);
-
-            self.insert_interaction(
-                InteractionType::Lookup,
-                from_namespace,
-                to_namespace,
-                lhs,
-                rhs,
-                latch,
-            );
+            (InteractionType::Lookup, selected(latch.clone(), rhs_list))
         };
+
+        let lhs_columns =
+            referenced_columns(&namespaced_expression(from_namespace.clone(), lhs.clone()));
+        let rhs_columns = referenced_columns(&rhs);
+        let param_types = |params: &[Param]| {
+            params
+                .iter()
+                .map(|param| {
+                    (
+                        param.name.clone(),
+                        param.ty.as_ref().map(ToString::to_string),
+                    )
+                })
+                .collect()
+        };
+        let inputs = param_types(&to.operation.params.inputs);
+        let outputs = param_types(&to.operation.params.outputs);
+        let interaction_id = self.insert_interaction(
+            interaction_type,
+            from_namespace,
+            to_namespace,
+            lhs,
+            rhs,
+            latch,
+        );
+        self.report
+            .interactions
+            .entry(from_location.clone())
+            .or_default()
+            .push(LinkInteraction {
+                interaction_type,
+                interaction_id,
+                target: to_location,
+                operation,
+                lhs_columns,
+                rhs_columns,
+                inputs,
+                outputs,
+            });
+        Ok(())
+    }
+
+    /// Adds a copy of the inlined machine called by the link to the calling namespace, with its
+    /// symbols renamed to avoid clashes, and constrains the arguments of the link to the
+    /// parameters of the copy whenever `flag` is set.
+    fn inline_link(
+        &mut self,
+        from_namespace: String,
+        to: LinkTo,
+        flag: Expression,
+        lhs: Vec<Expression>,
+    ) {
+        let to_namespace = to.machine.location.namespace();
+        let copies = self
+            .inlined_copies
+            .entry((from_namespace.clone(), to_namespace.clone()))
+            .or_default();
+        let prefix = format!("{to_namespace}_{copies}_");
+        *copies += 1;
+
+        let object = &self.inlined[&to.machine.location];
+        let names = object
+            .pil
+            .iter()
+            .flat_map(|statement| statement.symbol_definition_names())
+            .map(|(name, _)| name.clone())
+            .collect::<BTreeSet<_>>();
+        let local = |name: &str| direct_reference(format!("{prefix}{name}"));
+        let rhs = to
+            .machine
+            .operation_id
+            .iter()
+            .map(|operation_id| local(operation_id))
+            .chain(
+                to.operation
+                    .params
+                    .inputs_and_outputs()
+                    .map(|param| index_access(local(&param.name), param.index.clone())),
+            );
+        let latch = local(to.machine.latch.as_ref().unwrap());
+
+        // `flag * (1 - latch) = 0` and `flag * (lhs_i - rhs_i) = 0` for each argument
+        let constraints = once(Expression::from(1u32) - latch)
+            .chain(lhs.into_iter().zip_eq(rhs).map(|(l, r)| l - r))
+            .map(|difference| identity(flag.clone() * difference, 0u32.into()))
+            .map(|constraint| PilStatement::Expression(SourceRef::unknown(), constraint));
+        let statements = object
+            .pil
+            .iter()
+            .cloned()
+            .map(|statement| rename_symbols(statement, &names, &prefix))
+            .chain(constraints)
+            .collect::<Vec<_>>();
+        self.namespaces
+            .entry(from_namespace)
+            .or_default()
+            .1
+            .extend(statements);
     }
 
     fn insert_interaction(
@@ -240,7 +734,7 @@ impl Linker {
         lhs: Expression,
         rhs: Expression,
         latch: Expression,
-    ) {
+    ) -> u32 {
         // get a new unique interaction id
         let interaction_id = self.next_interaction_id();
 
@@ -284,15 +778,29 @@ impl Linker {
                     ));
             }
         }
+
+        interaction_id
     }
 }
 
-#[derive(Clone, Copy)]
-enum InteractionType {
+/// The kind of identity a link becomes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InteractionType {
     Lookup,
     Permutation,
 }
 
+/// Returns the names of the columns referenced in `expr`, without duplicates.
+fn referenced_columns(expr: &Expression) -> Vec<String> {
+    expr.all_children()
+        .filter_map(|e| match e {
+            Expression::Reference(_, reference) => Some(reference.path.to_string()),
+            _ => None,
+        })
+        .unique()
+        .collect()
+}
+
 fn send(
     identity_type: InteractionType,
     lhs: Expression,
@@ -354,6 +862,108 @@ fn receive(
     )
 }
 
+/// Returns the machines in `objects` which can be inlined into their callers, see
+/// [LinkerParams::inline_threshold].
+/// These are the machines other than `main` without a pc, links and publics, with at most
+/// `threshold` PIL statements, which are only called by lookups. Their rows must be
+/// independent of each other and of their position, so their constraints may not refer to the
+/// next row and their fixed columns must be constant.
+pub fn inlinable_machines(
+    objects: &BTreeMap<Location, Object>,
+    main: &Location,
+    threshold: usize,
+) -> BTreeSet<Location> {
+    let mut incoming: BTreeMap<&Location, Vec<&Link>> = BTreeMap::new();
+    for link in objects.values().flat_map(|object| &object.links) {
+        incoming
+            .entry(&link.to.machine.location)
+            .or_default()
+            .push(link);
+    }
+    let is_row_independent = |statement: &PilStatement| match statement {
+        PilStatement::PolynomialConstantDefinition(
+            _,
+            _,
+            FunctionDefinition::Array(ArrayExpression::RepeatedValue(values)),
+        ) => matches!(values[..], [Expression::Number(..)]),
+        // A lambda could define a fixed column.
+        PilStatement::LetStatement(_, _, _, value) => {
+            !matches!(value, Some(Expression::LambdaExpression(..)))
+        }
+        PilStatement::PolynomialDefinition(..)
+        | PilStatement::PolynomialCommitDeclaration(..)
+        | PilStatement::Expression(..) => true,
+        _ => false,
+    };
+    let refers_to_next_row = |statement: &PilStatement| {
+        statement.all_children().any(|e| {
+            matches!(
+                e,
+                Expression::UnaryOperation(
+                    _,
+                    UnaryOperation {
+                        op: UnaryOperator::Next,
+                        ..
+                    }
+                )
+            )
+        })
+    };
+    objects
+        .iter()
+        .filter(|(location, object)| {
+            *location != main
+                && !object.has_pc
+                && object.links.is_empty()
+                && object.publics.is_empty()
+                && object.pil.len() <= threshold
+                && object.pil.iter().all(&is_row_independent)
+                && !object.pil.iter().any(&refers_to_next_row)
+                && incoming
+                    .get(*location)
+                    .is_some_and(|links| links.iter().all(|link| !link.is_permutation))
+        })
+        .map(|(location, _)| location.clone())
+        .collect()
+}
+
+/// Prefixes the symbols in `names` with `prefix`, both where they are defined in `statement`
+/// and where they are referenced.
+fn rename_symbols(
+    mut statement: PilStatement,
+    names: &BTreeSet<String>,
+    prefix: &str,
+) -> PilStatement {
+    let rename = |name: &mut String| {
+        if names.contains(&*name) {
+            *name = format!("{prefix}{name}");
+        }
+    };
+    match &mut statement {
+        PilStatement::LetStatement(_, name, _, _)
+        | PilStatement::PolynomialDefinition(_, PolynomialName { name, .. }, _)
+        | PilStatement::PolynomialConstantDefinition(_, name, _) => rename(name),
+        PilStatement::PolynomialCommitDeclaration(_, _, polynomials, _) => polynomials
+            .iter_mut()
+            .for_each(|PolynomialName { name, .. }| rename(name)),
+        _ => {}
+    }
+    statement.visit_expressions_mut(
+        &mut |expr| {
+            if let Expression::Reference(_, reference) = expr {
+                if let Some(name) = reference.try_to_identifier().cloned() {
+                    if names.contains(&name) {
+                        reference.path = SymbolPath::from_identifier(format!("{prefix}{name}"));
+                    }
+                }
+            }
+            ControlFlow::Continue::<(), _>(())
+        },
+        VisitOrder::Pre,
+    );
+    statement
+}
+
 /// Convert a [MachineDegree] into a [NamespaceDegree]
 fn try_into_namespace_degree(d: MachineDegree) -> Option<NamespaceDegree> {
     let min = d.min?;
@@ -406,15 +1016,19 @@ fn process_definitions(
 mod test {
     use std::{fs, path::PathBuf};
 
-    use powdr_ast::{object::MachineInstanceGraph, parsed::PILFile};
+    use powdr_ast::{
+        object::{Location, MachineInstanceGraph, Operation},
+        parsed::{build::direct_reference, PILFile},
+    };
     use powdr_number::{FieldElement, GoldilocksField};
 
+    use itertools::Itertools;
     use powdr_analysis::convert_asm_to_pil;
     use powdr_parser::parse_asm;
 
     use pretty_assertions::assert_eq;
 
-    fn link_native(graph: MachineInstanceGraph) -> Result<PILFile, Vec<String>> {
+    fn link_native(graph: MachineInstanceGraph) -> Result<PILFile, Vec<super::LinkerError>> {
         super::link(
             graph,
             super::LinkerParams {
@@ -424,22 +1038,28 @@ mod test {
         )
     }
 
-    fn link_native_monolithic(graph: MachineInstanceGraph) -> Result<PILFile, Vec<String>> {
+    fn link_native_monolithic(
+        graph: MachineInstanceGraph,
+    ) -> Result<PILFile, Vec<super::LinkerError>> {
         super::link(
             graph,
             super::LinkerParams {
                 mode: super::LinkerMode::Native,
                 degree_mode: super::DegreeMode::Monolithic,
+                ..Default::default()
             },
         )
     }
 
-    fn link_with_bus_monolithic(graph: MachineInstanceGraph) -> Result<PILFile, Vec<String>> {
+    fn link_with_bus_monolithic(
+        graph: MachineInstanceGraph,
+    ) -> Result<PILFile, Vec<super::LinkerError>> {
         super::link(
             graph,
             super::LinkerParams {
                 mode: super::LinkerMode::Bus,
                 degree_mode: super::DegreeMode::Monolithic,
+                ..Default::default()
             },
         )
     }
@@ -470,6 +1090,39 @@ mod test {
         &code[start..]
     }
 
+    /// Removes empty lines and collapses all other whitespace to single spaces.
+    fn normalize_whitespace(code: &str) -> String {
+        code.lines()
+            .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Compares the main machine part of `pil` (see `extract_main`) against the golden file
+    /// at `golden_path`, ignoring insignificant whitespace.
+    /// On mismatch, the actual output is written to `<golden_path>.actual`.
+    /// If the environment variable `UPDATE_GOLDEN` is set, the golden file is updated instead.
+    fn assert_pil_matches_golden(pil: &PILFile, golden_path: &str) {
+        let actual = pil.to_string();
+        let actual = extract_main(&actual);
+        if std::env::var("UPDATE_GOLDEN").is_ok() {
+            fs::write(golden_path, actual).unwrap();
+            return;
+        }
+        let expected = fs::read_to_string(golden_path)
+            .unwrap_or_else(|e| panic!("Could not read golden file {golden_path}: {e}"));
+        if normalize_whitespace(actual) != normalize_whitespace(&expected) {
+            let actual_path = format!("{golden_path}.actual");
+            fs::write(&actual_path, actual).unwrap();
+            assert_eq!(
+                normalize_whitespace(actual),
+                normalize_whitespace(&expected),
+                "PIL does not match golden file {golden_path}, actual output written to {actual_path}"
+            );
+        }
+    }
+
     #[test]
     fn compile_empty_vm() {
         let native_expectation = r#"namespace main(8);
@@ -485,8 +1138,12 @@ mod test {
     pol commit instr_return;
     pol constant first_step = [1] + [0]*;
     pol commit pc_update;
-    pc_update = instr__jump_to_operation * _operation_id + instr__loop * pc + instr_return * 0 + (1 - (instr__jump_to_operation + instr__loop + instr_return)) * (pc + 1);
+    pc_update = instr__jump_to_operation * _operation_id + instr__loop * pc + (1 - (instr__jump_to_operation + instr__loop + instr_return)) * (pc + 1);
     pc' = (1 - first_step') * pc_update;
+    instr__jump_to_operation * (1 - instr__jump_to_operation) = 0;
+    instr__loop * (1 - instr__loop) = 0;
+    instr_return * (1 - instr_return) = 0;
+    (instr__jump_to_operation + instr__loop + instr_return) * (1 - (instr__jump_to_operation + instr__loop + instr_return)) = 0;
     1 $ [0, pc, instr__jump_to_operation, instr__reset, instr__loop, instr_return] in main__rom::latch $ [main__rom::operation_id, main__rom::p_line, main__rom::p_instr__jump_to_operation, main__rom::p_instr__reset, main__rom::p_instr__loop, main__rom::p_instr_return];
 namespace main__rom(8);
     pol constant p_line = [0, 1, 2] + [2]*;
@@ -511,8 +1168,12 @@ namespace main__rom(8);
     pol commit instr_return;
     pol constant first_step = [1] + [0]*;
     pol commit pc_update;
-    pc_update = instr__jump_to_operation * _operation_id + instr__loop * pc + instr_return * 0 + (1 - (instr__jump_to_operation + instr__loop + instr_return)) * (pc + 1);
+    pc_update = instr__jump_to_operation * _operation_id + instr__loop * pc + (1 - (instr__jump_to_operation + instr__loop + instr_return)) * (pc + 1);
     pc' = (1 - first_step') * pc_update;
+    instr__jump_to_operation * (1 - instr__jump_to_operation) = 0;
+    instr__loop * (1 - instr__loop) = 0;
+    instr_return * (1 - instr_return) = 0;
+    (instr__jump_to_operation + instr__loop + instr_return) * (1 - (instr__jump_to_operation + instr__loop + instr_return)) = 0;
     std::protocols::lookup_via_bus::lookup_send(0, 1 $ [0, pc, instr__jump_to_operation, instr__reset, instr__loop, instr_return] in main__rom::latch $ [main__rom::operation_id, main__rom::p_line, main__rom::p_instr__jump_to_operation, main__rom::p_instr__reset, main__rom::p_instr__loop, main__rom::p_instr_return]);
 namespace main__rom(8);
     pol constant p_line = [0, 1, 2] + [2]*;
@@ -549,6 +1210,32 @@ namespace main__rom(8);
         assert_eq!(&pil[0..input.len()], input);
     }
 
+    #[test]
+    fn link_then_transform() {
+        let input = "
+machine Main {
+    col witness x;
+}
+";
+        let graph = parse_analyze_and_compile::<GoldilocksField>(input);
+        let pil = super::link_then(graph, Default::default(), |mut pil| {
+            pil.0.push(super::parse_pil_statement("x = 1;"));
+            Ok(pil)
+        })
+        .unwrap()
+        .to_string();
+        assert!(pil.ends_with("x = 1;\n"));
+
+        let graph = parse_analyze_and_compile::<GoldilocksField>(input);
+        let errors = super::link_then(graph, Default::default(), |_| {
+            Err(vec!["transform failed".to_string()])
+        })
+        .unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].to_string(), "transform failed");
+        assert_eq!(errors[0].location, Location::main());
+    }
+
     #[test]
     fn compile_different_signatures() {
         let expectation = r#"namespace main(16);
@@ -572,22 +1259,26 @@ namespace main__rom(8);
     pol commit instr_return;
     pol commit X_const;
     pol commit X_read_free;
-    pol commit read_X_A;
-    pol commit read_X_pc;
-    X = read_X_A * A + read_X_pc * pc + X_const + X_read_free * X_free_value;
+    X = X_const + X_read_free * X_free_value;
     pol commit Y_const;
     pol commit Y_read_free;
-    pol commit read_Y_A;
-    pol commit read_Y_pc;
-    Y = read_Y_A * A + read_Y_pc * pc + Y_const + Y_read_free * Y_free_value;
+    Y = Y_const + Y_read_free * Y_free_value;
     pol constant first_step = [1] + [0]*;
-    A' = reg_write_X_A * X + reg_write_Y_A * Y + instr__reset * 0 + (1 - (reg_write_X_A + reg_write_Y_A + instr__reset)) * A;
+    A' = reg_write_X_A * X + reg_write_Y_A * Y + (1 - (reg_write_X_A + reg_write_Y_A + instr__reset)) * A;
+    reg_write_X_A * (1 - reg_write_X_A) = 0;
+    reg_write_Y_A * (1 - reg_write_Y_A) = 0;
+    instr__reset * (1 - instr__reset) = 0;
+    (reg_write_X_A + reg_write_Y_A + instr__reset) * (1 - (reg_write_X_A + reg_write_Y_A + instr__reset)) = 0;
     pol commit pc_update;
-    pc_update = instr__jump_to_operation * _operation_id + instr__loop * pc + instr_return * 0 + (1 - (instr__jump_to_operation + instr__loop + instr_return)) * (pc + 1);
+    pc_update = instr__jump_to_operation * _operation_id + instr__loop * pc + (1 - (instr__jump_to_operation + instr__loop + instr_return)) * (pc + 1);
     pc' = (1 - first_step') * pc_update;
+    instr__jump_to_operation * (1 - instr__jump_to_operation) = 0;
+    instr__loop * (1 - instr__loop) = 0;
+    instr_return * (1 - instr_return) = 0;
+    (instr__jump_to_operation + instr__loop + instr_return) * (1 - (instr__jump_to_operation + instr__loop + instr_return)) = 0;
     pol commit X_free_value;
     pol commit Y_free_value;
-    1 $ [0, pc, reg_write_X_A, reg_write_Y_A, instr_identity, instr_one, instr_nothing, instr__jump_to_operation, instr__reset, instr__loop, instr_return, X_const, X_read_free, read_X_A, read_X_pc, Y_const, Y_read_free, read_Y_A, read_Y_pc] in main__rom::latch $ [main__rom::operation_id, main__rom::p_line, main__rom::p_reg_write_X_A, main__rom::p_reg_write_Y_A, main__rom::p_instr_identity, main__rom::p_instr_one, main__rom::p_instr_nothing, main__rom::p_instr__jump_to_operation, main__rom::p_instr__reset, main__rom::p_instr__loop, main__rom::p_instr_return, main__rom::p_X_const, main__rom::p_X_read_free, main__rom::p_read_X_A, main__rom::p_read_X_pc, main__rom::p_Y_const, main__rom::p_Y_read_free, main__rom::p_read_Y_A, main__rom::p_read_Y_pc];
+    1 $ [0, pc, reg_write_X_A, reg_write_Y_A, instr_identity, instr_one, instr_nothing, instr__jump_to_operation, instr__reset, instr__loop, instr_return, X_const, X_read_free, Y_const, Y_read_free] in main__rom::latch $ [main__rom::operation_id, main__rom::p_line, main__rom::p_reg_write_X_A, main__rom::p_reg_write_Y_A, main__rom::p_instr_identity, main__rom::p_instr_one, main__rom::p_instr_nothing, main__rom::p_instr__jump_to_operation, main__rom::p_instr__reset, main__rom::p_instr__loop, main__rom::p_instr_return, main__rom::p_X_const, main__rom::p_X_read_free, main__rom::p_Y_const, main__rom::p_Y_read_free];
     instr_identity $ [2, X, Y] in main_sub::instr_return $ [main_sub::_operation_id, main_sub::_input_0, main_sub::_output_0];
     instr_nothing $ [3] in main_sub::instr_return $ [main_sub::_operation_id];
     instr_one $ [4, Y] in main_sub::instr_return $ [main_sub::_operation_id, main_sub::_output_0];
@@ -606,10 +1297,6 @@ namespace main__rom(16);
     pol constant p_instr_nothing = [0]*;
     pol constant p_instr_one = [0, 0, 1, 0, 0] + [0]*;
     pol constant p_instr_return = [0, 0, 0, 1, 0] + [0]*;
-    pol constant p_read_X_A = [0]*;
-    pol constant p_read_X_pc = [0]*;
-    pol constant p_read_Y_A = [0]*;
-    pol constant p_read_Y_pc = [0]*;
     pol constant p_reg_write_X_A = [0]*;
     pol constant p_reg_write_Y_A = [0, 0, 1, 0, 0] + [0]*;
     pol constant operation_id = [0]*;
@@ -629,16 +1316,19 @@ namespace main_sub(16);
     pol commit instr_return;
     pol commit _output_0_const;
     pol commit _output_0_read_free;
-    pol commit read__output_0_pc;
     pol commit read__output_0__input_0;
-    _output_0 = read__output_0_pc * pc + read__output_0__input_0 * _input_0 + _output_0_const + _output_0_read_free * _output_0_free_value;
+    _output_0 = read__output_0__input_0 * _input_0 + _output_0_const + _output_0_read_free * _output_0_free_value;
     pol constant first_step = [1] + [0]*;
     (1 - instr__reset) * (_input_0' - _input_0) = 0;
     pol commit pc_update;
-    pc_update = instr__jump_to_operation * _operation_id + instr__loop * pc + instr_return * 0 + (1 - (instr__jump_to_operation + instr__loop + instr_return)) * (pc + 1);
+    pc_update = instr__jump_to_operation * _operation_id + instr__loop * pc + (1 - (instr__jump_to_operation + instr__loop + instr_return)) * (pc + 1);
     pc' = (1 - first_step') * pc_update;
+    instr__jump_to_operation * (1 - instr__jump_to_operation) = 0;
+    instr__loop * (1 - instr__loop) = 0;
+    instr_return * (1 - instr_return) = 0;
+    (instr__jump_to_operation + instr__loop + instr_return) * (1 - (instr__jump_to_operation + instr__loop + instr_return)) = 0;
     pol commit _output_0_free_value;
-    1 $ [0, pc, instr__jump_to_operation, instr__reset, instr__loop, instr_return, _output_0_const, _output_0_read_free, read__output_0_pc, read__output_0__input_0] in main_sub__rom::latch $ [main_sub__rom::operation_id, main_sub__rom::p_line, main_sub__rom::p_instr__jump_to_operation, main_sub__rom::p_instr__reset, main_sub__rom::p_instr__loop, main_sub__rom::p_instr_return, main_sub__rom::p__output_0_const, main_sub__rom::p__output_0_read_free, main_sub__rom::p_read__output_0_pc, main_sub__rom::p_read__output_0__input_0];
+    1 $ [0, pc, instr__jump_to_operation, instr__reset, instr__loop, instr_return, _output_0_const, _output_0_read_free, read__output_0__input_0] in main_sub__rom::latch $ [main_sub__rom::operation_id, main_sub__rom::p_line, main_sub__rom::p_instr__jump_to_operation, main_sub__rom::p_instr__reset, main_sub__rom::p_instr__loop, main_sub__rom::p_instr_return, main_sub__rom::p__output_0_const, main_sub__rom::p__output_0_read_free, main_sub__rom::p_read__output_0__input_0];
 namespace main_sub__rom(16);
     pol constant p_line = [0, 1, 2, 3, 4, 5] + [5]*;
     pol constant p__output_0_const = [0, 0, 0, 0, 1, 0] + [0]*;
@@ -648,7 +1338,6 @@ namespace main_sub__rom(16);
     pol constant p_instr__reset = [1, 0, 0, 0, 0, 0] + [0]*;
     pol constant p_instr_return = [0, 0, 1, 1, 1, 0] + [0]*;
     pol constant p_read__output_0__input_0 = [0, 0, 1, 0, 0, 0] + [0]*;
-    pol constant p_read__output_0_pc = [0]*;
     pol constant operation_id = [0]*;
     pol constant latch = [1]*;
 "#;
@@ -694,14 +1383,25 @@ namespace main_sub__rom(16);
     pol commit X_read_free;
     pol commit read_X_A;
     pol commit read_X_CNT;
-    pol commit read_X_pc;
-    X = read_X_A * A + read_X_CNT * CNT + read_X_pc * pc + X_const + X_read_free * X_free_value;
+    X = read_X_A * A + read_X_CNT * CNT + X_const + X_read_free * X_free_value;
     pol constant first_step = [1] + [0]*;
-    A' = reg_write_X_A * X + instr__reset * 0 + (1 - (reg_write_X_A + instr__reset)) * A;
-    CNT' = reg_write_X_CNT * X + instr_dec_CNT * (CNT - 1) + instr__reset * 0 + (1 - (reg_write_X_CNT + instr_dec_CNT + instr__reset)) * CNT;
+    A' = reg_write_X_A * X + (1 - (reg_write_X_A + instr__reset)) * A;
+    reg_write_X_A * (1 - reg_write_X_A) = 0;
+    instr__reset * (1 - instr__reset) = 0;
+    (reg_write_X_A + instr__reset) * (1 - (reg_write_X_A + instr__reset)) = 0;
+    CNT' = reg_write_X_CNT * X + instr_dec_CNT * (CNT - 1) + (1 - (reg_write_X_CNT + instr_dec_CNT + instr__reset)) * CNT;
+    reg_write_X_CNT * (1 - reg_write_X_CNT) = 0;
+    instr_dec_CNT * (1 - instr_dec_CNT) = 0;
+    (reg_write_X_CNT + instr_dec_CNT + instr__reset) * (1 - (reg_write_X_CNT + instr_dec_CNT + instr__reset)) = 0;
     pol commit pc_update;
-    pc_update = instr_jmpz * (instr_jmpz_pc_update + instr_jmpz_pc_update_1) + instr_jmp * instr_jmp_param_l + instr__jump_to_operation * _operation_id + instr__loop * pc + instr_return * 0 + (1 - (instr_jmpz + instr_jmp + instr__jump_to_operation + instr__loop + instr_return)) * (pc + 1);
+    pc_update = instr_jmpz * (instr_jmpz_pc_update + instr_jmpz_pc_update_1) + instr_jmp * instr_jmp_param_l + instr__jump_to_operation * _operation_id + instr__loop * pc + (1 - (instr_jmpz + instr_jmp + instr__jump_to_operation + instr__loop + instr_return)) * (pc + 1);
     pc' = (1 - first_step') * pc_update;
+    instr_jmpz * (1 - instr_jmpz) = 0;
+    instr_jmp * (1 - instr_jmp) = 0;
+    instr__jump_to_operation * (1 - instr__jump_to_operation) = 0;
+    instr__loop * (1 - instr__loop) = 0;
+    instr_return * (1 - instr_return) = 0;
+    (instr_jmpz + instr_jmp + instr__jump_to_operation + instr__loop + instr_return) * (1 - (instr_jmpz + instr_jmp + instr__jump_to_operation + instr__loop + instr_return)) = 0;
     pol commit X_free_value;
     query |__i| std::prover::handle_query(X_free_value, __i, match std::prover::eval(pc) {
         2 => std::prelude::Query::Input(0, 2),
@@ -709,7 +1409,7 @@ namespace main_sub__rom(16);
         7 => std::prelude::Query::Input(0, 1),
         _ => std::prelude::Query::None,
     });
-    1 $ [0, pc, reg_write_X_A, reg_write_X_CNT, instr_jmpz, instr_jmpz_param_l, instr_jmp, instr_jmp_param_l, instr_dec_CNT, instr_assert_zero, instr__jump_to_operation, instr__reset, instr__loop, instr_return, X_const, X_read_free, read_X_A, read_X_CNT, read_X_pc] in main__rom::latch $ [main__rom::operation_id, main__rom::p_line, main__rom::p_reg_write_X_A, main__rom::p_reg_write_X_CNT, main__rom::p_instr_jmpz, main__rom::p_instr_jmpz_param_l, main__rom::p_instr_jmp, main__rom::p_instr_jmp_param_l, main__rom::p_instr_dec_CNT, main__rom::p_instr_assert_zero, main__rom::p_instr__jump_to_operation, main__rom::p_instr__reset, main__rom::p_instr__loop, main__rom::p_instr_return, main__rom::p_X_const, main__rom::p_X_read_free, main__rom::p_read_X_A, main__rom::p_read_X_CNT, main__rom::p_read_X_pc];
+    1 $ [0, pc, reg_write_X_A, reg_write_X_CNT, instr_jmpz, instr_jmpz_param_l, instr_jmp, instr_jmp_param_l, instr_dec_CNT, instr_assert_zero, instr__jump_to_operation, instr__reset, instr__loop, instr_return, X_const, X_read_free, read_X_A, read_X_CNT] in main__rom::latch $ [main__rom::operation_id, main__rom::p_line, main__rom::p_reg_write_X_A, main__rom::p_reg_write_X_CNT, main__rom::p_instr_jmpz, main__rom::p_instr_jmpz_param_l, main__rom::p_instr_jmp, main__rom::p_instr_jmp_param_l, main__rom::p_instr_dec_CNT, main__rom::p_instr_assert_zero, main__rom::p_instr__jump_to_operation, main__rom::p_instr__reset, main__rom::p_instr__loop, main__rom::p_instr_return, main__rom::p_X_const, main__rom::p_X_read_free, main__rom::p_read_X_A, main__rom::p_read_X_CNT];
     pol constant _linker_first_step(i) { if i == 0 { 1 } else { 0 } };
     _linker_first_step * (_operation_id - 2) = 0;
 namespace main__rom(16);
@@ -728,7 +1428,6 @@ namespace main__rom(16);
     pol constant p_instr_return = [0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0] + [0]*;
     pol constant p_read_X_A = [0, 0, 0, 0, 1, 0, 0, 1, 1, 0, 0] + [0]*;
     pol constant p_read_X_CNT = [0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0] + [0]*;
-    pol constant p_read_X_pc = [0]*;
     pol constant p_reg_write_X_A = [0, 0, 0, 0, 1, 0, 0, 1, 0, 0, 0] + [0]*;
     pol constant p_reg_write_X_CNT = [0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0] + [0]*;
     pol constant operation_id = [0]*;
@@ -740,6 +1439,40 @@ namespace main__rom(16);
         assert_eq!(extract_main(&format!("{pil}")), expectation);
     }
 
+    #[test]
+    fn compile_simple_sum_golden() {
+        let file_name = "../test_data/asm/simple_sum.asm";
+        let graph = parse_analyze_and_compile_file::<GoldilocksField>(file_name);
+        let pil = link_native(graph).unwrap();
+        assert_pil_matches_golden(&pil, "../test_data/golden/simple_sum.pil");
+    }
+
+    #[test]
+    fn annotated_simple_sum() {
+        let file_name = "../test_data/asm/simple_sum.asm";
+        let graph = parse_analyze_and_compile_file::<GoldilocksField>(file_name);
+        let pil = link_native(graph).unwrap();
+        let annotated = super::format_pil_annotated(&pil);
+
+        assert!(annotated.contains("// ===== Machine main =====\nnamespace main(16);\n"));
+        assert!(annotated.contains("// ===== Machine main__rom =====\n"));
+        for section in ["Fixed columns", "Witness columns", "Constraints"] {
+            assert!(annotated.contains(&format!("    // {section}\n")));
+        }
+
+        // The annotated PIL contains the same statements.
+        let reparsed = powdr_parser::parse(None, &annotated).unwrap();
+        let pil = powdr_parser::parse(None, &pil.to_string()).unwrap();
+        let statements = |pil: &PILFile| {
+            pil.0
+                .iter()
+                .map(ToString::to_string)
+                .sorted()
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(statements(&reparsed), statements(&pil));
+    }
+
     #[test]
     fn compile_literal_number_args() {
         let source = r#"
@@ -775,10 +1508,18 @@ machine Machine with min_degree: 32, max_degree: 64 {
     pol commit instr__loop;
     pol commit instr_return;
     pol constant first_step = [1] + [0]*;
-    fp' = instr_inc_fp * (fp + instr_inc_fp_param_amount) + instr_adjust_fp * (fp + instr_adjust_fp_param_amount) + instr__reset * 0 + (1 - (instr_inc_fp + instr_adjust_fp + instr__reset)) * fp;
+    fp' = instr_inc_fp * (fp + instr_inc_fp_param_amount) + instr_adjust_fp * (fp + instr_adjust_fp_param_amount) + (1 - (instr_inc_fp + instr_adjust_fp + instr__reset)) * fp;
+    instr_inc_fp * (1 - instr_inc_fp) = 0;
+    instr_adjust_fp * (1 - instr_adjust_fp) = 0;
+    instr__reset * (1 - instr__reset) = 0;
+    (instr_inc_fp + instr_adjust_fp + instr__reset) * (1 - (instr_inc_fp + instr_adjust_fp + instr__reset)) = 0;
     pol commit pc_update;
-    pc_update = instr_adjust_fp * instr_adjust_fp_param_t + instr__jump_to_operation * _operation_id + instr__loop * pc + instr_return * 0 + (1 - (instr_adjust_fp + instr__jump_to_operation + instr__loop + instr_return)) * (pc + 1);
+    pc_update = instr_adjust_fp * instr_adjust_fp_param_t + instr__jump_to_operation * _operation_id + instr__loop * pc + (1 - (instr_adjust_fp + instr__jump_to_operation + instr__loop + instr_return)) * (pc + 1);
     pc' = (1 - first_step') * pc_update;
+    instr__jump_to_operation * (1 - instr__jump_to_operation) = 0;
+    instr__loop * (1 - instr__loop) = 0;
+    instr_return * (1 - instr_return) = 0;
+    (instr_adjust_fp + instr__jump_to_operation + instr__loop + instr_return) * (1 - (instr_adjust_fp + instr__jump_to_operation + instr__loop + instr_return)) = 0;
     1 $ [0, pc, instr_inc_fp, instr_inc_fp_param_amount, instr_adjust_fp, instr_adjust_fp_param_amount, instr_adjust_fp_param_t, instr__jump_to_operation, instr__reset, instr__loop, instr_return] in main__rom::latch $ [main__rom::operation_id, main__rom::p_line, main__rom::p_instr_inc_fp, main__rom::p_instr_inc_fp_param_amount, main__rom::p_instr_adjust_fp, main__rom::p_instr_adjust_fp_param_amount, main__rom::p_instr_adjust_fp_param_t, main__rom::p_instr__jump_to_operation, main__rom::p_instr__reset, main__rom::p_instr__loop, main__rom::p_instr_return];
     pol constant _linker_first_step(i) { if i == 0 { 1 } else { 0 } };
     _linker_first_step * (_operation_id - 2) = 0;
@@ -866,17 +1607,23 @@ machine Main with min_degree: 32, max_degree: 64 {
     pol commit instr_return;
     pol commit X_const;
     pol commit X_read_free;
-    pol commit read_X_A;
-    pol commit read_X_pc;
-    X = read_X_A * A + read_X_pc * pc + X_const + X_read_free * X_free_value;
+    X = X_const + X_read_free * X_free_value;
     pol constant first_step = [1] + [0]*;
-    A' = reg_write_X_A * X + instr_add5_into_A * A' + instr__reset * 0 + (1 - (reg_write_X_A + instr_add5_into_A + instr__reset)) * A;
+    A' = reg_write_X_A * X + instr_add5_into_A * A' + (1 - (reg_write_X_A + instr_add5_into_A + instr__reset)) * A;
+    reg_write_X_A * (1 - reg_write_X_A) = 0;
+    instr_add5_into_A * (1 - instr_add5_into_A) = 0;
+    instr__reset * (1 - instr__reset) = 0;
+    (reg_write_X_A + instr_add5_into_A + instr__reset) * (1 - (reg_write_X_A + instr_add5_into_A + instr__reset)) = 0;
     pol commit pc_update;
-    pc_update = instr__jump_to_operation * _operation_id + instr__loop * pc + instr_return * 0 + (1 - (instr__jump_to_operation + instr__loop + instr_return)) * (pc + 1);
+    pc_update = instr__jump_to_operation * _operation_id + instr__loop * pc + (1 - (instr__jump_to_operation + instr__loop + instr_return)) * (pc + 1);
     pc' = (1 - first_step') * pc_update;
+    instr__jump_to_operation * (1 - instr__jump_to_operation) = 0;
+    instr__loop * (1 - instr__loop) = 0;
+    instr_return * (1 - instr_return) = 0;
+    (instr__jump_to_operation + instr__loop + instr_return) * (1 - (instr__jump_to_operation + instr__loop + instr_return)) = 0;
     pol commit X_free_value;
     instr_add5_into_A $ [0, X, A'] in main_vm::latch $ [main_vm::operation_id, main_vm::x, main_vm::y];
-    1 $ [0, pc, reg_write_X_A, instr_add5_into_A, instr__jump_to_operation, instr__reset, instr__loop, instr_return, X_const, X_read_free, read_X_A, read_X_pc] in main__rom::latch $ [main__rom::operation_id, main__rom::p_line, main__rom::p_reg_write_X_A, main__rom::p_instr_add5_into_A, main__rom::p_instr__jump_to_operation, main__rom::p_instr__reset, main__rom::p_instr__loop, main__rom::p_instr_return, main__rom::p_X_const, main__rom::p_X_read_free, main__rom::p_read_X_A, main__rom::p_read_X_pc];
+    1 $ [0, pc, reg_write_X_A, instr_add5_into_A, instr__jump_to_operation, instr__reset, instr__loop, instr_return, X_const, X_read_free] in main__rom::latch $ [main__rom::operation_id, main__rom::p_line, main__rom::p_reg_write_X_A, main__rom::p_instr_add5_into_A, main__rom::p_instr__jump_to_operation, main__rom::p_instr__reset, main__rom::p_instr__loop, main__rom::p_instr_return, main__rom::p_X_const, main__rom::p_X_read_free];
     pol constant _linker_first_step(i) { if i == 0 { 1 } else { 0 } };
     _linker_first_step * (_operation_id - 2) = 0;
 namespace main__rom(4);
@@ -888,8 +1635,6 @@ namespace main__rom(4);
     pol constant p_instr__reset = [1, 0, 0, 0] + [0]*;
     pol constant p_instr_add5_into_A = [0, 0, 1, 0] + [0]*;
     pol constant p_instr_return = [0]*;
-    pol constant p_read_X_A = [0]*;
-    pol constant p_read_X_pc = [0]*;
     pol constant p_reg_write_X_A = [0]*;
     pol constant operation_id = [0]*;
     pol constant latch = [1]*;
@@ -905,6 +1650,70 @@ namespace main_vm(64..128);
         assert_eq!(extract_main(&(pil.to_string())), expected);
     }
 
+    #[test]
+    fn inline_submachine() {
+        let asm = r"
+machine SubVM with latch: latch, operation_id: operation_id, min_degree: 64, max_degree: 128 {
+    operation add5<0> x -> y;
+
+    col witness operation_id;
+    col fixed latch = [1]*;
+
+    col witness x;
+    col witness y;
+
+    y = x + 5;
+}
+
+machine Main with min_degree: 32, max_degree: 64 {
+    reg pc[@pc];
+    reg X[<=];
+    reg A;
+
+    SubVM vm;
+
+    instr add5_into_A X link => A' = vm.add5(X);
+
+    function main {
+        add5_into_A 10; // A <== 15
+    }
+}
+";
+        let graph = parse_analyze_and_compile::<GoldilocksField>(asm);
+        // The ROM is not inlined because its fixed columns are not constant.
+        assert_eq!(
+            super::inlinable_machines(&graph.objects, &graph.main.location, 10),
+            [Location::main().join("vm")].into()
+        );
+        let pil = super::link(
+            graph,
+            super::LinkerParams {
+                inline_threshold: Some(10),
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .to_string();
+
+        assert!(!pil.contains("namespace main_vm"));
+        assert!(!pil.contains("main_vm::"));
+        let main = extract_main(&pil);
+        for statement in [
+            "pol commit main_vm_0_operation_id;",
+            "pol constant main_vm_0_latch = [1]*;",
+            "pol commit main_vm_0_x;",
+            "main_vm_0_y = main_vm_0_x + 5;",
+            "instr_add5_into_A * (1 - main_vm_0_latch) = 0;",
+            "instr_add5_into_A * (0 - main_vm_0_operation_id) = 0;",
+            "instr_add5_into_A * (X - main_vm_0_x) = 0;",
+            "instr_add5_into_A * (A' - main_vm_0_y) = 0;",
+        ] {
+            assert!(main.contains(statement), "{statement} not found in {main}");
+        }
+        // The ROM is still linked by a lookup.
+        assert!(main.contains("in main__rom::latch $ ["));
+    }
+
     #[test]
     fn permutation_instructions() {
         let expected = r#"namespace main(256);
@@ -935,31 +1744,36 @@ namespace main_vm(64..128);
     pol commit X_const;
     pol commit X_read_free;
     pol commit read_X_A;
-    pol commit read_X_B;
-    pol commit read_X_pc;
-    X = read_X_A * A + read_X_B * B + read_X_pc * pc + X_const + X_read_free * X_free_value;
+    X = read_X_A * A + X_const + X_read_free * X_free_value;
     pol commit Y_const;
     pol commit Y_read_free;
-    pol commit read_Y_A;
-    pol commit read_Y_B;
-    pol commit read_Y_pc;
-    Y = read_Y_A * A + read_Y_B * B + read_Y_pc * pc + Y_const + Y_read_free * Y_free_value;
+    Y = Y_const + Y_read_free * Y_free_value;
     pol commit Z_const;
     pol commit Z_read_free;
-    pol commit read_Z_A;
-    pol commit read_Z_B;
-    pol commit read_Z_pc;
-    Z = read_Z_A * A + read_Z_B * B + read_Z_pc * pc + Z_const + Z_read_free * Z_free_value;
+    Z = Z_const + Z_read_free * Z_free_value;
     pol constant first_step = [1] + [0]*;
-    A' = reg_write_X_A * X + reg_write_Y_A * Y + reg_write_Z_A * Z + instr__reset * 0 + (1 - (reg_write_X_A + reg_write_Y_A + reg_write_Z_A + instr__reset)) * A;
-    B' = reg_write_X_B * X + reg_write_Y_B * Y + reg_write_Z_B * Z + instr__reset * 0 + (1 - (reg_write_X_B + reg_write_Y_B + reg_write_Z_B + instr__reset)) * B;
+    A' = reg_write_X_A * X + reg_write_Y_A * Y + reg_write_Z_A * Z + (1 - (reg_write_X_A + reg_write_Y_A + reg_write_Z_A + instr__reset)) * A;
+    reg_write_X_A * (1 - reg_write_X_A) = 0;
+    reg_write_Y_A * (1 - reg_write_Y_A) = 0;
+    reg_write_Z_A * (1 - reg_write_Z_A) = 0;
+    instr__reset * (1 - instr__reset) = 0;
+    (reg_write_X_A + reg_write_Y_A + reg_write_Z_A + instr__reset) * (1 - (reg_write_X_A + reg_write_Y_A + reg_write_Z_A + instr__reset)) = 0;
+    B' = reg_write_X_B * X + reg_write_Y_B * Y + reg_write_Z_B * Z + (1 - (reg_write_X_B + reg_write_Y_B + reg_write_Z_B + instr__reset)) * B;
+    reg_write_X_B * (1 - reg_write_X_B) = 0;
+    reg_write_Y_B * (1 - reg_write_Y_B) = 0;
+    reg_write_Z_B * (1 - reg_write_Z_B) = 0;
+    (reg_write_X_B + reg_write_Y_B + reg_write_Z_B + instr__reset) * (1 - (reg_write_X_B + reg_write_Y_B + reg_write_Z_B + instr__reset)) = 0;
     pol commit pc_update;
-    pc_update = instr__jump_to_operation * _operation_id + instr__loop * pc + instr_return * 0 + (1 - (instr__jump_to_operation + instr__loop + instr_return)) * (pc + 1);
+    pc_update = instr__jump_to_operation * _operation_id + instr__loop * pc + (1 - (instr__jump_to_operation + instr__loop + instr_return)) * (pc + 1);
     pc' = (1 - first_step') * pc_update;
+    instr__jump_to_operation * (1 - instr__jump_to_operation) = 0;
+    instr__loop * (1 - instr__loop) = 0;
+    instr_return * (1 - instr_return) = 0;
+    (instr__jump_to_operation + instr__loop + instr_return) * (1 - (instr__jump_to_operation + instr__loop + instr_return)) = 0;
     pol commit X_free_value;
     pol commit Y_free_value;
     pol commit Z_free_value;
-    1 $ [0, pc, reg_write_X_A, reg_write_Y_A, reg_write_Z_A, reg_write_X_B, reg_write_Y_B, reg_write_Z_B, instr_or, instr_assert_eq, instr__jump_to_operation, instr__reset, instr__loop, instr_return, X_const, X_read_free, read_X_A, read_X_B, read_X_pc, Y_const, Y_read_free, read_Y_A, read_Y_B, read_Y_pc, Z_const, Z_read_free, read_Z_A, read_Z_B, read_Z_pc] in main__rom::latch $ [main__rom::operation_id, main__rom::p_line, main__rom::p_reg_write_X_A, main__rom::p_reg_write_Y_A, main__rom::p_reg_write_Z_A, main__rom::p_reg_write_X_B, main__rom::p_reg_write_Y_B, main__rom::p_reg_write_Z_B, main__rom::p_instr_or, main__rom::p_instr_assert_eq, main__rom::p_instr__jump_to_operation, main__rom::p_instr__reset, main__rom::p_instr__loop, main__rom::p_instr_return, main__rom::p_X_const, main__rom::p_X_read_free, main__rom::p_read_X_A, main__rom::p_read_X_B, main__rom::p_read_X_pc, main__rom::p_Y_const, main__rom::p_Y_read_free, main__rom::p_read_Y_A, main__rom::p_read_Y_B, main__rom::p_read_Y_pc, main__rom::p_Z_const, main__rom::p_Z_read_free, main__rom::p_read_Z_A, main__rom::p_read_Z_B, main__rom::p_read_Z_pc];
+    1 $ [0, pc, reg_write_X_A, reg_write_Y_A, reg_write_Z_A, reg_write_X_B, reg_write_Y_B, reg_write_Z_B, instr_or, instr_assert_eq, instr__jump_to_operation, instr__reset, instr__loop, instr_return, X_const, X_read_free, read_X_A, Y_const, Y_read_free, Z_const, Z_read_free] in main__rom::latch $ [main__rom::operation_id, main__rom::p_line, main__rom::p_reg_write_X_A, main__rom::p_reg_write_Y_A, main__rom::p_reg_write_Z_A, main__rom::p_reg_write_X_B, main__rom::p_reg_write_Y_B, main__rom::p_reg_write_Z_B, main__rom::p_instr_or, main__rom::p_instr_assert_eq, main__rom::p_instr__jump_to_operation, main__rom::p_instr__reset, main__rom::p_instr__loop, main__rom::p_instr_return, main__rom::p_X_const, main__rom::p_X_read_free, main__rom::p_read_X_A, main__rom::p_Y_const, main__rom::p_Y_read_free, main__rom::p_Z_const, main__rom::p_Z_read_free];
     instr_or $ [0, X, Y, Z] is main_bin::latch * main_bin::sel[0] $ [main_bin::operation_id, main_bin::A, main_bin::B, main_bin::C];
     pol constant _linker_first_step(i) { if i == 0 { 1 } else { 0 } };
     _linker_first_step * (_operation_id - 2) = 0;
@@ -978,14 +1792,6 @@ namespace main__rom(256);
     pol constant p_instr_or = [0, 0, 1, 0, 1, 0, 1, 0, 0, 0] + [0]*;
     pol constant p_instr_return = [0, 0, 0, 0, 0, 0, 0, 0, 1, 0] + [0]*;
     pol constant p_read_X_A = [0, 0, 0, 1, 0, 1, 0, 1, 0, 0] + [0]*;
-    pol constant p_read_X_B = [0]*;
-    pol constant p_read_X_pc = [0]*;
-    pol constant p_read_Y_A = [0]*;
-    pol constant p_read_Y_B = [0]*;
-    pol constant p_read_Y_pc = [0]*;
-    pol constant p_read_Z_A = [0]*;
-    pol constant p_read_Z_B = [0]*;
-    pol constant p_read_Z_pc = [0]*;
     pol constant p_reg_write_X_A = [0]*;
     pol constant p_reg_write_X_B = [0]*;
     pol constant p_reg_write_Y_A = [0]*;
@@ -1026,6 +1832,26 @@ namespace main_bin_o(256);
         assert_eq!(extract_main(&format!("{pil}")), expected);
     }
 
+    #[test]
+    fn permutation_links_as_lookups() {
+        let file_name = "../test_data/asm/permutations/vm_to_block.asm";
+        let graph = parse_analyze_and_compile_file::<GoldilocksField>(file_name);
+        let pil = super::link(
+            graph,
+            super::LinkerParams {
+                degree_mode: super::DegreeMode::Monolithic,
+                lookups_only: true,
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .to_string();
+        assert!(pil.contains(
+            "instr_or $ [0, X, Y, Z] in main_bin::latch $ [main_bin::operation_id, main_bin::A, main_bin::B, main_bin::C];"
+        ));
+        assert!(!pil.contains(" is main_bin::"));
+    }
+
     #[test]
     fn link_merging() {
         let expected = r#"namespace main(32);
@@ -1072,45 +1898,53 @@ namespace main_bin_o(256);
     pol commit X_const;
     pol commit X_read_free;
     pol commit read_X_A;
-    pol commit read_X_B;
     pol commit read_X_C;
-    pol commit read_X_pc;
-    X = read_X_A * A + read_X_B * B + read_X_C * C + read_X_pc * pc + X_const + X_read_free * X_free_value;
+    X = read_X_A * A + read_X_C * C + X_const + X_read_free * X_free_value;
     pol commit Y_const;
     pol commit Y_read_free;
-    pol commit read_Y_A;
-    pol commit read_Y_B;
-    pol commit read_Y_C;
-    pol commit read_Y_pc;
-    Y = read_Y_A * A + read_Y_B * B + read_Y_C * C + read_Y_pc * pc + Y_const + Y_read_free * Y_free_value;
+    Y = Y_const + Y_read_free * Y_free_value;
     pol commit Z_const;
     pol commit Z_read_free;
-    pol commit read_Z_A;
-    pol commit read_Z_B;
-    pol commit read_Z_C;
-    pol commit read_Z_pc;
-    Z = read_Z_A * A + read_Z_B * B + read_Z_C * C + read_Z_pc * pc + Z_const + Z_read_free * Z_free_value;
+    Z = Z_const + Z_read_free * Z_free_value;
     pol commit W_const;
     pol commit W_read_free;
-    pol commit read_W_A;
-    pol commit read_W_B;
-    pol commit read_W_C;
-    pol commit read_W_pc;
-    W = read_W_A * A + read_W_B * B + read_W_C * C + read_W_pc * pc + W_const + W_read_free * W_free_value;
+    W = W_const + W_read_free * W_free_value;
     pol constant first_step = [1] + [0]*;
-    A' = reg_write_X_A * X + reg_write_Y_A * Y + reg_write_Z_A * Z + reg_write_W_A * W + instr_add_to_A * A' + instr_add_BC_to_A * A' + instr__reset * 0 + (1 - (reg_write_X_A + reg_write_Y_A + reg_write_Z_A + reg_write_W_A + instr_add_to_A + instr_add_BC_to_A + instr__reset)) * A;
-    B' = reg_write_X_B * X + reg_write_Y_B * Y + reg_write_Z_B * Z + reg_write_W_B * W + instr__reset * 0 + (1 - (reg_write_X_B + reg_write_Y_B + reg_write_Z_B + reg_write_W_B + instr__reset)) * B;
-    C' = reg_write_X_C * X + reg_write_Y_C * Y + reg_write_Z_C * Z + reg_write_W_C * W + instr__reset * 0 + (1 - (reg_write_X_C + reg_write_Y_C + reg_write_Z_C + reg_write_W_C + instr__reset)) * C;
+    A' = reg_write_X_A * X + reg_write_Y_A * Y + reg_write_Z_A * Z + reg_write_W_A * W + instr_add_to_A * A' + instr_add_BC_to_A * A' + (1 - (reg_write_X_A + reg_write_Y_A + reg_write_Z_A + reg_write_W_A + instr_add_to_A + instr_add_BC_to_A + instr__reset)) * A;
+    reg_write_X_A * (1 - reg_write_X_A) = 0;
+    reg_write_Y_A * (1 - reg_write_Y_A) = 0;
+    reg_write_Z_A * (1 - reg_write_Z_A) = 0;
+    reg_write_W_A * (1 - reg_write_W_A) = 0;
+    instr_add_to_A * (1 - instr_add_to_A) = 0;
+    instr_add_BC_to_A * (1 - instr_add_BC_to_A) = 0;
+    instr__reset * (1 - instr__reset) = 0;
+    (reg_write_X_A + reg_write_Y_A + reg_write_Z_A + reg_write_W_A + instr_add_to_A + instr_add_BC_to_A + instr__reset) * (1 - (reg_write_X_A + reg_write_Y_A + reg_write_Z_A + reg_write_W_A + instr_add_to_A + instr_add_BC_to_A + instr__reset)) = 0;
+    B' = reg_write_X_B * X + reg_write_Y_B * Y + reg_write_Z_B * Z + reg_write_W_B * W + (1 - (reg_write_X_B + reg_write_Y_B + reg_write_Z_B + reg_write_W_B + instr__reset)) * B;
+    reg_write_X_B * (1 - reg_write_X_B) = 0;
+    reg_write_Y_B * (1 - reg_write_Y_B) = 0;
+    reg_write_Z_B * (1 - reg_write_Z_B) = 0;
+    reg_write_W_B * (1 - reg_write_W_B) = 0;
+    (reg_write_X_B + reg_write_Y_B + reg_write_Z_B + reg_write_W_B + instr__reset) * (1 - (reg_write_X_B + reg_write_Y_B + reg_write_Z_B + reg_write_W_B + instr__reset)) = 0;
+    C' = reg_write_X_C * X + reg_write_Y_C * Y + reg_write_Z_C * Z + reg_write_W_C * W + (1 - (reg_write_X_C + reg_write_Y_C + reg_write_Z_C + reg_write_W_C + instr__reset)) * C;
+    reg_write_X_C * (1 - reg_write_X_C) = 0;
+    reg_write_Y_C * (1 - reg_write_Y_C) = 0;
+    reg_write_Z_C * (1 - reg_write_Z_C) = 0;
+    reg_write_W_C * (1 - reg_write_W_C) = 0;
+    (reg_write_X_C + reg_write_Y_C + reg_write_Z_C + reg_write_W_C + instr__reset) * (1 - (reg_write_X_C + reg_write_Y_C + reg_write_Z_C + reg_write_W_C + instr__reset)) = 0;
     pol commit pc_update;
-    pc_update = instr__jump_to_operation * _operation_id + instr__loop * pc + instr_return * 0 + (1 - (instr__jump_to_operation + instr__loop + instr_return)) * (pc + 1);
+    pc_update = instr__jump_to_operation * _operation_id + instr__loop * pc + (1 - (instr__jump_to_operation + instr__loop + instr_return)) * (pc + 1);
     pc' = (1 - first_step') * pc_update;
+    instr__jump_to_operation * (1 - instr__jump_to_operation) = 0;
+    instr__loop * (1 - instr__loop) = 0;
+    instr_return * (1 - instr_return) = 0;
+    (instr__jump_to_operation + instr__loop + instr_return) * (1 - (instr__jump_to_operation + instr__loop + instr_return)) = 0;
     pol commit X_free_value;
     pol commit Y_free_value;
     pol commit Z_free_value;
     pol commit W_free_value;
     instr_add_to_A $ [0, X, Y, A'] in main_submachine::latch $ [main_submachine::operation_id, main_submachine::x, main_submachine::y, main_submachine::z];
     instr_add_BC_to_A $ [0, B, C, A'] in main_submachine::latch $ [main_submachine::operation_id, main_submachine::x, main_submachine::y, main_submachine::z];
-    1 $ [0, pc, reg_write_X_A, reg_write_Y_A, reg_write_Z_A, reg_write_W_A, reg_write_X_B, reg_write_Y_B, reg_write_Z_B, reg_write_W_B, reg_write_X_C, reg_write_Y_C, reg_write_Z_C, reg_write_W_C, instr_add, instr_sub_with_add, instr_addAB, instr_add3, instr_add_to_A, instr_add_BC_to_A, instr_sub, instr_add_with_sub, instr_assert_eq, instr__jump_to_operation, instr__reset, instr__loop, instr_return, X_const, X_read_free, read_X_A, read_X_B, read_X_C, read_X_pc, Y_const, Y_read_free, read_Y_A, read_Y_B, read_Y_C, read_Y_pc, Z_const, Z_read_free, read_Z_A, read_Z_B, read_Z_C, read_Z_pc, W_const, W_read_free, read_W_A, read_W_B, read_W_C, read_W_pc] in main__rom::latch $ [main__rom::operation_id, main__rom::p_line, main__rom::p_reg_write_X_A, main__rom::p_reg_write_Y_A, main__rom::p_reg_write_Z_A, main__rom::p_reg_write_W_A, main__rom::p_reg_write_X_B, main__rom::p_reg_write_Y_B, main__rom::p_reg_write_Z_B, main__rom::p_reg_write_W_B, main__rom::p_reg_write_X_C, main__rom::p_reg_write_Y_C, main__rom::p_reg_write_Z_C, main__rom::p_reg_write_W_C, main__rom::p_instr_add, main__rom::p_instr_sub_with_add, main__rom::p_instr_addAB, main__rom::p_instr_add3, main__rom::p_instr_add_to_A, main__rom::p_instr_add_BC_to_A, main__rom::p_instr_sub, main__rom::p_instr_add_with_sub, main__rom::p_instr_assert_eq, main__rom::p_instr__jump_to_operation, main__rom::p_instr__reset, main__rom::p_instr__loop, main__rom::p_instr_return, main__rom::p_X_const, main__rom::p_X_read_free, main__rom::p_read_X_A, main__rom::p_read_X_B, main__rom::p_read_X_C, main__rom::p_read_X_pc, main__rom::p_Y_const, main__rom::p_Y_read_free, main__rom::p_read_Y_A, main__rom::p_read_Y_B, main__rom::p_read_Y_C, main__rom::p_read_Y_pc, main__rom::p_Z_const, main__rom::p_Z_read_free, main__rom::p_read_Z_A, main__rom::p_read_Z_B, main__rom::p_read_Z_C, main__rom::p_read_Z_pc, main__rom::p_W_const, main__rom::p_W_read_free, main__rom::p_read_W_A, main__rom::p_read_W_B, main__rom::p_read_W_C, main__rom::p_read_W_pc];
+    1 $ [0, pc, reg_write_X_A, reg_write_Y_A, reg_write_Z_A, reg_write_W_A, reg_write_X_B, reg_write_Y_B, reg_write_Z_B, reg_write_W_B, reg_write_X_C, reg_write_Y_C, reg_write_Z_C, reg_write_W_C, instr_add, instr_sub_with_add, instr_addAB, instr_add3, instr_add_to_A, instr_add_BC_to_A, instr_sub, instr_add_with_sub, instr_assert_eq, instr__jump_to_operation, instr__reset, instr__loop, instr_return, X_const, X_read_free, read_X_A, read_X_C, Y_const, Y_read_free, Z_const, Z_read_free, W_const, W_read_free] in main__rom::latch $ [main__rom::operation_id, main__rom::p_line, main__rom::p_reg_write_X_A, main__rom::p_reg_write_Y_A, main__rom::p_reg_write_Z_A, main__rom::p_reg_write_W_A, main__rom::p_reg_write_X_B, main__rom::p_reg_write_Y_B, main__rom::p_reg_write_Z_B, main__rom::p_reg_write_W_B, main__rom::p_reg_write_X_C, main__rom::p_reg_write_Y_C, main__rom::p_reg_write_Z_C, main__rom::p_reg_write_W_C, main__rom::p_instr_add, main__rom::p_instr_sub_with_add, main__rom::p_instr_addAB, main__rom::p_instr_add3, main__rom::p_instr_add_to_A, main__rom::p_instr_add_BC_to_A, main__rom::p_instr_sub, main__rom::p_instr_add_with_sub, main__rom::p_instr_assert_eq, main__rom::p_instr__jump_to_operation, main__rom::p_instr__reset, main__rom::p_instr__loop, main__rom::p_instr_return, main__rom::p_X_const, main__rom::p_X_read_free, main__rom::p_read_X_A, main__rom::p_read_X_C, main__rom::p_Y_const, main__rom::p_Y_read_free, main__rom::p_Z_const, main__rom::p_Z_read_free, main__rom::p_W_const, main__rom::p_W_read_free];
     instr_add + instr_add3 + instr_addAB + instr_sub_with_add $ [0, X * instr_add + X * instr_add3 + A * instr_addAB + Y * instr_sub_with_add, Y * instr_add + Y * instr_add3 + B * instr_addAB + Z * instr_sub_with_add, Z * instr_add + tmp * instr_add3 + X * instr_addAB + X * instr_sub_with_add] in main_submachine::latch $ [main_submachine::operation_id, main_submachine::x, main_submachine::y, main_submachine::z];
     instr_add3 $ [0, tmp, Z, W] in main_submachine::latch $ [main_submachine::operation_id, main_submachine::x, main_submachine::y, main_submachine::z];
     instr_add_with_sub + instr_sub $ [1, Z * instr_add_with_sub + X * instr_sub, X * instr_add_with_sub + Y * instr_sub, Y * instr_add_with_sub + Z * instr_sub] in main_submachine::latch $ [main_submachine::operation_id, main_submachine::z, main_submachine::x, main_submachine::y];
@@ -1139,22 +1973,8 @@ namespace main__rom(32);
     pol constant p_instr_return = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0] + [0]*;
     pol constant p_instr_sub = [0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0] + [0]*;
     pol constant p_instr_sub_with_add = [0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0] + [0]*;
-    pol constant p_read_W_A = [0]*;
-    pol constant p_read_W_B = [0]*;
-    pol constant p_read_W_C = [0]*;
-    pol constant p_read_W_pc = [0]*;
     pol constant p_read_X_A = [0, 0, 0, 1, 0, 1, 0, 1, 0, 1, 0, 0, 0, 0, 1, 0, 1, 0, 0] + [0]*;
-    pol constant p_read_X_B = [0]*;
     pol constant p_read_X_C = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0] + [0]*;
-    pol constant p_read_X_pc = [0]*;
-    pol constant p_read_Y_A = [0]*;
-    pol constant p_read_Y_B = [0]*;
-    pol constant p_read_Y_C = [0]*;
-    pol constant p_read_Y_pc = [0]*;
-    pol constant p_read_Z_A = [0]*;
-    pol constant p_read_Z_B = [0]*;
-    pol constant p_read_Z_C = [0]*;
-    pol constant p_read_Z_pc = [0]*;
     pol constant p_reg_write_W_A = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0] + [0]*;
     pol constant p_reg_write_W_B = [0]*;
     pol constant p_reg_write_W_C = [0]*;
@@ -1182,4 +2002,531 @@ namespace main_submachine(32);
         let pil = link_native_monolithic(graph).unwrap();
         assert_eq!(extract_main(&format!("{pil}")), expected);
     }
+
+    #[test]
+    fn dynamic_operation_link() {
+        let asm = r"
+machine Arith with latch: latch, operation_id: operation_id, degree: 32 {
+    operation add<0> x, y -> z;
+    operation sub<1> x, y -> z;
+
+    col witness operation_id;
+    col fixed latch = [1]*;
+
+    col witness x;
+    col witness y;
+    col witness z;
+
+    z = (1 - operation_id) * (x + y) + operation_id * (x - y);
+}
+
+machine Main with degree: 32 {
+    reg pc[@pc];
+    reg X[<=];
+    reg Y[<=];
+    reg Z[<=];
+    reg A;
+
+    Arith arith;
+
+    col witness op;
+
+    instr calc X, Y -> Z link => Z = arith.add(X, Y);
+
+    function main {
+        A <== calc(1, 2);
+    }
+}
+";
+        let mut graph = parse_analyze_and_compile::<GoldilocksField>(asm);
+        let sub = Operation {
+            name: "sub".to_string(),
+            id: Some(1u32.into()),
+            ..graph.objects[&Location::main()]
+                .links
+                .iter()
+                .find(|link| link.to.operation.name == "add")
+                .unwrap()
+                .to
+                .operation
+                .clone()
+        };
+        let link = graph
+            .objects
+            .get_mut(&Location::main())
+            .unwrap()
+            .links
+            .iter_mut()
+            .find(|link| link.to.operation.name == "add")
+            .unwrap();
+        link.from.operation_id = Some(direct_reference("op"));
+        link.to.alternative_operations.push(sub);
+
+        let pil = link_native(graph).unwrap().to_string();
+        assert!(pil.contains(
+            "instr_calc $ [op, X, Y, Z] in main_arith::latch $ [main_arith::operation_id, main_arith::x, main_arith::y, main_arith::z];"
+        ));
+        assert!(pil.contains("instr_calc * (op - 0) * (op - 1) = 0;"));
+    }
+
+    #[test]
+    fn dynamic_operation_link_different_params() {
+        let asm = r"
+machine Arith with latch: latch, operation_id: operation_id, degree: 32 {
+    operation add<0> x, y -> z;
+
+    col witness operation_id;
+    col fixed latch = [1]*;
+
+    col witness x;
+    col witness y;
+    col witness z;
+
+    z = x + y;
+}
+
+machine Main with degree: 32 {
+    reg pc[@pc];
+    reg X[<=];
+    reg Y[<=];
+    reg Z[<=];
+    reg A;
+
+    Arith arith;
+
+    col witness op;
+
+    instr calc X, Y -> Z link => Z = arith.add(X, Y);
+
+    function main {
+        A <== calc(1, 2);
+    }
+}
+";
+        let mut graph = parse_analyze_and_compile::<GoldilocksField>(asm);
+        let link = graph
+            .objects
+            .get_mut(&Location::main())
+            .unwrap()
+            .links
+            .iter_mut()
+            .find(|link| link.to.operation.name == "add")
+            .unwrap();
+        let mut sub = Operation {
+            name: "sub".to_string(),
+            id: Some(1u32.into()),
+            ..link.to.operation.clone()
+        };
+        sub.params.outputs.clear();
+        link.from.operation_id = Some(direct_reference("op"));
+        link.to.alternative_operations.push(sub);
+
+        let errors = link_native(graph).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].to_string(),
+            "operations add and sub of main_arith have different parameters and cannot be selected dynamically"
+        );
+        assert_eq!(errors[0].location, Location::main());
+    }
+
+    #[test]
+    fn link_with_other_main() {
+        let asm = r"
+machine Foo with latch: latch, operation_id: operation_id, degree: 32 {
+    operation main<7> x;
+
+    col witness operation_id;
+    col fixed latch = [1]*;
+
+    col witness x;
+}
+
+machine Main with degree: 32 {
+    reg pc[@pc];
+    reg X[<=];
+
+    Foo foo;
+
+    instr run X link => foo.main(X);
+
+    function main {
+        run 10;
+    }
+}
+";
+        let boot_constraint = "_linker_first_step * (operation_id - 7) = 0;";
+        let graph = parse_analyze_and_compile::<GoldilocksField>(asm);
+        let pil = super::link_with_main(graph, Location::main().join("foo"), Default::default())
+            .unwrap()
+            .to_string();
+        let (main, foo) = pil.split_at(pil.find("namespace main_foo").unwrap());
+        assert!(!main.contains("_linker_first_step"));
+        assert!(foo.contains(boot_constraint));
+    }
+
+    #[test]
+    fn custom_entry_point() {
+        let asm = r"
+machine Main with latch: latch, operation_id: operation_id, degree: 32 {
+    operation other<5> x;
+    operation start<3> x;
+
+    col witness operation_id;
+    col fixed latch = [1]*;
+
+    col witness x;
+}
+";
+        let graph = parse_analyze_and_compile::<GoldilocksField>(asm);
+        let pil = link_native(graph).unwrap().to_string();
+        assert!(!pil.contains("_linker_first_step"));
+
+        let graph = parse_analyze_and_compile::<GoldilocksField>(asm);
+        let pil = super::link(
+            graph,
+            super::LinkerParams {
+                entry_point: super::EntryPoint::Fixed("start".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .to_string();
+        assert!(pil.contains("_linker_first_step * (operation_id - 3) = 0;"));
+    }
+
+    #[test]
+    fn unknown_entry_point() {
+        let asm = r"
+machine Main with latch: latch, operation_id: operation_id, degree: 32 {
+    operation start<3> x;
+
+    col witness operation_id;
+    col fixed latch = [1]*;
+
+    col witness x;
+}
+";
+        let graph = parse_analyze_and_compile::<GoldilocksField>(asm);
+        let errors = super::link(
+            graph,
+            super::LinkerParams {
+                entry_point: super::EntryPoint::Fixed("strat".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].to_string(),
+            "Machine main does not have an operation called strat"
+        );
+        assert_eq!(errors[0].location, Location::main());
+    }
+
+    #[test]
+    fn public_entry_point() {
+        let asm = r"
+machine Main with latch: latch, operation_id: operation_id, degree: 32 {
+    operation other<5> x;
+    operation start<3> x;
+
+    col witness operation_id;
+    col fixed latch = [1]*;
+
+    col witness x;
+}
+";
+        let graph = parse_analyze_and_compile::<GoldilocksField>(asm);
+        let pil = super::link(
+            graph,
+            super::LinkerParams {
+                entry_point: super::EntryPoint::Public("entry".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .to_string();
+        assert!(pil.contains("_linker_first_step * (operation_id - 5) * (operation_id - 3) = 0;"));
+        assert!(pil.contains("public entry = operation_id(0);"));
+    }
+
+    #[test]
+    fn link_report() {
+        let asm = r"
+machine Foo with latch: latch, operation_id: operation_id, degree: 32 {
+    operation main<7> x;
+
+    col witness operation_id;
+    col fixed latch = [1]*;
+
+    col witness x;
+}
+
+machine Main with degree: 32 {
+    reg pc[@pc];
+    reg X[<=];
+
+    Foo foo;
+
+    instr run X link => foo.main(X);
+
+    function main {
+        run 10;
+    }
+}
+";
+        let graph = parse_analyze_and_compile::<GoldilocksField>(asm);
+        let (pil, report) = super::link_with_report(graph, Default::default()).unwrap();
+        let pil = pil.to_string();
+
+        assert_eq!(report.interactions.len(), 1);
+        // The main machine also calls its ROM.
+        let interactions = &report.interactions[&Location::main()];
+        assert_eq!(interactions.len(), 2);
+        let interaction = interactions
+            .iter()
+            .find(|interaction| interaction.target == Location::main().join("foo"))
+            .unwrap();
+        assert_eq!(interaction.interaction_type, super::InteractionType::Lookup);
+        assert_eq!(interaction.operation, "main");
+        assert!(interaction
+            .lhs_columns
+            .contains(&"main::instr_run".to_string()));
+        assert!(interaction
+            .rhs_columns
+            .contains(&"main_foo::latch".to_string()));
+        assert_eq!(interaction.inputs, vec![("x".to_string(), None)]);
+        assert!(interaction.outputs.is_empty());
+
+        let lookup = pil.lines().find(|l| l.contains(" in main_foo::")).unwrap();
+        for column in &interaction.lhs_columns {
+            assert!(lookup.contains(column.strip_prefix("main::").unwrap()));
+        }
+        for column in &interaction.rhs_columns {
+            assert!(lookup.contains(column.as_str()));
+        }
+    }
+
+    #[test]
+    fn link_with_unknown_main() {
+        let graph = parse_analyze_and_compile_file::<GoldilocksField>(
+            "../test_data/asm/different_signatures.asm",
+        );
+        let errors = super::link_with_main(graph, Location::main().join("bar"), Default::default())
+            .unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].to_string(), "Machine main.bar not found");
+        assert_eq!(errors[0].location, Location::main().join("bar"));
+    }
+
+    #[test]
+    fn partial_degree() {
+        let asm = r"
+machine Partial with latch: latch, operation_id: operation_id, min_degree: 4 {
+    operation get<0> x;
+
+    col witness operation_id;
+    col fixed latch = [1]*;
+
+    col witness x;
+}
+
+machine Main with degree: 32 {
+    reg pc[@pc];
+    reg X[<=];
+
+    Partial partial;
+
+    instr partial X link => partial.get(X);
+
+    function main {
+        partial 1;
+    }
+}
+";
+        let graph = parse_analyze_and_compile::<GoldilocksField>(asm);
+        let errors = link_native(graph).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].to_string(),
+            "machine at main.partial must have both a min and a max degree"
+        );
+        assert_eq!(errors[0].location, Location::main().join("partial"));
+        assert!(errors[0].source_ref.is_some());
+    }
+
+    #[test]
+    fn heterogeneous_degrees() {
+        let asm = r"
+machine Small with latch: latch, operation_id: operation_id, degree: 4 {
+    operation get<0> x;
+
+    col witness operation_id;
+    col fixed latch = [1]*;
+
+    col witness x;
+}
+
+machine NoDegree with latch: latch, operation_id: operation_id {
+    operation get<0> x;
+
+    col witness operation_id;
+    col fixed latch = [1]*;
+
+    col witness x;
+}
+
+machine Main with degree: 32 {
+    reg pc[@pc];
+    reg X[<=];
+
+    Small small;
+    NoDegree no_degree;
+
+    instr small X link => small.get(X);
+    instr no_degree X link => no_degree.get(X);
+
+    function main {
+        small 1;
+        no_degree 1;
+    }
+}
+";
+        let graph = parse_analyze_and_compile::<GoldilocksField>(asm);
+        let pil = link_native(graph).unwrap().to_string();
+        assert!(pil.contains("namespace main(32);"));
+        assert!(pil.contains("namespace main_small(4);"));
+        assert!(pil.contains(&format!(
+            "namespace main_no_degree({});",
+            super::DEFAULT_DEGREE
+        )));
+    }
+
+    #[test]
+    fn custom_default_degree() {
+        let asm = r"
+machine Main {
+    reg pc[@pc];
+    reg X[<=];
+    reg A;
+
+    function main {
+        A <=X= 1;
+    }
+}
+";
+        let params = |default_degree| super::LinkerParams {
+            default_degree: Some(default_degree),
+            ..Default::default()
+        };
+        let graph = parse_analyze_and_compile::<GoldilocksField>(asm);
+        let pil = super::link(graph, params(256)).unwrap().to_string();
+        assert!(pil.contains("namespace main(256);"));
+
+        let graph = parse_analyze_and_compile::<GoldilocksField>(asm);
+        let errors = super::link(graph, params(100)).unwrap_err();
+        assert_eq!(
+            errors[0].to_string(),
+            "The default degree 100 is not a power of two"
+        );
+    }
+
+    #[test]
+    fn main_degree_override() {
+        let asm = r"
+machine Main with degree: 8 {
+    reg pc[@pc];
+    reg X[<=];
+    reg A;
+
+    function main {
+        A <=X= 1;
+    }
+}
+";
+        let params = |main_degree| super::LinkerParams {
+            main_degree: Some(main_degree),
+            ..Default::default()
+        };
+        let graph = parse_analyze_and_compile::<GoldilocksField>(asm);
+        let pil = super::link(graph, params(64)).unwrap().to_string();
+        assert!(pil.contains("namespace main(64);"));
+
+        let graph = parse_analyze_and_compile::<GoldilocksField>(asm);
+        let errors = super::link(graph, params(48)).unwrap_err();
+        assert_eq!(
+            errors[0].to_string(),
+            "The main degree 48 is not a power of two"
+        );
+    }
+
+    #[test]
+    fn object_publics() {
+        let asm = r"
+machine Main with degree: 8 {
+    col witness x;
+    col witness y;
+}
+";
+        let mut graph = parse_analyze_and_compile::<GoldilocksField>(asm);
+        let main = graph.objects.remove(&Location::main()).unwrap();
+        graph.objects.insert(
+            Location::main(),
+            main.with_publics([("x".to_string(), 0), ("y".to_string(), 7)]),
+        );
+        let pil = link_native(graph).unwrap().to_string();
+        assert!(pil.contains("public _public_x_0 = x(0);"));
+        assert!(pil.contains("public _public_y_7 = y(7);"));
+    }
+
+    #[test]
+    fn link_report_param_types() {
+        let asm = r"
+machine Foo with latch: latch, operation_id: operation_id, degree: 32 {
+    operation add<1> x: field, y -> z: std::prelude::Option;
+
+    col witness operation_id;
+    col fixed latch = [1]*;
+
+    col witness x;
+    col witness y;
+    col witness z;
+}
+
+machine Main with degree: 32 {
+    reg pc[@pc];
+    reg X[<=];
+    reg Y[<=];
+    reg Z[<=];
+    reg A;
+
+    Foo foo;
+
+    instr add X, Y -> Z link => Z = foo.add(X, Y);
+
+    function main {
+        A <== add(1, 2);
+    }
+}
+";
+        let graph = parse_analyze_and_compile::<GoldilocksField>(asm);
+        let (_, report) = super::link_with_report(graph, Default::default()).unwrap();
+        let interaction = report.interactions[&Location::main()]
+            .iter()
+            .find(|interaction| interaction.target == Location::main().join("foo"))
+            .unwrap();
+        assert_eq!(interaction.operation, "add");
+        assert_eq!(
+            interaction.inputs,
+            vec![
+                ("x".to_string(), Some("field".to_string())),
+                ("y".to_string(), None)
+            ]
+        );
+        assert_eq!(
+            interaction.outputs,
+            vec![("z".to_string(), Some("std::prelude::Option".to_string()))]
+        );
+    }
 }
@@ -9,7 +9,8 @@ mod tests {
     use std::ops::*;
 
     use super::Bn254Field;
-    use crate::{traits::int_from_hex_str, FieldElement};
+    use crate::{traits::int_from_hex_str, BigInt, Choice, FieldElement, LargeInt};
+    use num_traits::Zero;
     use test_log::test;
 
     #[test]
@@ -108,4 +109,109 @@ mod tests {
             assert_eq!(signed_integer_value, value.into());
         }
     }
+
+    #[test]
+    fn bytes_le() {
+        let x = -Bn254Field::from(1);
+        let bytes = x.to_bytes_le();
+        assert_eq!(bytes.len(), 32);
+        assert_eq!(Bn254Field::try_from_bytes_le(&bytes), Ok(x));
+        assert_eq!(
+            Bn254Field::try_from_bytes_le(&Bn254Field::from(7).to_bytes_le()),
+            Ok(7.into())
+        );
+
+        let modulus = Bn254Field::modulus().to_arbitrary_integer().to_le_bytes();
+        assert_eq!(modulus.len(), 32);
+        assert!(Bn254Field::try_from_bytes_le(&modulus).is_err());
+        assert!(Bn254Field::try_from_bytes_le(&[0xff; 32]).is_err());
+        assert!(Bn254Field::try_from_bytes_le(&bytes[..31]).is_err());
+    }
+
+    #[test]
+    fn batch_invert() {
+        let elements = [3, 0, 7, 1, 0, 12345678].map(Bn254Field::from).to_vec();
+        let mut inverted = elements.clone();
+        Bn254Field::batch_invert(&mut inverted);
+        for (x, x_inv) in elements.into_iter().zip(inverted) {
+            if x.is_zero() {
+                assert!(x_inv.is_zero());
+            } else {
+                assert_eq!(x_inv, Bn254Field::from(1) / x);
+            }
+        }
+
+        let mut empty: [Bn254Field; 0] = [];
+        Bn254Field::batch_invert(&mut empty);
+    }
+
+    #[test]
+    fn from_signed() {
+        assert_eq!(Bn254Field::from_signed(-2), -Bn254Field::from(2));
+        assert_eq!(
+            Bn254Field::from_signed(-1),
+            Bn254Field::from(0) - Bn254Field::from(1)
+        );
+        assert_eq!(Bn254Field::from_signed(5), Bn254Field::from(5));
+        assert_eq!(Bn254Field::from_signed(-2).to_signed_integer(), (-2).into());
+
+        let modulus = BigInt::from(Bn254Field::modulus().to_arbitrary_integer());
+        assert_eq!(
+            Bn254Field::from_signed_bigint(&(-2).into()),
+            -Bn254Field::from(2)
+        );
+        assert_eq!(
+            Bn254Field::from_signed_bigint(&(modulus.clone() + 3)),
+            Bn254Field::from(3)
+        );
+        assert_eq!(
+            Bn254Field::from_signed_bigint(&(-modulus - 3)),
+            -Bn254Field::from(3)
+        );
+    }
+
+    #[test]
+    fn constant_time_operations() {
+        let a = Bn254Field::from(7);
+        let b = -Bn254Field::from(3);
+        assert_eq!(Bn254Field::conditional_select(&a, &b, Choice::from(0)), a);
+        assert_eq!(Bn254Field::conditional_select(&a, &b, Choice::from(1)), b);
+
+        // A simple linear congruential generator is enough to get varied inputs.
+        let mut state = 0x2545f4914f6cdd1du64;
+        let mut next = || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            Bn254Field::from(state >> 60) * Bn254Field::from(state)
+        };
+        for i in 0..100 {
+            let x = next();
+            let y = if i % 4 == 0 { x } else { next() };
+            assert_eq!(bool::from(x.ct_eq(&y)), x == y);
+            assert!(bool::from(x.ct_eq(&x)));
+            assert!(bool::from(
+                Bn254Field::conditional_select(&x, &y, Choice::from(1)).ct_eq(&y)
+            ));
+        }
+    }
+
+    #[test]
+    fn from_str_radix() {
+        assert_eq!(
+            Bn254Field::from_str_radix(
+                "30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000000",
+                16
+            ),
+            Ok(-Bn254Field::from(1))
+        );
+
+        // The modulus of the secp256k1 base field is larger than the BN254 modulus.
+        assert!(Bn254Field::from_str_radix(
+            "fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f",
+            16
+        )
+        .is_err());
+        assert!(Bn254Field::from_str_radix("12x", 16).is_err());
+    }
 }
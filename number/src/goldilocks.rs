@@ -7,6 +7,7 @@ use ark_ff::{One, Zero};
 use num_traits::{ConstOne, ConstZero};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
 
 use core::fmt::{self, Debug, Formatter};
 use core::hash::Hash;
@@ -366,9 +367,12 @@ impl FieldElement for GoldilocksField {
     }
 
     fn from_str_radix(s: &str, radix: u32) -> Result<Self, String> {
-        u64::from_str_radix(s, radix)
-            .map(Self)
-            .map_err(|e| e.to_string())
+        let n = u64::from_str_radix(s, radix).map_err(|e| e.to_string())?;
+        if n >= Self::ORDER {
+            Err(format!("Number \"{s}\" too large for field."))
+        } else {
+            Ok(Self(n))
+        }
     }
 
     fn checked_from(value: ibig::UBig) -> Option<Self> {
@@ -390,6 +394,14 @@ impl FieldElement for GoldilocksField {
     fn has_direct_repr() -> bool {
         true
     }
+
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        Self(u64::conditional_select(&a.0, &b.0, choice))
+    }
 }
 
 impl LowerHex for GoldilocksField {
@@ -589,6 +601,7 @@ impl Not for GLLargeInt {
 #[cfg(test)]
 mod test {
     use crate::traits::int_from_hex_str;
+    use crate::BigInt;
     use test_log::test;
 
     use super::*;
@@ -636,4 +649,131 @@ mod test {
     fn div_by_zero() {
         let _ = GoldilocksField::from(1) / GoldilocksField::from(0);
     }
+
+    #[test]
+    fn bytes_le() {
+        for v in [0, 1, 0x0102030405060708, GoldilocksField::ORDER - 1] {
+            let x = GoldilocksField::from(v);
+            let bytes = x.to_bytes_le();
+            assert_eq!(bytes, v.to_le_bytes());
+            assert_eq!(GoldilocksField::try_from_bytes_le(&bytes), Ok(x));
+        }
+
+        let modulus = GoldilocksField::ORDER.to_le_bytes();
+        assert!(GoldilocksField::try_from_bytes_le(&modulus).is_err());
+        assert!(GoldilocksField::try_from_bytes_le(&[0xff; 8]).is_err());
+        assert!(GoldilocksField::try_from_bytes_le(&[1, 0, 0, 0]).is_err());
+    }
+
+    #[test]
+    fn batch_invert() {
+        let elements = [3, 0, 7, 1, 0, 12345678, GoldilocksField::ORDER - 2]
+            .map(GoldilocksField::from)
+            .to_vec();
+        let mut inverted = elements.clone();
+        GoldilocksField::batch_invert(&mut inverted);
+        for (x, x_inv) in elements.into_iter().zip(inverted) {
+            if x.is_zero() {
+                assert!(x_inv.is_zero());
+            } else {
+                assert_eq!(x_inv, GoldilocksField::from(1) / x);
+            }
+        }
+
+        let mut empty: [GoldilocksField; 0] = [];
+        GoldilocksField::batch_invert(&mut empty);
+    }
+
+    #[test]
+    fn from_signed() {
+        assert_eq!(GoldilocksField::from_signed(-2), -GoldilocksField::from(2));
+        assert_eq!(
+            GoldilocksField::from_signed(-1),
+            GoldilocksField::from(0) - GoldilocksField::from(1)
+        );
+        assert_eq!(GoldilocksField::from_signed(5), GoldilocksField::from(5));
+        assert_eq!(
+            GoldilocksField::from_signed(-2).to_signed_integer(),
+            (-2).into()
+        );
+
+        let modulus = BigInt::from(GoldilocksField::modulus().to_arbitrary_integer());
+        assert_eq!(
+            GoldilocksField::from_signed_bigint(&(-2).into()),
+            -GoldilocksField::from(2)
+        );
+        assert_eq!(
+            GoldilocksField::from_signed_bigint(&(modulus.clone() + 3)),
+            GoldilocksField::from(3)
+        );
+        assert_eq!(
+            GoldilocksField::from_signed_bigint(&(-modulus - 3)),
+            -GoldilocksField::from(3)
+        );
+    }
+
+    #[test]
+    fn signed_integer_near_half() {
+        let half = (GoldilocksField::ORDER - 1) / 2;
+        let largest_positive = GoldilocksField::from(half);
+        assert!(largest_positive.is_in_lower_half());
+        assert_eq!(largest_positive.to_signed_integer(), half.into());
+
+        let smallest_negative = largest_positive + 1.into();
+        assert!(!smallest_negative.is_in_lower_half());
+        assert_eq!(smallest_negative.to_signed_integer(), -BigInt::from(half));
+        assert_eq!(
+            GoldilocksField::from_signed(-(half as i64)),
+            smallest_negative
+        );
+
+        assert_eq!(GoldilocksField::from(0).to_signed_integer(), 0.into());
+        assert_eq!((-GoldilocksField::from(1)).to_signed_integer(), (-1).into());
+    }
+
+    #[test]
+    fn constant_time_operations() {
+        let a = GoldilocksField::from(7);
+        let b = -GoldilocksField::from(3);
+        assert_eq!(
+            GoldilocksField::conditional_select(&a, &b, Choice::from(0)),
+            a
+        );
+        assert_eq!(
+            GoldilocksField::conditional_select(&a, &b, Choice::from(1)),
+            b
+        );
+
+        // A simple linear congruential generator is enough to get varied inputs.
+        let mut state = 0x2545f4914f6cdd1du64;
+        let mut next = || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            GoldilocksField::from(state >> 60) * GoldilocksField::from(state)
+        };
+        for i in 0..100 {
+            let x = next();
+            let y = if i % 4 == 0 { x } else { next() };
+            assert_eq!(bool::from(x.ct_eq(&y)), x == y);
+            assert!(bool::from(x.ct_eq(&x)));
+            assert!(bool::from(
+                GoldilocksField::conditional_select(&x, &y, Choice::from(1)).ct_eq(&y)
+            ));
+        }
+    }
+
+    #[test]
+    fn from_str_radix() {
+        assert_eq!(
+            GoldilocksField::from_str_radix("ffffffff00000000", 16),
+            Ok(-GoldilocksField::from(1))
+        );
+        assert_eq!(GoldilocksField::from_str_radix("123", 10), Ok(123.into()));
+
+        assert!(GoldilocksField::from_str_radix("ffffffff00000001", 16).is_err());
+        assert!(GoldilocksField::from_str_radix("ffffffffffffffff", 16).is_err());
+        assert!(GoldilocksField::from_str_radix("1ffffffffffffffff", 16).is_err());
+        assert!(GoldilocksField::from_str_radix("12x", 10).is_err());
+    }
 }
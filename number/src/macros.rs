@@ -9,6 +9,7 @@ macro_rules! powdr_field {
         use std::fmt;
         use std::ops::*;
         use std::str::FromStr;
+        use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
 
         #[derive(
             Clone,
@@ -390,6 +391,22 @@ macro_rules! powdr_field {
             fn has_direct_repr() -> bool {
                 false
             }
+
+            // Both functions operate on the limbs of the Montgomery representation,
+            // which is unique for each element.
+            fn ct_eq(&self, other: &Self) -> Choice {
+                self.value.0 .0[..].ct_eq(&other.value.0 .0[..])
+            }
+
+            fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+                let mut limbs = a.value.0;
+                for (limb, b) in limbs.0.iter_mut().zip(b.value.0 .0.iter()) {
+                    *limb = u64::conditional_select(limb, b, choice);
+                }
+                Self {
+                    value: <$ark_type>::new_unchecked(limbs),
+                }
+            }
         }
 
         impl From<$ark_type> for $name {
@@ -92,9 +92,12 @@ macro_rules! powdr_field_plonky3 {
             }
 
             fn from_str_radix(s: &str, radix: u32) -> Result<Self, String> {
-                u32::from_str_radix(s, radix)
-                    .map(Self::from_canonical_u32)
-                    .map_err(|e| e.to_string())
+                let n = u32::from_str_radix(s, radix).map_err(|e| e.to_string())?;
+                if BigUint::from(n) >= Self::modulus().to_arbitrary_integer() {
+                    Err(format!("Number \"{s}\" too large for field."))
+                } else {
+                    Ok(Self::from_canonical_u32(n))
+                }
             }
 
             fn checked_from(value: ibig::UBig) -> Option<Self> {
@@ -9,6 +9,7 @@ use ibig::IBig;
 use num_traits::{ConstOne, ConstZero, One, Zero};
 use schemars::JsonSchema;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
 
 use crate::{BigUint, DegreeType};
 
@@ -165,10 +166,26 @@ pub trait FieldElement:
 
     fn pow(self, exponent: Self::Integer) -> Self;
 
+    /// Returns the canonical little-endian encoding of the element, which has a
+    /// length of `ceil(BITS / 8)` bytes.
     fn to_bytes_le(&self) -> Vec<u8>;
 
     fn from_bytes_le(bytes: &[u8]) -> Self;
 
+    /// Decodes an element encoded by [FieldElement::to_bytes_le]. Fails if the number
+    /// of bytes is wrong or the encoded value is not smaller than the modulus.
+    fn try_from_bytes_le(bytes: &[u8]) -> Result<Self, String> {
+        let len = (Self::BITS as usize).div_ceil(8);
+        if bytes.len() != len {
+            return Err(format!(
+                "Expected {len} bytes for a field element, but got {}.",
+                bytes.len()
+            ));
+        }
+        Self::checked_from(BigUint::from_le_bytes(bytes))
+            .ok_or_else(|| "Non-canonical field element encoding.".to_string())
+    }
+
     fn from_str_radix(s: &str, radix: u32) -> Result<Self, String>;
 
     /// Only converts the value to a field element if it is less than the modulus.
@@ -194,6 +211,21 @@ pub trait FieldElement:
         }
     }
 
+    /// Converts a signed integer to a field element, mapping negative values `x`
+    /// to `modulus() + x`. This is the inverse of [FieldElement::to_signed_integer]
+    /// for values whose absolute value is at most `(modulus() - 1) / 2`.
+    fn from_signed(value: i64) -> Self {
+        value.into()
+    }
+
+    /// Like [FieldElement::from_signed], but for arbitrarily large integers, which
+    /// are reduced modulo the modulus.
+    fn from_signed_bigint(value: &IBig) -> Self {
+        let modulus = IBig::from(Self::modulus().to_arbitrary_integer());
+        let reduced = ((value % &modulus) + &modulus) % &modulus;
+        BigUint::try_from(reduced).unwrap().into()
+    }
+
     /// Returns `true` if values of this type are directly stored as their integer
     /// value, i.e
     /// - montgomery representation is not used
@@ -204,6 +236,47 @@ pub trait FieldElement:
     /// In other words, the `to_integer` function can be implemented as
     /// a mem::transmute operation on pointers.
     fn has_direct_repr() -> bool;
+
+    /// Returns whether `self` and `other` are equal without branching on their values.
+    /// The result is a [Choice], which can only be turned into a `bool` explicitly.
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.to_bytes_le()[..].ct_eq(&other.to_bytes_le()[..])
+    }
+
+    /// Returns `b` if `choice` is set and `a` otherwise, without branching on `choice`
+    /// or the values.
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        let bytes = a
+            .to_bytes_le()
+            .into_iter()
+            .zip(b.to_bytes_le())
+            .map(|(a, b)| u8::conditional_select(&a, &b, choice))
+            .collect::<Vec<_>>();
+        Self::from_bytes_le(&bytes)
+    }
+
+    /// Replaces each non-zero element of `elements` by its inverse, leaving zeros as they are.
+    /// Uses Montgomery's trick, i.e. a single inversion and `3 * (n - 1)` multiplications.
+    fn batch_invert(elements: &mut [Self]) {
+        // prefix_products[i] is the product of all non-zero elements before index i.
+        let mut prefix_products = Vec::with_capacity(elements.len());
+        let mut product = Self::one();
+        for x in elements.iter() {
+            prefix_products.push(product);
+            if !x.is_zero() {
+                product = product * *x;
+            }
+        }
+        // The inverse of the product of all non-zero elements seen so far (from the left).
+        let mut inverse = Self::one() / product;
+        for (x, prefix_product) in elements.iter_mut().zip(prefix_products).rev() {
+            if !x.is_zero() {
+                let x_inverse = inverse * prefix_product;
+                inverse = inverse * *x;
+                *x = x_inverse;
+            }
+        }
+    }
 }
 
 #[cfg(test)]
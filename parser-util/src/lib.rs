@@ -50,6 +50,15 @@ impl SourceRef {
         Default::default()
     }
 
+    /// Returns the 1-based line and column of the start of this source reference,
+    /// if the contents of the file are known.
+    pub fn line_col(&self) -> Option<(usize, usize)> {
+        let before = self.file_contents.as_deref()?.get(..self.start)?;
+        let line = before.matches('\n').count() + 1;
+        let column = before.chars().rev().take_while(|c| *c != '\n').count() + 1;
+        Some((line, column))
+    }
+
     /// Returns a new Error for this source reference.
     pub fn with_error(&self, message: String) -> Error {
         Error {
@@ -29,10 +29,12 @@ fn topo_sort_visit<'a, 'b>(
     if !visited.insert(name) {
         return;
     }
-    if let Some(called) = graph.get(name) {
-        for c in called {
-            topo_sort_visit(c, graph, visited, result);
-        }
+    // References to unknown symbols are reported during type inference.
+    let Some(called) = graph.get(name) else {
+        return;
+    };
+    for c in called {
+        topo_sort_visit(c, graph, visited, result);
     }
     result.push(name.to_string());
 }
@@ -16,10 +16,10 @@ use powdr_ast::{
     parsed::{
         display::quote,
         types::{ArrayType, Type, TypeScheme},
-        ArrayLiteral, BinaryOperation, BinaryOperator, BlockExpression, EnumDeclaration,
-        FunctionCall, IfExpression, IndexAccess, LambdaExpression, LetStatementInsideBlock,
-        MatchArm, MatchExpression, Number, Pattern, StatementInsideBlock, UnaryOperation,
-        UnaryOperator,
+        ArrayLiteral, BinaryOperation, BinaryOperator, BlockExpression, CastExpression,
+        EnumDeclaration, FunctionCall, IfExpression, IndexAccess, LambdaExpression,
+        LetStatementInsideBlock, MatchArm, MatchExpression, Number, Pattern, StatementInsideBlock,
+        UnaryOperation, UnaryOperator,
     },
 };
 use powdr_number::{BigInt, BigUint, FieldElement, LargeInt};
@@ -236,7 +236,7 @@ impl<T: FieldElement> Value<'_, T> {
         pattern: &Pattern,
     ) -> Option<Vec<Arc<Value<'b, T>>>> {
         match pattern {
-            Pattern::Ellipsis(_) => unreachable!("Should be handled higher up"),
+            Pattern::Ellipsis(_, _) => unreachable!("Should be handled higher up"),
             Pattern::CatchAll(_) => Some(vec![]),
             Pattern::Number(_, n) => match v.as_ref() {
                 Value::Integer(x) if x == n => Some(vec![]),
@@ -258,7 +258,9 @@ impl<T: FieldElement> Value<'_, T> {
                     panic!("Type error")
                 };
                 // Index of ".."
-                let ellipsis_pos = items.iter().position(|i| matches!(i, Pattern::Ellipsis(_)));
+                let ellipsis_pos = items
+                    .iter()
+                    .position(|i| matches!(i, Pattern::Ellipsis(_, _)));
                 // Check if the value is too short.
                 let length_matches = match ellipsis_pos {
                     Some(_) => values.len() >= items.len() - 1,
@@ -276,14 +278,21 @@ impl<T: FieldElement> Value<'_, T> {
                     left.len() + right.len(),
                     items.len() - ellipsis_pos.map(|_| 1).unwrap_or_default()
                 );
-                left.chain(right)
-                    .zip(items.iter().filter(|&i| !matches!(i, Pattern::Ellipsis(_))))
-                    .try_fold(vec![], |mut vars, (e, p)| {
-                        Value::try_match_pattern(e, p).map(|v| {
-                            vars.extend(v);
-                            vars
-                        })
-                    })
+                let mut item_values = left.chain(right);
+                items.iter().try_fold(vec![], |mut vars, p| match p {
+                    Pattern::Ellipsis(_, rest) => {
+                        // "..rest" binds the values not matched by other items.
+                        if rest.is_some() {
+                            let rest_values = values[left_len..values.len() - right_len].to_vec();
+                            vars.push(Arc::new(Value::Array(rest_values)));
+                        }
+                        Some(vars)
+                    }
+                    _ => Value::try_match_pattern(item_values.next().unwrap(), p).map(|v| {
+                        vars.extend(v);
+                        vars
+                    }),
+                })
             }
             Pattern::Variable(_, _) => Some(vec![v.clone()]),
             Pattern::Enum(_, name, fields_pattern) => {
@@ -907,7 +916,8 @@ impl<'a, 'b, T: FieldElement, S: SymbolLookup<'a, T>> Evaluator<'a, 'b, T, S> {
                 self.op_stack.push(Operation::Expand(right));
                 self.expand(left)?;
             }
-            Expression::UnaryOperation(_, UnaryOperation { expr: inner, .. }) => {
+            Expression::UnaryOperation(_, UnaryOperation { expr: inner, .. })
+            | Expression::Cast(_, CastExpression { expr: inner, .. }) => {
                 self.op_stack.push(Operation::Combine(expr));
                 self.expand(inner)?;
             }
@@ -1082,6 +1092,18 @@ impl<'a, 'b, T: FieldElement, S: SymbolLookup<'a, T>> Evaluator<'a, 'b, T, S> {
                     )))?,
                 }
             }
+            Expression::Cast(_, CastExpression { ty, .. }) => {
+                let v = self.value_stack.pop().unwrap();
+                match ty {
+                    Type::Int => Value::Integer(v.try_to_integer()?).into(),
+                    // Fails for integers that are negative or not less than the modulus.
+                    Type::Fe => Value::FieldElement(v.try_to_field_element()?).into(),
+                    _ => Err(EvalError::TypeError(format!(
+                        "Unsupported cast from {v}: {} to {ty}",
+                        v.type_formatted()
+                    )))?,
+                }
+            }
             Expression::IndexAccess(_, _) => {
                 let index = self.value_stack.pop().unwrap();
                 let array = self.value_stack.pop().unwrap();
@@ -1598,7 +1620,7 @@ pub fn evaluate_binary_operation_field<'a, T: FieldElement>(
         BinaryOperator::Add => Value::FieldElement(left + right),
         BinaryOperator::Sub => Value::FieldElement(left - right),
         BinaryOperator::Mul => Value::FieldElement(left * right),
-        BinaryOperator::Equal => Value::Bool(left == right),
+        BinaryOperator::Equal | BinaryOperator::Identity => Value::Bool(left == right),
         BinaryOperator::NotEqual => Value::Bool(left != right),
         _ => Err(EvalError::TypeError(format!(
             "Invalid operator \"{op}\" on field elements: {left} {op} {right}"
@@ -1626,7 +1648,7 @@ pub fn evaluate_binary_operation_integer<'a, T>(
         BinaryOperator::ShiftRight => Value::Integer(left >> usize::try_from(right).unwrap()),
         BinaryOperator::Less => Value::Bool(left < right),
         BinaryOperator::LessEqual => Value::Bool(left <= right),
-        BinaryOperator::Equal => Value::Bool(left == right),
+        BinaryOperator::Equal | BinaryOperator::Identity => Value::Bool(left == right),
         BinaryOperator::NotEqual => Value::Bool(left != right),
         BinaryOperator::GreaterEqual => Value::Bool(left >= right),
         BinaryOperator::Greater => Value::Bool(left > right),
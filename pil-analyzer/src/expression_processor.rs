@@ -3,8 +3,8 @@ use powdr_ast::{
     analyzed::{Expression, PolynomialReference, Reference},
     parsed::{
         self, asm::SymbolPath, types::Type, ArrayExpression, ArrayLiteral, BinaryOperation,
-        BlockExpression, IfExpression, LambdaExpression, LetStatementInsideBlock, MatchArm,
-        MatchExpression, NamedExpression, NamespacedPolynomialReference, Number, Pattern,
+        BlockExpression, CastExpression, IfExpression, LambdaExpression, LetStatementInsideBlock,
+        MatchArm, MatchExpression, NamedExpression, NamespacedPolynomialReference, Number, Pattern,
         SourceReference, StatementInsideBlock, StructExpression, SymbolCategory, UnaryOperation,
     },
 };
@@ -117,6 +117,13 @@ impl<'a, D: AnalysisDriver> ExpressionProcessor<'a, D> {
                     },
                 )
             }
+            PExpression::Cast(src, CastExpression { expr, ty }) => Expression::Cast(
+                src,
+                CastExpression {
+                    expr: Box::new(self.process_expression(*expr)?),
+                    ty,
+                },
+            ),
             PExpression::IndexAccess(src, index_access) => Expression::IndexAccess(
                 src,
                 parsed::IndexAccess {
@@ -209,14 +216,18 @@ impl<'a, D: AnalysisDriver> ExpressionProcessor<'a, D> {
     fn process_pattern(&mut self, pattern: Pattern) -> Result<Pattern, Error> {
         match pattern {
             Pattern::CatchAll(_)
-            | Pattern::Ellipsis(_)
+            | Pattern::Ellipsis(_, None)
             | Pattern::Number(_, _)
             | Pattern::String(_, _) => Ok(pattern),
+            Pattern::Ellipsis(source_ref, Some(name)) => {
+                self.process_variable_pattern(source_ref.clone(), name.clone())?;
+                Ok(Pattern::Ellipsis(source_ref, Some(name)))
+            }
             Pattern::Array(source_ref, items) => {
                 // If there is more than one Pattern::Ellipsis in items, it is an error
                 if items
                     .iter()
-                    .filter(|p| matches!(p, Pattern::Ellipsis(_)))
+                    .filter(|p| matches!(p, Pattern::Ellipsis(_, _)))
                     .count()
                     > 1
                 {
@@ -323,9 +334,19 @@ impl<'a, D: AnalysisDriver> ExpressionProcessor<'a, D> {
         &mut self,
         source_ref: SourceRef,
         LambdaExpression {
-            kind, params, body, ..
+            kind,
+            params,
+            param_defaults,
+            body,
+            ..
         }: LambdaExpression,
     ) -> Result<Expression, Error> {
+        // The default values are processed outside of the scope of the parameters.
+        let param_defaults = param_defaults
+            .into_iter()
+            .map(|d| self.process_expression(d))
+            .collect::<Result<Vec<_>, Error>>()?;
+
         let previous_local_vars = self.save_local_variables();
 
         let params = params
@@ -348,6 +369,7 @@ impl<'a, D: AnalysisDriver> ExpressionProcessor<'a, D> {
             LambdaExpression {
                 kind,
                 params,
+                param_defaults,
                 body,
                 param_types: vec![],
             },
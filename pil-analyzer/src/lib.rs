@@ -24,7 +24,11 @@ use powdr_ast::{
     },
 };
 
-pub use pil_analyzer::{analyze_ast, analyze_file, analyze_string};
+pub use pil_analyzer::{
+    analyze_ast, analyze_ast_with_warnings, analyze_file, analyze_file_with_warnings,
+    analyze_string, analyze_string_with_warnings,
+};
+pub use type_inference::{check_expression, infer_types_full, ExpectedType};
 
 pub trait AnalysisDriver: Clone + Copy {
     /// Turns a declaration into an absolute name.
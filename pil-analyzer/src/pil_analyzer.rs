@@ -17,7 +17,7 @@ use powdr_ast::parsed::{
     self, FunctionKind, LambdaExpression, PILFile, PilStatement, SymbolCategory,
     TraitImplementation, TypedExpression,
 };
-use powdr_number::{FieldElement, GoldilocksField};
+use powdr_number::{FieldElement, GoldilocksField, LargeInt};
 
 use powdr_ast::analyzed::{
     type_from_definition, Analyzed, DegreeRange, Expression, FunctionValueDefinition,
@@ -29,34 +29,67 @@ use powdr_parser_util::Error;
 
 use crate::traits_resolver::TraitsResolver;
 use crate::type_builtins::constr_function_statement_type;
-use crate::type_inference::infer_types;
+use crate::type_inference::{check_literal_sizes, infer_types};
 use crate::{side_effect_checker, AnalysisDriver};
 
 use crate::statement_processor::{Counters, PILItem, StatementProcessor};
 use crate::{condenser, evaluator, expression_processor::ExpressionProcessor};
 
 pub fn analyze_file<T: FieldElement>(path: &Path) -> Result<Analyzed<T>, Vec<Error>> {
+    analyze_file_with_warnings(path).map(log_warnings)
+}
+
+pub fn analyze_ast<T: FieldElement>(pil_file: PILFile) -> Result<Analyzed<T>, Vec<Error>> {
+    analyze_ast_with_warnings(pil_file).map(log_warnings)
+}
+
+pub fn analyze_string<T: FieldElement>(contents: &str) -> Result<Analyzed<T>, Vec<Error>> {
+    analyze_string_with_warnings(contents).map(log_warnings)
+}
+
+/// Like [analyze_file], but returns the warnings emitted during analysis
+/// instead of logging them.
+pub fn analyze_file_with_warnings<T: FieldElement>(
+    path: &Path,
+) -> Result<(Analyzed<T>, Vec<Error>), Vec<Error>> {
     let files = import_all_dependencies(path);
     analyze(files)
 }
 
-pub fn analyze_ast<T: FieldElement>(pil_file: PILFile) -> Result<Analyzed<T>, Vec<Error>> {
+/// Like [analyze_ast], but returns the warnings emitted during analysis
+/// instead of logging them.
+pub fn analyze_ast_with_warnings<T: FieldElement>(
+    pil_file: PILFile,
+) -> Result<(Analyzed<T>, Vec<Error>), Vec<Error>> {
     analyze(vec![pil_file])
 }
 
-pub fn analyze_string<T: FieldElement>(contents: &str) -> Result<Analyzed<T>, Vec<Error>> {
+/// Like [analyze_string], but returns the warnings emitted during analysis
+/// instead of logging them.
+pub fn analyze_string_with_warnings<T: FieldElement>(
+    contents: &str,
+) -> Result<(Analyzed<T>, Vec<Error>), Vec<Error>> {
     let pil_file = powdr_parser::parse(Some("input"), contents).map_err(|e| vec![e])?;
     analyze(vec![pil_file])
 }
 
-fn analyze<T: FieldElement>(files: Vec<PILFile>) -> Result<Analyzed<T>, Vec<Error>> {
+fn log_warnings<T>((analyzed, warnings): (Analyzed<T>, Vec<Error>)) -> Analyzed<T> {
+    for warning in warnings {
+        log::warn!("{}", warning.message());
+    }
+    analyzed
+}
+
+fn analyze<T: FieldElement>(files: Vec<PILFile>) -> Result<(Analyzed<T>, Vec<Error>), Vec<Error>> {
     let mut analyzer = PILAnalyzer::new();
     analyzer.process(files)?;
     analyzer.side_effect_check()?;
     analyzer.validate_structs()?;
     analyzer.type_check()?;
+    analyzer.check_literal_sizes::<T>()?;
     let solved_impls = analyzer.resolve_trait_impls()?;
-    analyzer.condense(solved_impls)
+    let warnings = std::mem::take(&mut analyzer.warnings);
+    Ok((analyzer.condense(solved_impls)?, warnings))
 }
 
 #[derive(Default)]
@@ -78,6 +111,8 @@ struct PILAnalyzer {
     auto_added_symbols: HashSet<String>,
     /// All trait implementations found, in source order.
     trait_impls: Vec<TraitImplementation<Expression>>,
+    /// Warnings emitted while processing the statements.
+    warnings: Vec<Error>,
 }
 
 /// Reads and parses the given path and all its imports.
@@ -360,6 +395,21 @@ impl PILAnalyzer {
         Ok(())
     }
 
+    /// Checks that the field element literals are valid elements of the field `T`.
+    fn check_literal_sizes<T: FieldElement>(&self) -> Result<(), Vec<Error>> {
+        let definitions = self
+            .definitions
+            .iter()
+            .sorted_by_key(|(name, _)| *name)
+            .filter_map(|(_, (_, value))| value.as_ref())
+            .flat_map(|value| value.children());
+        let trait_impls = self.trait_impls.iter().flat_map(|i| i.children());
+        check_literal_sizes(
+            definitions.chain(trait_impls).chain(&self.proof_items),
+            &T::modulus().to_arbitrary_integer(),
+        )
+    }
+
     /// Creates and returns a map for every referenced trait function with concrete type to the
     /// corresponding trait implementation function.
     fn resolve_trait_impls(&mut self) -> Result<SolvedTraitImpls, Vec<Error>> {
@@ -479,10 +529,12 @@ impl PILAnalyzer {
             _ => {
                 // We need a mutable reference to the counter, but it is short-lived.
                 let mut counters = self.symbol_counters.take().unwrap();
-                let items =
-                    StatementProcessor::new(self.driver(), &mut counters, self.polynomial_degree)
-                        .handle_statement(statement);
+                let mut processor =
+                    StatementProcessor::new(self.driver(), &mut counters, self.polynomial_degree);
+                let items = processor.handle_statement(statement);
+                let warnings = processor.into_warnings();
                 self.symbol_counters = Some(counters);
+                self.warnings.extend(warnings);
                 for item in items {
                     match item {
                         PILItem::Definition(symbol, value) => {
@@ -17,7 +17,7 @@ use powdr_ast::parsed::{
     ArrayExpression, NamedExpression, StructDeclaration, SymbolCategory, TraitImplementation,
     TypeDeclaration,
 };
-use powdr_parser_util::SourceRef;
+use powdr_parser_util::{Error, SourceRef};
 use std::str::FromStr;
 
 use powdr_ast::analyzed::{
@@ -107,6 +107,8 @@ pub struct StatementProcessor<'a, D> {
     driver: D,
     counters: &'a mut Counters,
     degree: Option<DegreeRange>,
+    /// Warnings about statements that were processed nonetheless.
+    warnings: Vec<Error>,
 }
 
 impl<'a, D> StatementProcessor<'a, D>
@@ -118,9 +120,15 @@ where
             driver,
             counters,
             degree,
+            warnings: vec![],
         }
     }
 
+    /// Returns the warnings emitted while processing the statements.
+    pub fn into_warnings(self) -> Vec<Error> {
+        self.warnings
+    }
+
     pub fn handle_statement(&mut self, statement: PilStatement) -> Vec<PILItem> {
         match statement {
             PilStatement::Include(_, _) => {
@@ -270,16 +278,25 @@ where
                 );
             }
             let declared_type_vars = vars.vars().collect::<HashSet<_>>();
+            if let Some(var) = vars
+                .equalities()
+                .flat_map(|(a, b)| [a, b])
+                .find(|v| !declared_type_vars.contains(v))
+            {
+                panic!("Undeclared type variable {var} in where clause of \"{name}\".");
+            }
             let ty = self.type_processor(&declared_type_vars).process_type(ts.ty);
             let contained_type_vars = ty.contained_type_vars().collect::<HashSet<_>>();
+            let mut vars = vars.clone();
             if contained_type_vars != declared_type_vars {
                 assert!(contained_type_vars.is_subset(&declared_type_vars));
-                panic!(
+                self.warnings.push(source.with_error(format!(
                     "Unused type variable(s) in declaration: {}\nlet<{vars}> {name}: {ty}",
                     declared_type_vars
                         .difference(&contained_type_vars)
                         .format(", ")
-                );
+                )));
+                vars.retain_vars(|v| contained_type_vars.contains(v));
             };
             TypeScheme { vars, ty }
         });
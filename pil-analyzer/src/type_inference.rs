@@ -1,4 +1,4 @@
-use std::collections::{BTreeSet, HashMap};
+use std::collections::{BTreeSet, HashMap, HashSet};
 
 use itertools::Itertools;
 use powdr_ast::{
@@ -6,12 +6,14 @@ use powdr_ast::{
     parsed::{
         display::format_type_scheme_around_name,
         types::{ArrayType, FunctionType, TupleType, Type, TypeBounds, TypeScheme},
-        visitor::ExpressionVisitable,
-        ArrayLiteral, BinaryOperation, BlockExpression, FunctionCall, FunctionKind, IndexAccess,
-        LambdaExpression, LetStatementInsideBlock, MatchArm, MatchExpression, Number, Pattern,
-        SourceReference, StatementInsideBlock, UnaryOperation,
+        visitor::{AllChildren, ExpressionVisitable},
+        ArrayLiteral, BinaryOperation, BinaryOperator, BlockExpression, CastExpression,
+        FunctionCall, FunctionKind, IndexAccess, LambdaExpression, LetStatementInsideBlock,
+        MatchArm, MatchExpression, Number, Pattern, SourceReference, StatementInsideBlock,
+        UnaryOperation,
     },
 };
+use powdr_number::BigUint;
 use powdr_parser_util::{Error, SourceRef};
 
 use crate::{
@@ -35,6 +37,55 @@ pub fn infer_types(
     TypeChecker::new().infer_types(definitions, expressions)
 }
 
+/// Like [infer_types], but returns the type schemes of all definitions, including those
+/// with declared type. Definitions with declared type keep their declared type scheme.
+pub fn infer_types_full(
+    definitions: HashMap<String, (Option<TypeScheme>, Option<&mut Expression>)>,
+    expressions: &mut [(&mut Expression, ExpectedType)],
+) -> Result<Vec<(String, TypeScheme)>, Vec<Error>> {
+    TypeChecker::new().infer_types_full(definitions, expressions)
+}
+
+/// Type-checks the single expression `expr` against `expected` in the context of
+/// `definitions` (see [infer_types]) and returns its type.
+/// Sets the generic arguments for references and the literal types in `expr`.
+pub fn check_expression(
+    definitions: HashMap<String, (Option<TypeScheme>, Option<&mut Expression>)>,
+    expr: &mut Expression,
+    expected: ExpectedType,
+) -> Result<Type, Vec<Error>> {
+    TypeChecker::new().check_expression(definitions, expr, expected)
+}
+
+/// Checks that all literals of type `fe` or `expr` in `expressions` are smaller than `modulus`,
+/// i.e. that they are valid field elements. Requires the literal types to be set by [infer_types].
+pub fn check_literal_sizes<'a>(
+    expressions: impl IntoIterator<Item = &'a Expression>,
+    modulus: &BigUint,
+) -> Result<(), Vec<Error>> {
+    let errors = expressions
+        .into_iter()
+        .flat_map(|e| e.all_children())
+        .filter_map(|e| match e {
+            Expression::Number(
+                source_ref,
+                Number {
+                    value,
+                    type_: Some(ty @ (Type::Fe | Type::Expr)),
+                },
+            ) if value >= modulus => Some(source_ref.with_error(format!(
+                "Literal {value} of type {ty} is too large for the field with modulus {modulus}."
+            ))),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
 /// A type to expect with a bit of flexibility.
 /// This is used for example at statement level, where we allow Constr, Constr[], prover functions
 /// (functions from int to ()) and the empty tuple.
@@ -72,6 +123,18 @@ struct TypeChecker {
     unifier: Unifier,
     /// Keeps track of the kind of lambda we are currently type-checking.
     lambda_kind: FunctionKind,
+    /// For type variables created when instantiating the type scheme of a referenced
+    /// symbol: the symbol name and the type variable in its declared scheme.
+    instantiations: HashMap<String, (String, String)>,
+    /// For symbols defined by a lambda with default parameter values: the number
+    /// of parameters and the (not yet type-checked) default values of the trailing parameters.
+    param_defaults: HashMap<String, (usize, Vec<Expression>)>,
+    /// The inferred types of the isolated expressions, in order.
+    expression_types: Vec<Type>,
+    /// Casts whose operand type was not yet known when they were type-checked:
+    /// the operand type, the target type and the source reference of the cast.
+    /// They are resolved once all definitions have been processed.
+    pending_casts: Vec<(Type, Type, SourceRef)>,
 }
 
 impl TypeChecker {
@@ -82,33 +145,79 @@ impl TypeChecker {
             declared_type_vars: Default::default(),
             unifier: Default::default(),
             lambda_kind: FunctionKind::Constr,
+            instantiations: Default::default(),
+            param_defaults: Default::default(),
+            expression_types: Default::default(),
+            pending_casts: Default::default(),
         }
     }
 
     /// Infers and checks types for all provided definitions and expressions and
     /// returns the types for symbols without explicit type.
     pub fn infer_types(
+        self,
+        definitions: HashMap<String, (Option<TypeScheme>, Option<&mut Expression>)>,
+        expressions: &mut [(&mut Expression, ExpectedType)],
+    ) -> Result<Vec<(String, Type)>, Vec<Error>> {
+        let without_declared_type = definitions
+            .iter()
+            .filter(|(_, (ty, _))| ty.is_none())
+            .map(|(name, _)| name.clone())
+            .collect::<HashSet<_>>();
+        Ok(self
+            .infer_types_full(definitions, expressions)?
+            .into_iter()
+            .filter(|(name, _)| without_declared_type.contains(name))
+            .map(|(name, scheme)| {
+                assert!(scheme.vars.is_empty());
+                (name, scheme.ty)
+            })
+            .collect())
+    }
+
+    /// Infers and checks types for all provided definitions and expressions and
+    /// returns the type schemes of all definitions.
+    pub fn infer_types_full(
         mut self,
         mut definitions: HashMap<String, (Option<TypeScheme>, Option<&mut Expression>)>,
         expressions: &mut [(&mut Expression, ExpectedType)],
-    ) -> Result<Vec<(String, Type)>, Vec<Error>> {
+    ) -> Result<Vec<(String, TypeScheme)>, Vec<Error>> {
         let type_var_mapping = self
             .infer_types_inner(&mut definitions, expressions)
             .map_err(|e| vec![e])?;
         self.update_type_args(&mut definitions, expressions, &type_var_mapping)?;
         Ok(definitions
             .into_iter()
-            .filter(|(_, (ty, _))| ty.is_none())
-            .map(|(name, _)| {
-                let (_, mut scheme) = self.declared_types.remove(&name).unwrap();
-                assert!(scheme.vars.is_empty());
-                self.substitute(&mut scheme.ty);
-                assert!(scheme.ty.is_concrete_type());
-                (name, scheme.ty)
+            .map(|(name, (declared, _))| {
+                let scheme = declared.unwrap_or_else(|| {
+                    let (_, mut scheme) = self.declared_types.remove(&name).unwrap();
+                    assert!(scheme.vars.is_empty());
+                    self.substitute(&mut scheme.ty);
+                    assert!(scheme.ty.is_concrete_type());
+                    scheme
+                });
+                (name, scheme)
             })
             .collect())
     }
 
+    /// Infers and checks types for all provided definitions and the expression `expr`
+    /// and returns the type of `expr`.
+    pub fn check_expression(
+        mut self,
+        mut definitions: HashMap<String, (Option<TypeScheme>, Option<&mut Expression>)>,
+        expr: &mut Expression,
+        expected: ExpectedType,
+    ) -> Result<Type, Vec<Error>> {
+        let mut expressions = [(expr, expected)];
+        let type_var_mapping = self
+            .infer_types_inner(&mut definitions, &mut expressions)
+            .map_err(|e| vec![e])?;
+        self.update_type_args(&mut definitions, &mut expressions, &type_var_mapping)?;
+        let ty = self.expression_types.pop().unwrap();
+        Ok(self.type_into_substituted(ty))
+    }
+
     /// Returns, for each name declared with a type scheme, a mapping from
     /// the type variables used by the type checker to those used in the declaration.
     fn infer_types_inner(
@@ -116,10 +225,11 @@ impl TypeChecker {
         definitions: &mut HashMap<String, (Option<TypeScheme>, Option<&mut Expression>)>,
         expressions: &mut [(&mut Expression, ExpectedType)],
     ) -> Result<HashMap<String, HashMap<String, Type>>, Error> {
-        // TODO in order to fix type inference on recursive functions, we need to:
-        // - collect all groups of functions that call each other recursively
-        // - analyze each such group in an environment, where their type schemes
-        //   are instantiated once at the start and not anymore for the symbol lookup.
+        // Recursive and mutually recursive functions need no special treatment:
+        // Symbols without declared type are monomorphic, i.e. they are assigned a single
+        // type variable up front which is shared by all references to them, also by those
+        // from other symbols in the same cycle. Symbols with a declared type scheme are
+        // instantiated anew at each reference, which allows polymorphic recursion.
 
         // Sort the names such that called names occur first.
         let names = sort_called_first(
@@ -130,6 +240,25 @@ impl TypeChecker {
 
         self.setup_declared_types(definitions);
 
+        // Store the default values before they are modified by type inference,
+        // so that they can be type-checked anew at each call that omits them.
+        self.param_defaults = definitions
+            .iter()
+            .filter_map(|(name, (_, value))| match value.as_deref()? {
+                Expression::LambdaExpression(
+                    _,
+                    LambdaExpression {
+                        params,
+                        param_defaults,
+                        ..
+                    },
+                ) if !param_defaults.is_empty() => {
+                    Some((name.clone(), (params.len(), param_defaults.clone())))
+                }
+                _ => None,
+            })
+            .collect();
+
         // These are the inferred types for symbols that are declared
         // as type schemes. They are compared to the declared types
         // at the end.
@@ -141,20 +270,42 @@ impl TypeChecker {
         // type is done at the end.
         for name in names {
             // Ignore builtins (removed from definitions) and definitions without value.
-            let Some((_, Some(value))) = definitions.get_mut(&name) else {
+            let Some((type_scheme, Some(value))) = definitions.get_mut(&name) else {
                 continue;
             };
 
             let (_, declared_type) = self.declared_types[&name].clone();
             if declared_type.vars.is_empty() {
                 self.declared_type_vars.clear();
-                self.process_concrete_symbol(declared_type.ty.clone(), value)?;
+                let has_declared_type = type_scheme.is_some();
+                self.process_concrete_symbol(declared_type.ty.clone(), value)
+                    .map_err(|e| {
+                        if !has_declared_type && references_symbol(value, &name) {
+                            // Symbols without declared type are monomorphic, which means that
+                            // all recursive references have to be at the same type.
+                            e.source_ref().with_error(format!(
+                                "{}
+Note: {name} is recursive but does not have a declared type. Polymorphic recursion requires an explicit type annotation.",
+                                e.message()
+                            ))
+                        } else {
+                            e
+                        }
+                    })?;
             } else {
                 self.declared_type_vars = declared_type
                     .vars
                     .vars()
                     .map(|v| (v.clone(), self.unifier.new_type_var()))
                     .collect();
+                for (a, b) in declared_type.vars.equalities() {
+                    self.unifier
+                        .unify_types(
+                            self.declared_type_vars[a].clone(),
+                            self.declared_type_vars[b].clone(),
+                        )
+                        .unwrap();
+                }
                 self.infer_type_of_expression(value).map(|ty| {
                     inferred_types.insert(name.to_string(), ty);
                 })?;
@@ -164,6 +315,8 @@ impl TypeChecker {
 
         self.check_expressions(expressions)?;
 
+        self.resolve_pending_casts()?;
+
         // From this point on, the substitutions are fixed.
 
         // Now we check for all symbols that are not declared as a type scheme that they
@@ -309,6 +462,16 @@ impl TypeChecker {
             t => self
                 .unifier
                 .unify_types(t.clone(), Type::Fe)
+                .or_else(|err| match &t {
+                    // The bounds of the type variable might only be satisfied by `int`
+                    // (for example `Ord`). Failing to unify a type variable with `fe`
+                    // does not modify any substitutions, so we can try `int` instead.
+                    Type::TypeVar(_) => self
+                        .unifier
+                        .unify_types(t.clone(), Type::Int)
+                        .map_err(|_| err),
+                    _ => Err(err),
+                })
                 .map_err(|err| {
                     let substitute_flexible = |s: Type| {
                         let mut t = expected_type.clone();
@@ -425,7 +588,7 @@ impl TypeChecker {
                 }
             }
             Expression::BlockExpression(
-                source_ref,
+                _,
                 BlockExpression {
                     statements,
                     expr: _,
@@ -439,8 +602,7 @@ impl TypeChecker {
                             value: _,
                         }) => {
                             if !self.update_local_type(ty.as_mut().unwrap(), type_var_mapping) {
-                                // TODO better source ref
-                                return Err(source_ref.with_error(format!(
+                                return Err(pattern.source_reference().with_error(format!(
                                     "Unable to derive concrete type for local declaration {pattern}"
                                 )));
                             }
@@ -473,19 +635,21 @@ impl TypeChecker {
         &mut self,
         expressions: &mut [(&mut Expression, ExpectedType)],
     ) -> Result<(), Error> {
-        for (e, expected_type) in expressions {
-            self.expect_type_with_flexibility(expected_type, e)?;
-        }
+        let types = expressions
+            .iter_mut()
+            .map(|(e, expected_type)| self.expect_type_with_flexibility(expected_type, e))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.expression_types = types;
         Ok(())
     }
 
     /// Process an expression, inferring its type and allowing a certain flexibility in the type
-    /// as specified by `expected_type`.
+    /// as specified by `expected_type`. Returns the inferred type.
     fn expect_type_with_flexibility(
         &mut self,
         expected_type: &ExpectedType,
         expr: &mut Expression,
-    ) -> Result<(), Error> {
+    ) -> Result<Type, Error> {
         update_type_if_literal(expr, &expected_type.ty);
 
         let ty = self.infer_type_of_expression(expr)?;
@@ -495,7 +659,7 @@ impl TypeChecker {
                 base: Box::new(expected_type.ty.clone()),
                 length: None,
             })
-        } else if expected_type.allow_empty && (ty == Type::empty_tuple()) {
+        } else if expected_type.allow_empty && ty.is_unit() {
             Type::empty_tuple()
         } else if expected_type.allow_int_to_empty_fun && matches!(ty, Type::Function(_)) {
             Type::Function(FunctionType {
@@ -512,9 +676,10 @@ impl TypeChecker {
                 expr.source_reference().with_error(format!(
                     "Expected type {} but got type {}.\n{err}",
                     self.format_type_with_bounds(expected_type),
-                    self.format_type_with_bounds(ty),
+                    self.format_type_with_bounds(ty.clone()),
                 ))
-            })
+            })?;
+        Ok(ty)
     }
 
     /// Process an expression and return the type of the expression.
@@ -525,11 +690,30 @@ impl TypeChecker {
                 source_ref,
                 Reference::Poly(PolynomialReference { name, type_args }),
             ) => {
-                let (ty, args) = self
-                    .unifier
-                    .instantiate_scheme(self.declared_types[name].1.clone());
+                let Some((_, scheme)) = self.declared_types.get(name) else {
+                    let suggestion = closest_name(
+                        name,
+                        self.declared_types.keys().chain(builtin_schemes().keys()),
+                    )
+                    .map(|closest| format!("\nDid you mean {closest}?"))
+                    .unwrap_or_default();
+                    return Err(
+                        source_ref.with_error(format!("Unknown symbol {name}.{suggestion}"))
+                    );
+                };
+                let scheme = scheme.clone();
+                let scheme_vars = scheme.vars.vars().cloned().collect::<Vec<_>>();
+                let (ty, args) = self.unifier.instantiate_scheme(scheme);
+                for (var, arg) in scheme_vars.into_iter().zip(&args) {
+                    let Type::TypeVar(arg) = arg else {
+                        unreachable!()
+                    };
+                    self.instantiations.insert(arg.clone(), (name.clone(), var));
+                }
+                // The requested type arguments can be a prefix of the type arguments,
+                // the remaining ones are inferred.
                 if let Some(requested_type_args) = type_args {
-                    if requested_type_args.len() != args.len() {
+                    if requested_type_args.len() > args.len() {
                         return Err(source_ref.with_error(format!(
                             "Expected {} type arguments for symbol {name}, but got {}: {}",
                             args.len(),
@@ -584,6 +768,7 @@ impl TypeChecker {
                 LambdaExpression {
                     kind,
                     params,
+                    param_defaults,
                     body,
                     param_types,
                 },
@@ -602,6 +787,15 @@ impl TypeChecker {
                     });
                 self.local_var_types.truncate(old_len);
                 let (param_types_inferred, body_type) = result?;
+                // The default values cannot reference the parameters, so we
+                // check them after removing the parameters from the scope.
+                let first_default = params.len() - param_defaults.len();
+                for (default, ty) in param_defaults
+                    .iter_mut()
+                    .zip(&param_types_inferred[first_default..])
+                {
+                    self.expect_type(ty, default)?;
+                }
                 *param_types = param_types_inferred.clone();
                 Type::Function(FunctionType {
                     params: param_types_inferred,
@@ -619,6 +813,41 @@ impl TypeChecker {
                     length: None,
                 })
             }
+            Expression::BinaryOperation(
+                _,
+                BinaryOperation {
+                    left,
+                    op: BinaryOperator::Identity,
+                    right,
+                },
+            ) => {
+                // `=` creates a constraint between two expressions, but
+                // it compares for equality if the left side is `int` or `fe`.
+                let left_type = self.infer_type_of_expression(left)?;
+                let left_type = self.type_into_substituted(left_type);
+                let (operand_type, result_type) = match left_type {
+                    Type::Int | Type::Fe => (left_type.clone(), Type::Bool),
+                    _ => {
+                        let Type::Function(FunctionType { params, value }) =
+                            binary_operator_scheme(BinaryOperator::Identity).ty
+                        else {
+                            unreachable!()
+                        };
+                        (params[0].clone(), *value)
+                    }
+                };
+                self.unifier
+                    .unify_types(left_type.clone(), operand_type.clone())
+                    .map_err(|err| {
+                        left.source_reference().with_error(format!(
+                            "Expected type: {}\nInferred type: {}\n{err}",
+                            self.format_type_with_bounds(operand_type.clone()),
+                            self.format_type_with_bounds(left_type)
+                        ))
+                    })?;
+                self.expect_type(&operand_type, right)?;
+                result_type
+            }
             Expression::BinaryOperation(source_ref, BinaryOperation { left, op, right }) => {
                 // TODO at some point, also store the generic args for operators
                 let fun_type = self
@@ -645,6 +874,21 @@ impl TypeChecker {
                     source_ref,
                 )?
             }
+            Expression::Cast(source_ref, CastExpression { expr, ty }) => {
+                let inner_type = self.infer_type_of_expression(expr)?;
+                let inner_type = self.type_into_substituted(inner_type);
+                match &inner_type {
+                    Type::Int | Type::Fe if matches!(ty, Type::Int | Type::Fe) => {}
+                    // The operand type might still be determined by the context,
+                    // for example by the declared type of an enclosing lambda.
+                    Type::TypeVar(_) if matches!(ty, Type::Int | Type::Fe) => {
+                        self.pending_casts
+                            .push((inner_type, ty.clone(), source_ref.clone()));
+                    }
+                    _ => return Err(unsupported_cast(source_ref, &inner_type, ty)),
+                }
+                ty.clone()
+            }
             Expression::IndexAccess(_, IndexAccess { array, index }) => {
                 let result = self.unifier.new_type_var();
                 self.expect_type(
@@ -666,6 +910,7 @@ impl TypeChecker {
                 },
             ) => {
                 let ft = self.infer_type_of_expression(function)?;
+                self.add_default_arguments(function, arguments);
                 self.infer_type_of_function_call(
                     ft,
                     arguments.iter_mut(),
@@ -675,6 +920,7 @@ impl TypeChecker {
             }
             Expression::FreeInput(_, _) => todo!(),
             Expression::MatchExpression(_, MatchExpression { scrutinee, arms }) => {
+                check_for_unreachable_arms(arms)?;
                 let scrutinee_type = self.infer_type_of_expression(scrutinee)?;
                 let result = self.unifier.new_type_var();
                 for MatchArm { pattern, value } in arms {
@@ -692,7 +938,7 @@ impl TypeChecker {
                 self.expect_type(&result, &mut if_expr.else_body)?;
                 result
             }
-            Expression::BlockExpression(source_ref, BlockExpression { statements, expr }) => {
+            Expression::BlockExpression(_, BlockExpression { statements, expr }) => {
                 let original_var_count = self.local_var_types.len();
 
                 for statement in statements {
@@ -708,8 +954,7 @@ impl TypeChecker {
                                 }
                                 (Some(ty), None) => {
                                     if *ty != Type::Col {
-                                        // TODO better source ref
-                                        return Err(source_ref.with_error("Let-declared variables without value must have type 'col'.".to_string()));
+                                        return Err(pattern.source_reference().with_error("Let-declared variables without value must have type 'col'.".to_string()));
                                     }
                                 }
                                 (None, Some(value)) => {
@@ -751,6 +996,22 @@ impl TypeChecker {
     /// Process a function call and return the type of the expression.
     /// The error message is used to clarify which kind of function call it is
     /// (it might be an operator).
+    /// If `function` references a symbol defined by a lambda with default parameter values
+    /// and `arguments` omits some of these parameters, appends their default values.
+    fn add_default_arguments(&self, function: &Expression, arguments: &mut Vec<Expression>) {
+        let Expression::Reference(_, Reference::Poly(PolynomialReference { name, .. })) = function
+        else {
+            return;
+        };
+        let Some((param_count, defaults)) = self.param_defaults.get(name) else {
+            return;
+        };
+        let omitted = param_count.saturating_sub(arguments.len());
+        if omitted > 0 && omitted <= defaults.len() {
+            arguments.extend(defaults[defaults.len() - omitted..].iter().cloned());
+        }
+    }
+
     fn infer_type_of_function_call<'b>(
         &mut self,
         function_type: Type,
@@ -771,11 +1032,12 @@ impl TypeChecker {
             .unify_types(function_type.clone(), expected_function_type.clone())
             .map_err(|err| {
                 source_ref.with_error(format!(
-                    "Expected function of type `{}`, but got `{}` when {} on ({}):\n{err}",
-                    self.format_type_with_bounds(expected_function_type),
-                    self.format_type_with_bounds(function_type),
+                    "Expected function of type `{}`, but got `{}` when {} on ({}):\n{err}{}",
+                    self.format_type_with_bounds(expected_function_type.clone()),
+                    self.format_type_with_bounds(function_type.clone()),
                     error_message(),
-                    arguments.iter().format(", ")
+                    arguments.iter().format(", "),
+                    self.instantiation_notes([&expected_function_type, &function_type])
                 ))
             })?;
 
@@ -796,9 +1058,10 @@ impl TypeChecker {
             .unify_types(inferred_type.clone(), expected_type.clone())
             .map_err(|err| {
                 expr.source_reference().with_error(format!(
-                    "Expected type: {}\nInferred type: {}\n{err}",
+                    "Expected type: {}\nInferred type: {}\n{err}{}",
                     self.format_type_with_bounds(expected_type.clone()),
-                    self.format_type_with_bounds(inferred_type)
+                    self.format_type_with_bounds(inferred_type.clone()),
+                    self.instantiation_notes([expected_type, &inferred_type])
                 ))
             })
     }
@@ -809,6 +1072,7 @@ impl TypeChecker {
         expected_type: &Type,
         pattern: &Pattern,
     ) -> Result<(), Error> {
+        let expected_type = &self.check_array_pattern_length(expected_type, pattern)?;
         let inferred_type = self.infer_type_of_pattern(pattern)?;
         self.unifier
             .unify_types(inferred_type.clone(), expected_type.clone())
@@ -821,10 +1085,39 @@ impl TypeChecker {
             })
     }
 
+    /// If `pattern` is an array pattern and the length of the expected array type is known,
+    /// checks that the pattern can match an array of that length.
+    /// Returns the expected type with the length removed, since the length is
+    /// not tracked for array patterns.
+    fn check_array_pattern_length(
+        &self,
+        expected_type: &Type,
+        pattern: &Pattern,
+    ) -> Result<Type, Error> {
+        let (
+            Pattern::Array(source_ref, items),
+            Type::Array(ArrayType {
+                base,
+                length: Some(length),
+            }),
+        ) = (pattern, self.type_into_substituted(expected_type.clone()))
+        else {
+            return Ok(expected_type.clone());
+        };
+        let has_ellipsis = items.iter().any(|i| matches!(i, Pattern::Ellipsis(_, _)));
+        let min_length = items.len() as u64 - u64::from(has_ellipsis);
+        if length < min_length || (!has_ellipsis && length != min_length) {
+            return Err(source_ref.with_error(format!(
+                "Array pattern {pattern} cannot match an array of length {length}."
+            )));
+        }
+        Ok(Type::Array(ArrayType { base, length: None }))
+    }
+
     /// Type-checks a pattern and adds local variables.
     fn infer_type_of_pattern(&mut self, pattern: &Pattern) -> Result<Type, Error> {
         Ok(match pattern {
-            Pattern::Ellipsis(_) => unreachable!("Should be handled higher up."),
+            Pattern::Ellipsis(_, _) => unreachable!("Should be handled higher up."),
             Pattern::CatchAll(_) => self.unifier.new_type_var(),
             Pattern::Number(source_ref, _) => {
                 let ty = self.unifier.new_type_var();
@@ -842,15 +1135,21 @@ impl TypeChecker {
             }),
             Pattern::Array(_, items) => {
                 let item_type = self.unifier.new_type_var();
+                let array_type = Type::Array(ArrayType {
+                    base: Box::new(item_type.clone()),
+                    length: None,
+                });
                 for item in items {
-                    if !matches!(item, Pattern::Ellipsis(_)) {
-                        self.expect_type_of_pattern(&item_type, item)?;
+                    match item {
+                        Pattern::Ellipsis(_, None) => {}
+                        // The rest of the array has the same type as the array.
+                        Pattern::Ellipsis(_, Some(_)) => {
+                            self.local_var_types.push(array_type.clone())
+                        }
+                        _ => self.expect_type_of_pattern(&item_type, item)?,
                     }
                 }
-                Type::Array(ArrayType {
-                    base: Box::new(item_type),
-                    length: None,
-                })
+                array_type
             }
             Pattern::Variable(_, _) => {
                 let ty = self.unifier.new_type_var();
@@ -910,6 +1209,27 @@ impl TypeChecker {
         })
     }
 
+    /// Checks that the operands of all casts recorded in `pending_casts` are int or fe.
+    /// Operands whose type is still unknown are assigned the target type of the cast
+    /// (a no-op cast), or the other one if the bounds of the type variable do not allow it.
+    fn resolve_pending_casts(&mut self) -> Result<(), Error> {
+        for (inner_type, ty, source_ref) in std::mem::take(&mut self.pending_casts) {
+            let inner_type = self.type_into_substituted(inner_type);
+            match &inner_type {
+                Type::Int | Type::Fe => {}
+                Type::TypeVar(_) => {
+                    let other = if ty == Type::Int { Type::Fe } else { Type::Int };
+                    self.unifier
+                        .unify_types(inner_type.clone(), ty.clone())
+                        .or_else(|_| self.unifier.unify_types(inner_type.clone(), other))
+                        .map_err(|_| unsupported_cast(&source_ref, &inner_type, &ty))?;
+                }
+                _ => return Err(unsupported_cast(&source_ref, &inner_type, &ty)),
+            }
+        }
+        Ok(())
+    }
+
     /// Returns, for each name declared with a type scheme, a mapping from
     /// the type variables used by the type checker to those used in the declaration.
     fn verify_type_schemes(
@@ -920,7 +1240,9 @@ impl TypeChecker {
             let (source_ref, declared_type) = self.declared_types[&name].clone();
             let inferred_type = self.type_into_substituted(inferred_type.clone());
             let inferred = self.to_type_scheme(inferred_type.clone());
-            let declared = declared_type.clone().simplify_type_vars();
+            // Type variables required to be equal by a `where` clause are inferred as a single one.
+            let normalized = declared_type.clone().apply_type_var_equalities();
+            let declared = normalized.clone().simplify_type_vars();
             if inferred != declared {
                 return Err(source_ref.with_error(format!(
                     "Inferred type scheme for symbol {name} does not match the declared type.\nInferred: let{}\nDeclared: let{}",
@@ -928,7 +1250,7 @@ impl TypeChecker {
                     format_type_scheme_around_name(&name, &Some(declared_type),
                 ))));
             }
-            let declared_type_vars = declared_type.ty.contained_type_vars();
+            let declared_type_vars = normalized.ty.contained_type_vars();
             let inferred_type_vars = inferred_type.contained_type_vars();
             Ok((name.clone(),
                 inferred_type_vars
@@ -949,6 +1271,58 @@ impl TypeChecker {
         self.unifier.substitute(ty);
     }
 
+    /// Explains where the type variables related to `types` come from, if they were
+    /// created by instantiating the type scheme of a referenced symbol. A type variable
+    /// is related if it shares a (transitively) substituted type variable with `types`.
+    /// Returns an empty string if there is nothing to explain.
+    fn instantiation_notes<'b>(&self, types: impl IntoIterator<Item = &'b Type>) -> String {
+        let relevant = self.reachable_type_vars(
+            types
+                .into_iter()
+                .flat_map(|ty| ty.contained_type_vars().cloned().collect::<Vec<_>>()),
+        );
+        let notes = self
+            .instantiations
+            .iter()
+            .filter(|(var, _)| {
+                !self
+                    .reachable_type_vars([(*var).clone()])
+                    .is_disjoint(&relevant)
+            })
+            .sorted_by_key(|(var, _)| var[1..].parse::<u64>().unwrap_or_default())
+            .map(|(var, (symbol, scheme_var))| {
+                let scheme = &self.declared_types[symbol].1;
+                format!(
+                    "  {scheme_var} of {} instantiated as {}",
+                    format_type_scheme_around_name(symbol, &Some(scheme.clone())),
+                    self.format_type_with_bounds(Type::TypeVar(var.clone()))
+                )
+            })
+            .collect::<Vec<_>>();
+        if notes.is_empty() {
+            String::new()
+        } else {
+            format!("\nInstantiations:\n{}", notes.join("\n"))
+        }
+    }
+
+    /// Returns the given type variables and all type variables reachable from them
+    /// through the current substitutions.
+    fn reachable_type_vars(&self, vars: impl IntoIterator<Item = String>) -> HashSet<String> {
+        let mut to_visit = vars.into_iter().collect::<Vec<_>>();
+        let mut visited = HashSet::new();
+        while let Some(var) = to_visit.pop() {
+            if visited.contains(&var) {
+                continue;
+            }
+            if let Some(sub) = self.unifier.substitution(&var) {
+                to_visit.extend(sub.contained_type_vars().cloned());
+            }
+            visited.insert(var);
+        }
+        visited
+    }
+
     fn format_type_with_bounds(&self, ty: Type) -> String {
         let scheme = self.to_type_scheme(ty);
         let bounds = scheme.vars.format_vars_with_nonempty_bounds();
@@ -983,6 +1357,70 @@ impl TypeChecker {
     }
 }
 
+/// Returns the error for a cast from `from` to `to`, which are not `int` and `fe`.
+fn unsupported_cast(source_ref: &SourceRef, from: &Type, to: &Type) -> Error {
+    source_ref.with_error(format!(
+        "Unsupported cast from {from} to {to}, only casts between int and fe are supported."
+    ))
+}
+
+/// Returns true if `expr` contains a reference to the symbol `name`.
+fn references_symbol(expr: &Expression, name: &str) -> bool {
+    expr.all_children()
+        .any(|e| matches!(e, Expression::Reference(_, Reference::Poly(r)) if r.name == name))
+}
+
+/// Returns the name among `candidates` with the smallest edit distance to `name`,
+/// provided it is close enough to be a plausible typo.
+fn closest_name<'a>(name: &str, candidates: impl Iterator<Item = &'a String>) -> Option<&'a str> {
+    candidates
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance * 3 <= name.chars().count())
+        .min()
+        .map(|(_, candidate)| candidate.as_str())
+}
+
+/// Computes the Levenshtein distance between `a` and `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Returns an error if match arms follow an arm with an irrefutable pattern,
+/// since such arms can never be reached.
+fn check_for_unreachable_arms(arms: &[MatchArm<Expression>]) -> Result<(), Error> {
+    let Some(catch_all) = arms.iter().position(|arm| arm.pattern.is_irrefutable()) else {
+        return Ok(());
+    };
+    let unreachable = arms[catch_all + 1..]
+        .iter()
+        .enumerate()
+        .map(|(i, MatchArm { pattern, .. })| format!("#{} ({pattern})", catch_all + i + 2))
+        .collect::<Vec<_>>();
+    if unreachable.is_empty() {
+        return Ok(());
+    }
+    Err(arms[catch_all + 1]
+        .pattern
+        .source_reference()
+        .with_error(format!(
+            "Unreachable match arm{} {}: arm #{} with pattern {} already matches all values.",
+            if unreachable.len() == 1 { "" } else { "s" },
+            unreachable.join(", "),
+            catch_all + 1,
+            arms[catch_all].pattern
+        )))
+}
+
 fn update_type_if_literal(
     expr: &mut powdr_ast::parsed::Expression<Reference>,
     expected_type: &Type,
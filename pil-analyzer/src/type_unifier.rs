@@ -91,6 +91,10 @@ impl Unifier {
                 self.unify_types(*a1.base, *a2.base)
             }
             (Type::Tuple(t1), Type::Tuple(t2)) => {
+                if t1.items.is_empty() || t2.items.is_empty() {
+                    // One of them is the unit type, but not both (they are not equal).
+                    return Err(format!("Cannot unify types {t1} and {t2}"));
+                }
                 if t1.items.len() != t2.items.len() {
                     return Err(format!(
                         "Tuple types have different number of items: {t1} and {t2}"
@@ -113,6 +117,11 @@ impl Unifier {
         }
     }
 
+    /// Returns the type directly substituted for the type variable `var`, if any.
+    pub fn substitution(&self, var: &str) -> Option<&Type> {
+        self.substitutions.get(var)
+    }
+
     /// Recursively applies the current substitutions to the type.
     pub fn substitute(&self, ty: &mut Type) {
         if let Type::TypeVar(n) = ty {
@@ -143,7 +152,12 @@ impl Unifier {
                 new_var
             })
             .collect::<Vec<_>>();
-        let substitutions = scheme.vars.vars().cloned().zip(vars.clone()).collect();
+        let substitutions: HashMap<_, _> = scheme.vars.vars().cloned().zip(vars.clone()).collect();
+        // Enforce the `where` clause. The type variables are fresh, so this cannot fail.
+        for (a, b) in scheme.vars.equalities() {
+            self.unify_types(substitutions[a].clone(), substitutions[b].clone())
+                .unwrap();
+        }
         ty.substitute_type_vars(&substitutions);
         (ty, vars)
     }
@@ -54,6 +54,17 @@ fn trivial() {
     assert_eq!(result, r#"21"#);
 }
 
+#[test]
+fn int_identity_compares() {
+    let src = r#"namespace Main(16);
+        let a: int = 3;
+        let x = a = 3;
+        let y = a = 4;
+    "#;
+    assert_eq!(parse_and_evaluate_symbol(src, "Main::x"), "true");
+    assert_eq!(parse_and_evaluate_symbol(src, "Main::y"), "false");
+}
+
 #[test]
 fn recursion() {
     let src = r#"namespace Main(16);
@@ -174,7 +185,7 @@ fn decimal_number_outside_field() {
 }
 
 #[test]
-#[should_panic = "Number literal 9999999999999999999999999999999 is too large for field element."]
+#[should_panic = "Literal 9999999999999999999999999999999 of type fe is too large for the field with modulus"]
 fn decimal_number_outside_field_for_fe() {
     let src = r#"
         let N: fe = 9999999999999999999999999999999;
@@ -339,6 +350,39 @@ fn match_skip_array_4() {
     );
 }
 
+#[test]
+fn match_array_rest() {
+    let src = r#"
+        let sum: fe[] -> fe = |arr| match arr {
+            [a, ..rest] => a + sum(rest),
+            [] => 0,
+        };
+        let inner: int[] -> int[] = |arr| match arr {
+            [_, ..rest, _] => rest,
+            _ => [],
+        };
+        let t = [sum([]), sum([1]), sum([1, 2, 3])];
+        let u = [inner([1]), inner([1, 2]), inner([1, 2, 3, 4])];
+    "#;
+    assert_eq!(parse_and_evaluate_symbol(src, "t"), "[0, 1, 6]".to_string());
+    assert_eq!(
+        parse_and_evaluate_symbol(src, "u"),
+        "[[], [], [2, 3]]".to_string()
+    );
+}
+
+#[test]
+fn optional_parameter() {
+    let src = r#"
+        let f: int, int, int -> int[] = |a, b = 2, c = 7| [a, b, c];
+        let x: int[][] = [f(1), f(1, 3), f(1, 3, 4)];
+    "#;
+    assert_eq!(
+        parse_and_evaluate_symbol(src, "x"),
+        "[[1, 2, 7], [1, 3, 7], [1, 3, 4]]".to_string()
+    );
+}
+
 #[test]
 fn unpack_fun() {
     let src = r#"
@@ -647,3 +691,24 @@ fn test_trait_function_call_cross_impl() {
 
     assert_eq!(parse_and_evaluate_symbol(input, "F::r"), "6".to_string());
 }
+
+#[test]
+fn int_fe_casts() {
+    let src = r#"
+        let a: int = 7;
+        let b: fe = a as fe;
+        let c: int = (b * 2) as int + 1;
+    "#;
+    assert_eq!(parse_and_evaluate_symbol(src, "b"), "7");
+    assert_eq!(parse_and_evaluate_symbol(src, "c"), "15");
+}
+
+#[test]
+#[should_panic = "Expected field element but got negative integer: -1"]
+fn cast_negative_int_to_fe() {
+    let src = r#"
+        let a: int = -1;
+        let b: fe = a as fe;
+    "#;
+    parse_and_evaluate_symbol(src, "b");
+}
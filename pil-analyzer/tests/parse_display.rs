@@ -399,11 +399,15 @@ fn used_undeclared_type_var() {
 }
 
 #[test]
-#[should_panic = "Unused type variable(s) in declaration: T"]
 fn declared_unused_type_var() {
     let input = r#"let<T> x: int = 8;"#;
-    let formatted = analyze_string(input).to_string();
-    assert_eq!(formatted, input);
+    let (analyzed, warnings) =
+        powdr_pil_analyzer::analyze_string_with_warnings::<GoldilocksField>(input).unwrap();
+    assert_eq!(analyzed.to_string(), "    let x: int = 8_int;\n");
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0]
+        .message()
+        .starts_with("Unused type variable(s) in declaration: T"));
 }
 
 #[test]
@@ -730,7 +734,7 @@ fn function_param_shadowing() {
 #[test]
 fn match_shadowing() {
     let input = "    let t: (int, int) -> int = |i| match i {
-        (_, x) => 2_int,
+        (1, x) => 2_int,
         (x, _) => 3_int,
     };
 ";
@@ -741,10 +745,20 @@ fn match_shadowing() {
 fn single_ellipsis() {
     let input = "    let t: int[] -> int = |i| match i {
         [1, .., 3] => 2_int,
-        [..] => 3_int,
         [.., 1] => 9_int,
         [7, 8, ..] => 2_int,
-        _ => -1_int,
+        [..] => 3_int,
+    };
+";
+    assert_eq!(input, analyze_string(input).to_string());
+}
+
+#[test]
+fn ellipsis_rest() {
+    let input = "    let t: int[] -> int[] = |i| match i {
+        [1, ..rest] => rest,
+        [..rest, 2] => rest,
+        _ => [],
     };
 ";
     assert_eq!(input, analyze_string(input).to_string());
@@ -995,3 +1009,46 @@ namespace B(8);
     let analyzed = analyze_string(input);
     assert_eq!(analyzed.to_string(), expected);
 }
+
+#[test]
+fn int_and_fe_returning_columns() {
+    let input = r#"namespace N(16);
+    let<T: Ord> min: T, T -> T = |a, b| if a < b { a } else { b };
+    col fixed c(i) { i * 2 };
+    col fixed d(i) { 2 * 3 };
+    col fixed e(i) { min(1, 2) };
+"#;
+    let expected = r#"namespace N(16);
+    let<T: Ord> min: T, T -> T = |a, b| if a < b { a } else { b };
+    col fixed c(i) { i * 2_int };
+    col fixed d(i) { 2_fe * 3_fe };
+    col fixed e(i) { N::min::<int>(1_int, 2_int) };
+"#;
+    let analyzed = analyze_string(input);
+    assert_eq!(analyzed.to_string(), expected);
+}
+
+#[test]
+fn casts() {
+    let input = r#"namespace N(16);
+    let a: int = 3;
+    let b: fe = (a + 1) as fe * 2;
+    let c: int = -b as int;
+"#;
+    let expected = r#"namespace N(16);
+    let a: int = 3_int;
+    let b: fe = (N::a + 1_int) as fe * 2_fe;
+    let c: int = -N::b as int;
+"#;
+    let analyzed = analyze_string(input);
+    assert_eq!(analyzed.to_string(), expected);
+}
+
+#[test]
+fn where_clause() {
+    let input = r#"namespace N(16);
+    let<T: Add, U> f: T, U -> T where T = U = |a, b| a + b;
+"#;
+    let analyzed = analyze_string(input);
+    assert_eq!(analyzed.to_string(), input);
+}
@@ -1,8 +1,12 @@
+use std::collections::HashMap;
+
 use itertools::Itertools;
-use powdr_ast::parsed::display::format_type_scheme_around_name;
+use powdr_ast::analyzed::{Expression, PolynomialReference, Reference};
+use powdr_ast::parsed::{display::format_type_scheme_around_name, types::Type, Number};
 use powdr_number::GoldilocksField;
 use powdr_parser::parse_type_scheme;
-use powdr_pil_analyzer::analyze_string;
+use powdr_parser_util::SourceRef;
+use powdr_pil_analyzer::{analyze_string, check_expression, infer_types_full};
 
 use pretty_assertions::assert_eq;
 
@@ -101,6 +105,28 @@ fn higher_order() {
     );
 }
 
+#[test]
+fn function_returning_unit() {
+    let input = "let f: int -> () = |i| ();
+    let g = |i| f(i);";
+    type_check(input, &[("f", "", "int -> ()"), ("g", "", "int -> ()")]);
+}
+
+#[test]
+fn function_taking_unit() {
+    let input = "let f: () -> int = |()| 7;
+    let x: int = f(());";
+    type_check(input, &[("f", "", "() -> int"), ("x", "", "int")]);
+}
+
+#[test]
+#[should_panic(expected = "Cannot unify types")]
+fn unit_is_not_a_tuple() {
+    let input = "let f: () -> int = |()| 7;
+    let x: int = f((1, 2));";
+    type_check(input, &[]);
+}
+
 #[test]
 #[should_panic(expected = "Cannot unify types")]
 fn invalid_recursive() {
@@ -108,6 +134,84 @@ fn invalid_recursive() {
     type_check(input, &[]);
 }
 
+#[test]
+#[should_panic = "Instantiations:\\n  T of <T: Add> double: T -> T instantiated as string"]
+fn nested_generic_instantiation_in_error() {
+    let input = "
+    let<T: Add> add: T, T -> T = |a, b| a + b;
+    let<T: Add> double: T -> T = |x| add(x, x);
+    let y: int = double(\"a\");
+    ";
+    type_check(input, &[]);
+}
+
+#[test]
+fn polymorphic_recursion() {
+    let input = "let<T> f: int, T -> int = |n, x| if n <= 0 { 0 } else { f(n - 1, (x, x)) };
+    let y: int = f(3, \"x\");";
+    type_check(input, &[("f", "T", "int, T -> int"), ("y", "", "int")]);
+}
+
+#[test]
+#[should_panic(
+    expected = "Note: f is recursive but does not have a declared type. Polymorphic recursion requires an explicit type annotation."
+)]
+fn polymorphic_recursion_without_annotation() {
+    let input = "let f = |n, x| if n <= 0 { 0 } else { f(n - 1, (x, x)) };";
+    type_check(input, &[]);
+}
+
+#[test]
+fn self_recursion() {
+    let input = "let f = |n| if n <= 0 { 1 } else { n * f(n - 1) };
+    let y: int = f(4);";
+    type_check(input, &[("f", "", "int -> int"), ("y", "", "int")]);
+}
+
+#[test]
+fn mutual_recursion() {
+    let input = "let a = |x| if x == 0 { 0 } else { b(x - 1) };
+    let b = |x| a(x);
+    let n: int = 3;
+    let y: int = a(n);";
+    type_check(
+        input,
+        &[
+            ("a", "", "int -> int"),
+            ("b", "", "int -> int"),
+            ("y", "", "int"),
+        ],
+    );
+}
+
+#[test]
+#[should_panic = "Could not derive a concrete type for symbol"]
+fn mutual_recursion_ambiguous_param() {
+    // The use only fixes the return type, the type of the
+    // literal argument (and thus of `x`) stays ambiguous.
+    let input = "let a = |x| if x == 0 { 0 } else { b(x - 1) };
+    let b = |x| a(x);
+    let y: int = a(3);";
+    type_check(input, &[]);
+}
+
+#[test]
+fn mutual_recursion_declared() {
+    let input = "let is_even: int -> bool = |n| if n == 0 { true } else { is_odd(n - 1) };
+    let is_odd: int -> bool = |n| if n == 0 { false } else { is_even(n - 1) };
+    let<T> count: int, T -> int = |n, x| if n <= 0 { 0 } else { count_pair(n - 1, (x, x)) };
+    let<T> count_pair: int, T -> int = |n, x| 1 + count(n, x);";
+    type_check(
+        input,
+        &[
+            ("is_even", "", "int -> bool"),
+            ("is_odd", "", "int -> bool"),
+            ("count", "T", "int, T -> int"),
+            ("count_pair", "T", "int, T -> int"),
+        ],
+    );
+}
+
 #[test]
 fn fold() {
     let input = "let<T1, T2> fold: int, (int -> T1), T2, (T2, T1 -> T2) -> T2 = |length, f, initial, folder|
@@ -237,6 +341,35 @@ fn lambda() {
     type_check(input, &[]);
 }
 
+#[test]
+fn expr_identity_is_constraint() {
+    let input = "
+    let x: col;
+    let y: col;
+    let c = || x = y;
+    let d = |i| x' = y + i;
+    ";
+    type_check(
+        input,
+        &[
+            ("c", "", "-> std::prelude::Constr"),
+            ("d", "", "expr -> std::prelude::Constr"),
+        ],
+    );
+}
+
+#[test]
+fn int_and_fe_identity_is_bool() {
+    let input = "
+    let a: int = 3;
+    let b: int = 4;
+    let eq_int = a = b;
+    let f: fe = 1;
+    let eq_fe = f = 2;
+    ";
+    type_check(input, &[("eq_int", "", "bool"), ("eq_fe", "", "bool")]);
+}
+
 #[test]
 #[should_panic = "Unable to derive concrete type for literal 3"]
 fn non_concrete_inner_type() {
@@ -384,6 +517,35 @@ fn specialization_of_non_generic_symbol2() {
     type_check(input, &[]);
 }
 
+#[test]
+fn explicit_type_args() {
+    let input = "
+        let<T: Add> double: T -> T = |x| x + x;
+        let y = double::<fe>(2);
+    ";
+    type_check(input, &[("y", "", "fe")]);
+}
+
+#[test]
+fn explicit_type_args_prefix() {
+    let input = "
+        let<T1, T2> pair: T1, T2 -> (T1, T2) = |a, b| (a, b);
+        let<T: FromLiteral> one: T = 1;
+        let y = pair::<fe>(one, 2_int);
+    ";
+    type_check(input, &[("y", "", "(fe, int)")]);
+}
+
+#[test]
+#[should_panic = "Expected 1 type arguments for symbol id, but got 2: int, int"]
+fn explicit_type_args_too_many() {
+    let input = "
+        let<T> id: T -> T = |x| x;
+        let y: int = id::<int, int>(1);
+    ";
+    type_check(input, &[]);
+}
+
 #[test]
 fn partial_specialization() {
     let input = "
@@ -479,6 +641,37 @@ fn enum_pattern() {
     type_check(input, &[("f", "", "X -> (int -> X)")]);
 }
 
+#[test]
+fn array_rest_pattern() {
+    let input = "
+    let<T> f: T[], (T, T[]) -> (T, T[]) = |q, d| match q {
+        [x, ..rest] => (x, rest),
+        _ => d,
+    };
+    ";
+    type_check(input, &[("f", "T", "T[], (T, T[]) -> (T, T[])")]);
+}
+
+#[test]
+fn optional_parameter() {
+    let input = "
+    let<T: Add + FromLiteral> f: T, T -> T = |a, b = 5| a + b;
+    let x: int = f(1);
+    let y: fe = f(1, 2);
+    ";
+    type_check(input, &[("x", "", "int"), ("y", "", "fe")]);
+}
+
+#[test]
+#[should_panic = "Type string does not satisfy trait FromLiteral."]
+fn optional_parameter_default_type_checked() {
+    let input = r#"
+    let f: int, string -> int = |a, b = 5| a;
+    let x = f(1, "b");
+    "#;
+    type_check(input, &[]);
+}
+
 #[test]
 #[should_panic = "Only one \\\"..\\\"-item allowed in array pattern"]
 fn multi_ellipsis() {
@@ -529,6 +722,45 @@ fn enum_too_many_fields() {
     type_check(input, &[]);
 }
 
+#[test]
+#[should_panic = "Unreachable match arm #3 (2): arm #2 with pattern _ already matches all values."]
+fn unreachable_arm_after_catch_all() {
+    let input = "
+    let f: int -> int = |i| match i {
+        1 => 2,
+        _ => 3,
+        2 => 4,
+    };
+    ";
+    type_check(input, &[]);
+}
+
+#[test]
+#[should_panic = "Unreachable match arms #3 (2), #4 (x): arm #2 with pattern _ already matches all values."]
+fn unreachable_arms_after_catch_all() {
+    let input = "
+    let f: int -> int = |i| match i {
+        1 => 2,
+        _ => 3,
+        2 => 4,
+        x => x,
+    };
+    ";
+    type_check(input, &[]);
+}
+
+#[test]
+#[should_panic = "Unreachable match arm #2 ((1, 2)): arm #1 with pattern (_, _) already matches all values."]
+fn unreachable_arm_after_irrefutable_tuple() {
+    let input = "
+    let f: (int, int) -> int = |i| match i {
+        (_, _) => 1,
+        (1, 2) => 2,
+    };
+    ";
+    type_check(input, &[]);
+}
+
 #[test]
 fn empty_function() {
     let input = "
@@ -852,3 +1084,197 @@ fn typed_literals() {
         ],
     );
 }
+
+#[test]
+fn int_fe_casts() {
+    let input = "
+        let a: int = 3;
+        let b = a as fe;
+        let c: fe = 5;
+        let d = c as int + 1;
+        let e = 7 as fe;
+        let f: int -> fe = |i| (i * 2) as fe;
+        ";
+    type_check(
+        input,
+        &[
+            ("b", "", "fe"),
+            ("d", "", "int"),
+            ("e", "", "fe"),
+            ("f", "", "int -> fe"),
+        ],
+    );
+}
+
+#[test]
+#[should_panic = "Unsupported cast from string to int"]
+fn unsupported_cast() {
+    let input = "
+        let a: string = \"abc\";
+        let b = a as int;
+        ";
+    type_check(input, &[]);
+}
+
+#[test]
+fn where_clause() {
+    let input = "
+        let<T, U> choose: bool, T, U -> T where T = U = |c, a, b| if c { a } else { b };
+        let x = choose(true, 1_int, 2);
+        ";
+    type_check(
+        input,
+        &[("choose", "T, U", "bool, T, U -> T"), ("x", "", "int")],
+    );
+}
+
+#[test]
+#[should_panic = "Expected type: int\\nInferred type: fe\\n"]
+fn where_clause_violated() {
+    let input = "
+        let<T, U> choose: bool, T, U -> T where T = U = |c, a, b| if c { a } else { b };
+        let x = choose(true, 1_int, 2_fe);
+        ";
+    type_check(input, &[]);
+}
+
+#[test]
+#[should_panic = "Undeclared type variable V in where clause of \"choose\"."]
+fn where_clause_undeclared_var() {
+    let input = "
+        let<T, U> choose: bool, T, U -> T where T = V = |c, a, b| a;
+        ";
+    type_check(input, &[]);
+}
+
+#[test]
+#[should_panic = "Literal 18446744069414584321 of type fe is too large for the field with modulus 18446744069414584321."]
+fn literal_too_large_for_field() {
+    let input = "let x: fe = 18446744069414584321;";
+    type_check(input, &[]);
+}
+
+#[test]
+fn literal_fits_field() {
+    let input = "
+        let x: fe = 18446744069414584320;
+        let y: int = 18446744069414584321;
+        ";
+    type_check(input, &[("x", "", "fe"), ("y", "", "int")]);
+}
+
+#[test]
+fn type_error_location() {
+    let input = "let x: int = 1;\nlet y: fe = x;\n";
+    let errors = analyze_string::<GoldilocksField>(input).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    let source_ref = errors[0].source_ref();
+    assert_eq!(&input[source_ref.start..source_ref.end], "x");
+    assert_eq!(source_ref.line_col(), Some((2, 13)));
+}
+
+#[test]
+fn all_inferred_types() {
+    let number = || {
+        Expression::Number(
+            SourceRef::unknown(),
+            Number {
+                value: 1u32.into(),
+                type_: None,
+            },
+        )
+    };
+    let mut x = number();
+    let mut y = Expression::Reference(
+        SourceRef::unknown(),
+        Reference::Poly(PolynomialReference {
+            name: "x".to_string(),
+            type_args: None,
+        }),
+    );
+    let mut z = number();
+    let definitions = HashMap::from([
+        ("x".to_string(), (None, Some(&mut x))),
+        ("y".to_string(), (Some(Type::Int.into()), Some(&mut y))),
+        (
+            "z".to_string(),
+            (Some(parse_type_scheme("T: FromLiteral", "T")), Some(&mut z)),
+        ),
+    ]);
+    let types = infer_types_full(definitions, &mut [])
+        .unwrap()
+        .into_iter()
+        .map(|(name, scheme)| {
+            format_type_scheme_around_name(&name, &Some(scheme))
+                .trim_start()
+                .to_string()
+        })
+        .sorted()
+        .collect::<Vec<_>>();
+    assert_eq!(types, vec!["<T: FromLiteral> z: T", "x: int", "y: int"]);
+}
+
+fn infer_reference_to(name: &str) -> String {
+    let mut x = Expression::Reference(
+        SourceRef::unknown(),
+        Reference::Poly(PolynomialReference {
+            name: name.to_string(),
+            type_args: None,
+        }),
+    );
+    let definitions = HashMap::from([("x".to_string(), (None, Some(&mut x)))]);
+    infer_types_full(definitions, &mut []).unwrap_err()[0]
+        .message()
+        .to_string()
+}
+
+#[test]
+fn unknown_symbol() {
+    assert_eq!(
+        infer_reference_to("qwertyuiop"),
+        "Unknown symbol qwertyuiop."
+    );
+}
+
+#[test]
+fn unknown_symbol_suggestion() {
+    assert_eq!(
+        infer_reference_to("std::array::length"),
+        "Unknown symbol std::array::length.\nDid you mean std::array::len?"
+    );
+}
+
+#[test]
+fn check_single_expression() {
+    let number = |value: u32| {
+        Expression::Number(
+            SourceRef::unknown(),
+            Number {
+                value: value.into(),
+                type_: None,
+            },
+        )
+    };
+    let check = |mut expr: Expression, expected: Type| {
+        // `x` is already typed and has no value.
+        let definitions = HashMap::from([("x".to_string(), (Some(Type::Int.into()), None))]);
+        check_expression(definitions, &mut expr, expected.into())
+    };
+
+    assert_eq!(check(number(1) + number(1), Type::Int).unwrap(), Type::Int);
+    assert_eq!(
+        check(number(1) + number(1), Type::Expr).unwrap(),
+        Type::Expr
+    );
+    let x = Expression::Reference(
+        SourceRef::unknown(),
+        Reference::Poly(PolynomialReference {
+            name: "x".to_string(),
+            type_args: None,
+        }),
+    );
+    assert_eq!(check(x.clone() + number(1), Type::Int).unwrap(), Type::Int);
+    assert!(check(x, Type::Fe).is_err());
+    let tuple = Expression::Tuple(SourceRef::unknown(), vec![number(1), number(2)]);
+    assert!(check(tuple + number(3), Type::Int).is_err());
+}
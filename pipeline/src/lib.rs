@@ -6,31 +6,70 @@ pub mod test_util;
 pub mod util;
 pub mod verify;
 
+use std::any::Any;
 use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::mpsc::Receiver;
 use std::sync::{Arc, Mutex};
 
 use serde::de::DeserializeOwned;
 
-pub use pipeline::Pipeline;
+pub use pipeline::{AssumedTuples, ColumnFormat, ColumnOrigin, Pipeline};
 
-pub use powdr_backend::{BackendType, Proof};
+pub use powdr_backend::{BackendType, BackendUnavailable, Proof};
 use powdr_executor::witgen::QueryCallback;
 
 use powdr_number::FieldElement;
 
-#[derive(Clone)]
-pub struct HostContext {
+/// The data the host provides to the guest, by channel.
+pub trait HostStore: Send + Sync {
+    /// Returns the bytes available on `channel`, if any.
+    fn get(&self, channel: u32) -> Option<&[u8]>;
+}
+
+impl HostStore for BTreeMap<u32, Vec<u8>> {
+    fn get(&self, channel: u32) -> Option<&[u8]> {
+        BTreeMap::get(self, &channel).map(Vec::as_slice)
+    }
+}
+
+pub struct HostContext<S = BTreeMap<u32, Vec<u8>>> {
     /// Simulates a file system where the guest can write to stdout, stderr, or any other file descriptor.
     /// After witgen the host can read what the guest wrote.
     pub file_data: Arc<Mutex<BTreeMap<u32, Vec<u8>>>>,
+    /// The data the guest can read through `Input` queries.
+    pub store: Arc<S>,
+}
+
+impl<S> Clone for HostContext<S> {
+    fn clone(&self) -> Self {
+        Self {
+            file_data: self.file_data.clone(),
+            store: self.store.clone(),
+        }
+    }
 }
 
 impl HostContext {
     pub fn new<T: FieldElement>() -> (Self, Arc<dyn QueryCallback<T>>) {
+        Self::with_store(BTreeMap::new())
+    }
+}
+
+impl<S: HostStore + 'static> HostContext<S> {
+    /// Creates a context whose `Input` queries are answered from `store`: index 0 of a
+    /// channel is the number of bytes, index `i` is the byte at position `i - 1`.
+    pub fn with_store<T: FieldElement>(store: S) -> (Self, Arc<dyn QueryCallback<T>>) {
         let ctx = Self {
             file_data: Arc::new(Mutex::new(BTreeMap::<u32, Vec<u8>>::new())),
+            store: Arc::new(store),
         };
-        let cb = ctx.query_callback();
+        let cb = Arc::new(into_query_callback(ctx.query_callback()));
         (ctx, cb)
     }
 
@@ -39,6 +78,12 @@ impl HostContext {
         fs.clear();
     }
 
+    /// Returns the raw bytes the guest wrote to `fd`, e.g. its stdout (1) or stderr (2).
+    pub fn output(&self, fd: u32) -> Vec<u8> {
+        let fs = self.file_data.lock().unwrap();
+        fs.get(&fd).cloned().unwrap_or_default()
+    }
+
     pub fn read<T: DeserializeOwned>(&self, fd: u32) -> Result<T, String> {
         let fs = self.file_data.lock().unwrap();
         if let Some(data) = fs.get(&fd) {
@@ -48,26 +93,41 @@ impl HostContext {
         }
     }
 
-    fn query_callback<T: FieldElement>(&self) -> Arc<dyn QueryCallback<T>> {
+    /// Returns a callback answering `Input` queries from the store and `Output` and `Clear`
+    /// queries on the file data of this context.
+    pub fn query_callback<T: FieldElement>(&self) -> impl PartialQueryCallback<T> {
         let fs = self.file_data.clone();
-        Arc::new(move |query: &str| -> Result<Option<T>, String> {
+        let store = self.store.clone();
+        move |query: &str| -> Result<Option<T>, QueryError> {
             let (id, data) = parse_query(query)?;
             match id {
+                "Input" => {
+                    let (channel, index) = parse_input_query(&data)?;
+                    let Some(bytes) = store.get(channel) else {
+                        return Err(QueryError::ChannelMismatch(channel));
+                    };
+
+                    // query index 0 means the length
+                    Ok(Some(match index {
+                        0 => (bytes.len() as u64).into(),
+                        index => (*bytes.get(index - 1).ok_or_else(|| {
+                            format!("Index {index} out of bounds for channel {channel}")
+                        })? as u64)
+                            .into(),
+                    }))
+                }
                 "Output" => {
-                    assert_eq!(data.len(), 2);
-                    let fd = data[0]
-                        .parse::<u32>()
-                        .map_err(|e| format!("Invalid fd: {e}"))?;
-                    let byte = data[1]
-                        .parse::<u8>()
-                        .map_err(|e| format!("Invalid char to print: {e}"))?
-                        as char;
+                    let (fd, bytes) = parse_output(&data)?;
                     match fd {
                         // stdin cannot be used for Output
-                        0 => return Err(format!("Unsupported file descriptor: {fd}")),
+                        0 => {
+                            return Err(QueryError::Failed(format!(
+                                "Unsupported file descriptor: {fd}"
+                            )))
+                        }
                         _ => {
                             let mut map = fs.lock().unwrap();
-                            map.entry(fd).or_default().push(byte as u8);
+                            map.entry(fd).or_default().extend(bytes);
                         }
                     }
                     Ok(Some(0.into()))
@@ -76,128 +136,823 @@ impl HostContext {
                     fs.lock().unwrap().clear();
                     Ok(Some(0.into()))
                 }
-                _ => Err(format!("Unsupported query: {query}")),
+                _ => Err(QueryError::UnsupportedQuery(query.to_string())),
             }
-        })
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompileError {
+    /// The input was rejected with the given errors.
+    Errors(Vec<String>),
+    /// The compiler panicked. This is a bug in the compiler, not in the input.
+    Panic(String),
+}
+
+impl Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompileError::Errors(errors) => write!(f, "{}", errors.join("\n")),
+            CompileError::Panic(message) => write!(f, "Compiler panicked: {message}"),
+        }
     }
 }
 
+/// Compiles the asm program in `source` down to optimized PIL, without panicking.
+/// Intended as an entry point for fuzzing the front end: errors in the input are
+/// returned as [CompileError::Errors] and panics are caught and returned as
+/// [CompileError::Panic].
+pub fn try_compile_asm<T: FieldElement>(source: &str) -> Result<(), CompileError> {
+    panic::catch_unwind(AssertUnwindSafe(|| {
+        Pipeline::<T>::default()
+            .from_asm_string(source.to_string(), None)
+            .compute_optimized_pil()
+            .map(|_| ())
+    }))
+    .map_err(|payload| CompileError::Panic(panic_message(payload.as_ref())))?
+    .map_err(CompileError::Errors)
+}
+
+/// Returns the message of a panic caught by [panic::catch_unwind].
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
 // TODO at some point, we could also just pass evaluator::Values around - would be much faster.
-pub fn parse_query(query: &str) -> Result<(&str, Vec<&str>), String> {
+pub fn parse_query(query: &str) -> Result<(&str, Vec<&str>), QueryParseError> {
     // We are expecting an enum value
     let query = query.strip_prefix("std::prelude::Query::").unwrap_or(query);
-    if let Some(paren) = query.find('(') {
+    let (name, data) = if let Some(paren) = query.find('(') {
         let name = &query[..paren];
         let data = query[paren + 1..].strip_suffix(')').ok_or_else(|| {
-            format!(
-                "Error parsing query input \"{query}\". Could not find closing ')' in enum data."
-            )
+            QueryParseError::MissingClosingParen {
+                query: query.to_string(),
+            }
         })?;
-        Ok((name, data.split(',').map(|s| s.trim()).collect::<Vec<_>>()))
+        (name, data.split(',').map(|s| s.trim()).collect::<Vec<_>>())
     } else {
-        Ok((query, vec![]))
+        (query, vec![])
+    };
+    if name.is_empty() {
+        return Err(QueryParseError::EmptyName {
+            query: query.to_string(),
+        });
+    }
+    Ok((name, data))
+}
+
+/// The reasons why [parse_query] can fail.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryParseError {
+    /// The query has arguments, but they are not terminated by `)`.
+    MissingClosingParen { query: String },
+    /// The query does not start with the name of the query.
+    EmptyName { query: String },
+}
+
+impl Display for QueryParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryParseError::MissingClosingParen { query } => write!(
+                f,
+                "Error parsing query input \"{query}\". Could not find closing ')' in enum data."
+            ),
+            QueryParseError::EmptyName { query } => write!(
+                f,
+                "Error parsing query input \"{query}\". Missing name of the query."
+            ),
+        }
+    }
+}
+
+impl From<QueryParseError> for String {
+    fn from(error: QueryParseError) -> Self {
+        error.to_string()
+    }
+}
+
+/// The start of the message of [QueryError::UnsupportedQuery].
+const UNSUPPORTED_QUERY: &str = "Unsupported query";
+/// The start of the message of [QueryError::ChannelMismatch].
+const CHANNEL_MISMATCH: &str = "Callback channel mismatch";
+
+/// The reasons why the query callbacks of this crate do not answer a query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryError {
+    /// The callback does not handle queries of this kind.
+    UnsupportedQuery(String),
+    /// The callback does not serve the requested channel or file descriptor.
+    ChannelMismatch(u32),
+    /// The callback handles the query, but failed to answer it.
+    Failed(String),
+}
+
+impl QueryError {
+    /// Returns true if the callback does not handle the query at all, as opposed to
+    /// failing to answer a query it handles.
+    pub fn is_not_handled(&self) -> bool {
+        matches!(
+            self,
+            QueryError::UnsupportedQuery(_) | QueryError::ChannelMismatch(_)
+        )
+    }
+
+    /// Returns true if `message` is the message of an error for which
+    /// [QueryError::is_not_handled] holds, i.e. it starts with `Unsupported query`
+    /// or `Callback channel mismatch`.
+    pub fn is_not_handled_message(message: &str) -> bool {
+        message.starts_with(UNSUPPORTED_QUERY) || message.starts_with(CHANNEL_MISMATCH)
+    }
+}
+
+impl Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryError::UnsupportedQuery(query) => write!(f, "{UNSUPPORTED_QUERY}: {query}"),
+            QueryError::ChannelMismatch(channel) => write!(f, "{CHANNEL_MISMATCH}: {channel}"),
+            QueryError::Failed(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl From<String> for QueryError {
+    fn from(message: String) -> Self {
+        QueryError::Failed(message)
+    }
+}
+
+impl From<QueryParseError> for QueryError {
+    fn from(error: QueryParseError) -> Self {
+        QueryError::Failed(error.to_string())
     }
 }
 
+/// A query callback which reports queries it does not handle through [QueryError].
+/// Use [into_query_callback] to pass it to witness generation or to combine it with
+/// others using [chain_query_callbacks].
+pub trait PartialQueryCallback<T>: Fn(&str) -> Result<Option<T>, QueryError> + Send + Sync {}
+impl<T, F> PartialQueryCallback<T> for F where
+    F: Fn(&str) -> Result<Option<T>, QueryError> + Send + Sync
+{
+}
+
+/// Turns `callback` into a [QueryCallback], converting its errors into strings.
+pub fn into_query_callback<T>(callback: impl PartialQueryCallback<T>) -> impl QueryCallback<T> {
+    move |query: &str| callback(query).map_err(|e| e.to_string())
+}
+
+/// Parses the arguments of an `Input(channel, index)` query into the channel and the index.
+fn parse_input_query(data: &[&str]) -> Result<(u32, usize), String> {
+    let [channel, index] = data[..] else {
+        return Err(format!(
+            "Expected channel and index for input query, got {data:?}"
+        ));
+    };
+    let channel = channel
+        .parse::<u32>()
+        .map_err(|e| format!("Error parsing callback data channel: {e}"))?;
+    let index = index
+        .parse::<usize>()
+        .map_err(|e| format!("Error parsing index: {e}"))?;
+    Ok((channel, index))
+}
+
 pub fn serde_data_to_query_callback<T: FieldElement>(
     channel: u32,
     bytes: Vec<u8>,
 ) -> impl QueryCallback<T> {
-    move |query: &str| -> Result<Option<T>, String> {
+    let channels = BTreeMap::from([(channel, bytes)]);
+    into_query_callback(serde_channels_to_query_callback(channels))
+}
+
+/// Like [serde_data_to_query_callback], but serves several channels at once, each one
+/// with its serialized data. Queries for other channels are rejected.
+pub fn serde_channels_to_query_callback<T: FieldElement>(
+    channels: BTreeMap<u32, Vec<u8>>,
+) -> impl PartialQueryCallback<T> {
+    move |query: &str| -> Result<Option<T>, QueryError> {
         let (id, data) = parse_query(query)?;
         match id {
             "Input" => {
-                let [cb_channel, index] = data[..] else {
-                    panic!()
+                let (cb_channel, index) = parse_input_query(&data)?;
+                let Some(bytes) = channels.get(&cb_channel) else {
+                    return Err(QueryError::ChannelMismatch(cb_channel));
                 };
-                let cb_channel = cb_channel
-                    .parse::<u32>()
-                    .map_err(|e| format!("Error parsing callback data channel: {e})"))?;
 
-                if channel != cb_channel {
-                    return Err("Callback channel mismatch".to_string());
+                // query index 0 means the length
+                match index {
+                    0 => Ok(Some((bytes.len() as u64).into())),
+                    index => bytes
+                        .get(index - 1)
+                        .map(|b| Some((*b as u64).into()))
+                        .ok_or_else(|| {
+                            QueryError::Failed(format!(
+                                "Input index {index} out of bounds for channel {cb_channel} (there are {} bytes)",
+                                bytes.len()
+                            ))
+                        }),
                 }
+            }
+            _ => Err(QueryError::UnsupportedQuery(query.to_string())),
+        }
+    }
+}
 
-                let index = index
-                    .parse::<usize>()
-                    .map_err(|e| format!("Error parsing index: {e})"))?;
+pub fn dict_data_to_query_callback<T: FieldElement>(
+    dict: BTreeMap<u32, Vec<T>>,
+) -> impl QueryCallback<T> {
+    into_query_callback(move |query: &str| -> Result<Option<T>, QueryError> {
+        let (id, data) = parse_query(query)?;
+        match id {
+            "Input" => {
+                let (cb_channel, index) = parse_input_query(&data)?;
+                let Some(elems) = dict.get(&cb_channel) else {
+                    return Err(QueryError::ChannelMismatch(cb_channel));
+                };
 
                 // query index 0 means the length
-                Ok(Some(match index {
-                    0 => (bytes.len() as u64).into(),
-                    index => (bytes[index - 1] as u64).into(),
+                match index {
+                    0 => Ok(Some((elems.len() as u64).into())),
+                    index => elems.get(index - 1).map(|e| Some(*e)).ok_or_else(|| {
+                        QueryError::Failed(format!(
+                            "Input index {index} out of bounds for channel {cb_channel} (there are {} elements)",
+                            elems.len()
+                        ))
+                    }),
+                }
+            }
+            _ => Err(QueryError::UnsupportedQuery(query.to_string())),
+        }
+    })
+}
+
+/// Serves a `rows` x `cols` matrix stored in row-major order in `data`.
+/// Following the convention of the `Input` query, indices are 1-based and
+/// row 0 is reserved for metadata: `Matrix(0, 0)` returns the number of rows
+/// and `Matrix(0, 1)` returns the number of columns.
+pub fn matrix_query_callback<T: FieldElement>(
+    rows: usize,
+    cols: usize,
+    data: Vec<T>,
+) -> impl PartialQueryCallback<T> {
+    assert_eq!(
+        data.len(),
+        rows * cols,
+        "Matrix data has {} elements, but expected {rows} x {cols}.",
+        data.len()
+    );
+    move |query: &str| -> Result<Option<T>, QueryError> {
+        let (id, data_args) = parse_query(query)?;
+        match id {
+            "Matrix" => {
+                let [row, col] = data_args[..] else {
+                    return Err(QueryError::Failed(format!(
+                        "Expected two indices for matrix query: {query}"
+                    )));
+                };
+                let row = row
+                    .parse::<usize>()
+                    .map_err(|e| format!("Error parsing row index: {e}"))?;
+                let col = col
+                    .parse::<usize>()
+                    .map_err(|e| format!("Error parsing column index: {e}"))?;
+
+                Ok(Some(match (row, col) {
+                    (0, 0) => (rows as u64).into(),
+                    (0, 1) => (cols as u64).into(),
+                    (0, col) => {
+                        return Err(QueryError::Failed(format!(
+                            "Invalid matrix metadata index: {col}"
+                        )))
+                    }
+                    (row, _) if row > rows => {
+                        return Err(QueryError::Failed(format!(
+                            "Row index {row} out of bounds (matrix has {rows} rows)"
+                        )))
+                    }
+                    (_, col) if col == 0 || col > cols => {
+                        return Err(QueryError::Failed(format!(
+                            "Column index {col} out of bounds (matrix has {cols} columns)"
+                        )))
+                    }
+                    (row, col) => data[(row - 1) * cols + (col - 1)],
                 }))
             }
-            _ => Err(format!("Unsupported query: {query}")),
+            _ => Err(QueryError::UnsupportedQuery(query.to_string())),
         }
     }
 }
 
-pub fn dict_data_to_query_callback<T: FieldElement>(
-    dict: BTreeMap<u32, Vec<T>>,
+/// Answers `JsonGet("a.b.c")` queries by navigating the path through `doc` and
+/// converting the numeric value found there to a field element. Path segments are
+/// separated by `.`; segments that are array indices select array elements.
+pub fn json_query_callback<T: FieldElement>(
+    doc: serde_json::Value,
+) -> impl PartialQueryCallback<T> {
+    move |query: &str| -> Result<Option<T>, QueryError> {
+        let (id, data) = parse_query(query)?;
+        match id {
+            "JsonGet" => {
+                let [path] = data[..] else {
+                    return Err(QueryError::Failed(format!(
+                        "Expected a single path for JSON query: {query}"
+                    )));
+                };
+                let path = path.trim_matches('"');
+                let value = path
+                    .split('.')
+                    .try_fold(&doc, |value, segment| match value {
+                        serde_json::Value::Object(fields) => fields.get(segment),
+                        serde_json::Value::Array(items) => {
+                            segment.parse::<usize>().ok().and_then(|i| items.get(i))
+                        }
+                        _ => None,
+                    })
+                    .ok_or_else(|| format!("JSON path \"{path}\" not found"))?;
+                let serde_json::Value::Number(number) = value else {
+                    return Err(QueryError::Failed(format!(
+                        "Value at JSON path \"{path}\" is not a number: {value}"
+                    )));
+                };
+                if let Some(n) = number.as_u64() {
+                    Ok(Some(n.into()))
+                } else if let Some(n) = number.as_i64() {
+                    Ok(Some(-T::from(n.unsigned_abs())))
+                } else {
+                    Err(QueryError::Failed(format!(
+                        "Value at JSON path \"{path}\" is not an integer: {value}"
+                    )))
+                }
+            }
+            _ => Err(QueryError::UnsupportedQuery(query.to_string())),
+        }
+    }
+}
+
+/// Wraps `inner` such that successful answers are cached by the exact query string
+/// and repeated queries are not forwarded to `inner` again. Errors are not cached.
+/// This assumes `inner` is pure, i.e. always returns the same answer for the same query.
+pub fn memoizing_query_callback<T: FieldElement>(
+    inner: impl PartialQueryCallback<T>,
+) -> impl PartialQueryCallback<T> {
+    let cache = Mutex::new(BTreeMap::<String, Option<T>>::new());
+    move |query: &str| -> Result<Option<T>, QueryError> {
+        if let Some(answer) = cache.lock().unwrap().get(query) {
+            return Ok(*answer);
+        }
+        let answer = inner(query)?;
+        cache.lock().unwrap().insert(query.to_string(), answer);
+        Ok(answer)
+    }
+}
+
+/// Handler for a single kind of query, receiving the (already parsed) query arguments.
+pub type QueryHandler<T> = dyn Fn(&[&str]) -> Result<Option<T>, String> + Send + Sync;
+
+/// Builds a query callback from handlers registered by query name, e.g.
+/// `QueryRouter::default().on("Input", ...).on("Output", ...).into_callback()`.
+/// Queries whose name has no registered handler result in an unsupported query error.
+pub struct QueryRouter<T> {
+    handlers: BTreeMap<String, Box<QueryHandler<T>>>,
+}
+
+impl<T> Default for QueryRouter<T> {
+    fn default() -> Self {
+        Self {
+            handlers: Default::default(),
+        }
+    }
+}
+
+impl<T: FieldElement> QueryRouter<T> {
+    /// Registers `handler` for queries named `name`, replacing any previously
+    /// registered handler for that name.
+    pub fn on(
+        mut self,
+        name: &str,
+        handler: impl Fn(&[&str]) -> Result<Option<T>, String> + Send + Sync + 'static,
+    ) -> Self {
+        self.handlers.insert(name.to_string(), Box::new(handler));
+        self
+    }
+
+    pub fn into_callback(self) -> impl PartialQueryCallback<T> {
+        move |query: &str| -> Result<Option<T>, QueryError> {
+            let (id, data) = parse_query(query)?;
+            match self.handlers.get(id) {
+                Some(handler) => handler(&data).map_err(QueryError::Failed),
+                None => Err(QueryError::UnsupportedQuery(query.to_string())),
+            }
+        }
+    }
+}
+
+/// Combines `callbacks` into a single callback which asks them in order and returns the first
+/// answer, i.e. the first `Ok(Some(..))` or `Ok(None)`.
+/// An error falls through to the next callback if the callback does not handle the query,
+/// which it reports by an error starting with `Unsupported query` or `Callback channel mismatch`
+/// (see [QueryError::is_not_handled_message]). All other errors, e.g. for malformed queries,
+/// are returned immediately. If no callback handles the query, the error of the last one
+/// is returned.
+pub fn chain_query_callbacks<T: FieldElement>(
+    callbacks: Vec<Box<dyn QueryCallback<T>>>,
 ) -> impl QueryCallback<T> {
     move |query: &str| -> Result<Option<T>, String> {
+        let mut error = QueryError::UnsupportedQuery(query.to_string()).to_string();
+        for callback in &callbacks {
+            match callback(query) {
+                Err(e) if QueryError::is_not_handled_message(&e) => error = e,
+                result => return result,
+            }
+        }
+        Err(error)
+    }
+}
+
+pub fn inputs_to_query_callback<T: FieldElement>(inputs: Vec<T>) -> impl QueryCallback<T> {
+    let channels = BTreeMap::from([(0, inputs)]);
+    into_query_callback(channeled_inputs_to_query_callback(channels))
+}
+
+/// Serves `Input(channel, index)` queries from the inputs of the respective channel.
+/// `Input(index)` is short for `Input(0, index)`. Index 0 is the number of inputs of the
+/// channel and index `i` is the input at position `i - 1`.
+pub fn channeled_inputs_to_query_callback<T: FieldElement>(
+    channels: BTreeMap<u32, Vec<T>>,
+) -> impl PartialQueryCallback<T> {
+    move |query: &str| -> Result<Option<T>, QueryError> {
         let (id, data) = parse_query(query)?;
         match id {
             "Input" => {
-                let [cb_channel, index] = data[..] else {
-                    panic!()
+                let (channel, index) = match data[..] {
+                    [index] => parse_input_query(&["0", index])?,
+                    _ => parse_input_query(&data)?,
+                };
+                let Some(inputs) = channels.get(&channel) else {
+                    return Err(QueryError::ChannelMismatch(channel));
                 };
-                let cb_channel = cb_channel
-                    .parse::<u32>()
-                    .map_err(|e| format!("Error parsing callback data channel: {e})"))?;
+                Ok(Some(access_element(inputs, channel, index)?))
+            }
+            _ => Err(QueryError::UnsupportedQuery(query.to_string())),
+        }
+    }
+}
 
-                let Some(elems) = dict.get(&cb_channel) else {
-                    return Err("Callback channel mismatch".to_string());
+/// Returns the number of `inputs` of `channel` for index 0 and the input at `index - 1` otherwise.
+fn access_element<T: FieldElement>(inputs: &[T], channel: u32, index: usize) -> Result<T, String> {
+    match index {
+        0 => Ok((inputs.len() as u64).into()),
+        index => inputs.get(index - 1).cloned().ok_or_else(|| {
+            format!(
+                "Input index {index} out of bounds for channel {channel} (there are {} inputs)",
+                inputs.len()
+            )
+        }),
+    }
+}
+
+/// Like [inputs_to_query_callback], but requires the inputs to be read sequentially:
+/// apart from the length query (index 0), each query has to request the index following
+/// the previously requested one, starting at 1. Re-reading or skipping an index is an error.
+pub fn sequential_inputs_to_query_callback<T: FieldElement>(
+    inputs: Vec<T>,
+) -> impl PartialQueryCallback<T> {
+    let last_index = Mutex::new(0);
+    move |query: &str| -> Result<Option<T>, QueryError> {
+        let (id, data) = parse_query(query)?;
+        match id {
+            "Input" => {
+                let (cb_channel, index) = parse_input_query(&data)?;
+                if cb_channel != 0 {
+                    return Err(QueryError::ChannelMismatch(cb_channel));
+                }
+
+                // query index 0 means the length
+                if index == 0 {
+                    return Ok(Some((inputs.len() as u64).into()));
+                }
+
+                let mut last_index = last_index.lock().unwrap();
+                if index != *last_index + 1 {
+                    return Err(QueryError::Failed(format!(
+                        "Input index {index} requested out of order, expected index {}",
+                        *last_index + 1
+                    )));
+                }
+                let value = *inputs.get(index - 1).ok_or_else(|| {
+                    format!(
+                        "Input index {index} out of bounds (there are {} inputs)",
+                        inputs.len()
+                    )
+                })?;
+                *last_index = index;
+                Ok(Some(value))
+            }
+            _ => Err(QueryError::UnsupportedQuery(query.to_string())),
+        }
+    }
+}
+
+/// Serves `Input(0, index)` queries from values received through `receiver`, which
+/// allows the host to provide inputs while witness generation is running. A query
+/// blocks until the requested input has been received. Received inputs are kept, so
+/// they can be queried again. The length query (index 0) is not supported, since the
+/// number of inputs is not known in advance.
+pub fn receiver_to_query_callback<T: FieldElement>(
+    receiver: Receiver<T>,
+) -> impl PartialQueryCallback<T> {
+    let state = Mutex::new((receiver, vec![]));
+    move |query: &str| -> Result<Option<T>, QueryError> {
+        let (id, data) = parse_query(query)?;
+        match id {
+            "Input" => {
+                let (channel, index) = match data[..] {
+                    [index] => parse_input_query(&["0", index])?,
+                    _ => parse_input_query(&data)?,
                 };
+                if channel != 0 {
+                    return Err(QueryError::ChannelMismatch(channel));
+                }
+                if index == 0 {
+                    return Err(QueryError::Failed(
+                        "The number of inputs is not known for inputs received through a channel"
+                            .to_string(),
+                    ));
+                }
 
-                let index = index
-                    .parse::<usize>()
-                    .map_err(|e| format!("Error parsing index: {e})"))?;
+                let mut state = state.lock().unwrap();
+                let (receiver, received) = &mut *state;
+                while received.len() < index {
+                    let value = receiver.recv().map_err(|_| {
+                        format!(
+                            "Input channel closed before input {index} was received (received {} inputs)",
+                            received.len()
+                        )
+                    })?;
+                    received.push(value);
+                }
+                Ok(Some(received[index - 1]))
+            }
+            _ => Err(QueryError::UnsupportedQuery(query.to_string())),
+        }
+    }
+}
+
+/// Like [inputs_to_query_callback], but returns `default` for indices past the end
+/// of `inputs` instead of failing. The length query (index 0) still returns the
+/// number of actual inputs.
+///
+/// Note that this intentionally changes the semantics of out-of-bounds reads: a
+/// program reading more inputs than provided silently receives `default`.
+pub fn inputs_with_default_query_callback<T: FieldElement>(
+    inputs: Vec<T>,
+    default: T,
+) -> impl PartialQueryCallback<T> {
+    move |query: &str| -> Result<Option<T>, QueryError> {
+        let (id, data) = parse_query(query)?;
+        match id {
+            "Input" => {
+                let (cb_channel, index) = parse_input_query(&data)?;
+                if cb_channel != 0 {
+                    return Err(QueryError::ChannelMismatch(cb_channel));
+                }
 
                 // query index 0 means the length
                 Ok(Some(match index {
-                    0 => (elems.len() as u64).into(),
-                    index => elems[index - 1],
+                    0 => (inputs.len() as u64).into(),
+                    index => inputs.get(index - 1).copied().unwrap_or(default),
                 }))
             }
-            _ => Err(format!("Unsupported query: {query}")),
+            _ => Err(QueryError::UnsupportedQuery(query.to_string())),
         }
     }
 }
 
-pub fn inputs_to_query_callback<T: FieldElement>(inputs: Vec<T>) -> impl QueryCallback<T> {
-    let mut dict = BTreeMap::new();
-    dict.insert(0, inputs);
-    dict_data_to_query_callback(dict)
+/// Answers `Input(0, index)` queries from a binary file of field elements, each encoded
+/// in `element_size` little-endian bytes. The file is memory-mapped, so only the
+/// requested elements are read. Index 0 returns the number of elements.
+///
+/// The file must not be modified while the callback is in use.
+pub fn mmap_inputs_to_query_callback<T: FieldElement>(
+    path: &Path,
+    element_size: usize,
+) -> Result<impl PartialQueryCallback<T>, io::Error> {
+    let file = File::open(path)?;
+    // Safety: we rely on the file not being modified while it is mapped, see above.
+    let map = unsafe { memmap2::Mmap::map(&file)? };
+    if element_size == 0 || map.len() % element_size != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "File size {} is not a multiple of the element size {element_size}",
+                map.len()
+            ),
+        ));
+    }
+    let count = map.len() / element_size;
+    Ok(move |query: &str| -> Result<Option<T>, QueryError> {
+        let (id, data) = parse_query(query)?;
+        match id {
+            "Input" => {
+                let (cb_channel, index) = parse_input_query(&data)?;
+                if cb_channel != 0 {
+                    return Err(QueryError::ChannelMismatch(cb_channel));
+                }
+
+                // query index 0 means the length
+                if index == 0 {
+                    return Ok(Some((count as u64).into()));
+                }
+                if index > count {
+                    return Err(QueryError::Failed(format!(
+                        "Input index {index} out of bounds (there are {count} inputs)"
+                    )));
+                }
+                let start = (index - 1) * element_size;
+                Ok(Some(T::from_bytes_le(&map[start..start + element_size])))
+            }
+            _ => Err(QueryError::UnsupportedQuery(query.to_string())),
+        }
+    })
+}
+
+/// A running external process answering queries, see [subprocess_query_callback].
+struct QueryProcess {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl QueryProcess {
+    fn spawn(command: &[String]) -> Result<Self, String> {
+        let (program, args) = command
+            .split_first()
+            .ok_or_else(|| "Empty command for external query process".to_string())?;
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Could not start external query process `{program}`: {e}"))?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        Ok(Self {
+            child,
+            stdin,
+            stdout,
+        })
+    }
+
+    /// Sends `query` and returns the trimmed response line.
+    fn ask(&mut self, query: &str) -> Result<String, String> {
+        let mut response = String::new();
+        let sent = writeln!(self.stdin, "{query}").and_then(|_| self.stdin.flush());
+        let received = sent.and_then(|_| self.stdout.read_line(&mut response));
+        match received {
+            Ok(0) | Err(_) => Err(self.termination_error()),
+            Ok(_) => Ok(response.trim().to_string()),
+        }
+    }
+
+    fn termination_error(&mut self) -> String {
+        match self.child.try_wait() {
+            Ok(Some(status)) => format!("External query process terminated ({status})"),
+            _ => "External query process closed its input or output".to_string(),
+        }
+    }
+}
+
+impl Drop for QueryProcess {
+    fn drop(&mut self) {
+        // The process might be waiting for further queries.
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Forwards queries to an external process, which is started from `command`
+/// (the program followed by its arguments) when the first query arrives.
+/// Each query is written as a single line to the standard input of the process,
+/// which has to respond with a single line on its standard output: either a field
+/// element or `None` if it does not handle the query.
+/// Once the process terminated, all further queries result in an error.
+pub fn subprocess_query_callback<T: FieldElement>(
+    command: Vec<String>,
+) -> impl PartialQueryCallback<T> {
+    let process = Mutex::new(None::<QueryProcess>);
+    move |query: &str| -> Result<Option<T>, QueryError> {
+        let mut process = process.lock().unwrap();
+        if process.is_none() {
+            *process = Some(QueryProcess::spawn(&command)?);
+        }
+        let response = process.as_mut().unwrap().ask(query)?;
+        match response.as_str() {
+            "None" => Ok(None),
+            value => value.parse::<T>().map(Some).map_err(|e| {
+                QueryError::Failed(format!(
+                    "Invalid response of external query process to query {query}: {e}"
+                ))
+            }),
+        }
+    }
+}
+
+/// Parses the arguments of an `Output` query into the file descriptor and the bytes to write.
+/// The query is either `Output(fd, byte)` or `Output(fd, len, b_0, ..., b_{len - 1})`.
+fn parse_output(data: &[&str]) -> Result<(u32, Vec<u8>), String> {
+    let parse_byte = |b: &&str| {
+        b.parse::<u8>()
+            .map_err(|e| format!("Invalid char to print: {e}"))
+    };
+    let (fd, bytes) = match data {
+        [fd, byte] => (fd, vec![parse_byte(byte)?]),
+        [fd, len, bytes @ ..] => {
+            let len = len
+                .parse::<usize>()
+                .map_err(|e| format!("Invalid length: {e}"))?;
+            if len != bytes.len() {
+                return Err(format!(
+                    "Output query announces {len} bytes but contains {}",
+                    bytes.len()
+                ));
+            }
+            (fd, bytes.iter().map(parse_byte).collect::<Result<_, _>>()?)
+        }
+        _ => {
+            return Err(format!(
+                "Expected fd and bytes for output query, got {data:?}"
+            ))
+        }
+    };
+    let fd = fd.parse::<u32>().map_err(|e| format!("Invalid fd: {e}"))?;
+    Ok((fd, bytes))
 }
 
 #[allow(clippy::print_stdout)]
 pub fn handle_simple_queries_callback<'a, T: FieldElement>() -> impl QueryCallback<T> + 'a {
-    move |query: &str| -> Result<Option<T>, String> {
+    into_query_callback(move |query: &str| -> Result<Option<T>, QueryError> {
         let (id, data) = parse_query(query)?;
         match id {
             "None" => Ok(None),
             "Output" => {
-                assert_eq!(data.len(), 2);
-                let fd = data[0]
-                    .parse::<u32>()
-                    .map_err(|e| format!("Invalid fd: {e}"))?;
+                let (fd, bytes) = parse_output(&data)?;
                 if fd != 0 {
-                    return Err("Debug print requires output fd 0".to_string());
+                    return Err(QueryError::Failed(
+                        "Debug print requires output fd 0".to_string(),
+                    ));
                 }
-                let byte = data[1]
-                    .parse::<u8>()
-                    .map_err(|e| format!("Invalid char to print: {e}"))?
-                    as char;
-                print!("{byte}");
+                print!("{}", bytes.into_iter().map(char::from).collect::<String>());
                 Ok(Some(0.into()))
             }
             "Hint" => {
-                assert_eq!(data.len(), 1);
-                Ok(Some(T::from_str(data[0]).unwrap()))
+                let [value] = data[..] else {
+                    return Err(QueryError::Failed(format!(
+                        "Expected a single value for hint query: {query}"
+                    )));
+                };
+                let parsed = match value.strip_prefix("0x") {
+                    Some(hex) => T::from_str_radix(hex, 16),
+                    None => T::from_str_radix(value, 10),
+                };
+                parsed
+                    .map(Some)
+                    .map_err(|e| QueryError::Failed(format!("Invalid hint value {value}: {e}")))
+            }
+            _ => Err(QueryError::UnsupportedQuery(query.to_string())),
+        }
+    })
+}
+
+/// Serves `Output` queries by writing the bytes to the writer registered for the file
+/// descriptor. Without a registered writer, file descriptors 1 and 2 are written to the
+/// stdout and stderr of the host, writes to any other file descriptor are rejected.
+pub fn output_writers_callback<T: FieldElement>(
+    writers: BTreeMap<u32, Box<dyn Write + Send>>,
+) -> impl PartialQueryCallback<T> {
+    let writers = writers
+        .into_iter()
+        .map(|(fd, writer)| (fd, Mutex::new(writer)))
+        .collect::<BTreeMap<_, _>>();
+    move |query: &str| -> Result<Option<T>, QueryError> {
+        let (id, data) = parse_query(query)?;
+        match id {
+            "Output" => {
+                let (fd, bytes) = parse_output(&data)?;
+                let written = match (writers.get(&fd), fd) {
+                    (Some(writer), _) => writer.lock().unwrap().write_all(&bytes),
+                    (None, 1) => io::stdout().write_all(&bytes),
+                    (None, 2) => io::stderr().write_all(&bytes),
+                    (None, _) => return Err(QueryError::ChannelMismatch(fd)),
+                };
+                written.map_err(|e| format!("Error writing to file descriptor {fd}: {e}"))?;
+                Ok(Some(0.into()))
             }
-            _ => Err(format!("Unsupported query: {query}")),
+            _ => Err(QueryError::UnsupportedQuery(query.to_string())),
         }
     }
 }
@@ -4,22 +4,30 @@ use std::{
     fmt::Display,
     fs,
     io::{self, BufReader, BufWriter, Write},
+    panic::{self, AssertUnwindSafe},
     path::{Path, PathBuf},
     rc::Rc,
-    sync::Arc,
+    sync::{mpsc::Receiver, Arc},
     time::Instant,
 };
 
 use crate::util::PolySet;
+use itertools::Itertools;
 use log::Level;
 use mktemp::Temp;
+pub use powdr_asm_to_pil::ColumnOrigin;
+use powdr_asm_to_pil::ColumnOriginResolver;
 use powdr_ast::{
-    analyzed::Analyzed,
+    analyzed::{
+        Analyzed, Identity, LookupIdentity, PermutationIdentity, PhantomLookupIdentity,
+        PhantomPermutationIdentity,
+    },
     asm_analysis::AnalysisASMFile,
     object::MachineInstanceGraph,
     parsed::{asm::ASMProgram, PILFile},
 };
-use powdr_backend::{Backend, BackendOptions, BackendType, Proof};
+use powdr_backend::{Backend, BackendOptions, BackendType, BackendUnavailable, Proof};
+use powdr_backend_utils::referenced_namespaces_algebraic_expression;
 use powdr_executor::{
     constant_evaluator::{self, VariablySizedColumn},
     witgen::{
@@ -27,20 +35,67 @@ use powdr_executor::{
         WitgenCallbackContext, WitnessGenerator,
     },
 };
+use powdr_executor_utils::expression_evaluator::{ExpressionEvaluator, OwnedTerminalValues};
 pub use powdr_linker::{DegreeMode, LinkerMode, LinkerParams};
-use powdr_number::{write_polys_csv_file, CsvRenderMode, FieldElement, ReadWrite};
+use powdr_number::{write_polys_csv_file, CsvRenderMode, DegreeType, FieldElement, ReadWrite};
 use powdr_schemas::SerializedAnalyzed;
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use serde::{Deserialize, Serialize};
 
 use crate::{
     dict_data_to_query_callback, handle_simple_queries_callback, inputs_to_query_callback,
-    serde_data_to_query_callback,
+    into_query_callback, panic_message, receiver_to_query_callback,
+    serde_channels_to_query_callback, serde_data_to_query_callback,
     util::{FixedPolySet, WitnessPolySet},
 };
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 pub type Columns<T> = Vec<(String, Vec<T>)>;
 pub type VariablySizedColumns<T> = Vec<(String, VariablySizedColumn<T>)>;
 
+/// The tuples a lookup or permutation from one namespace into another requires to be
+/// present in the other namespace, see [Pipeline::assumed_tuples].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssumedTuples<T> {
+    /// The lookup or permutation, as it appears in the optimized PIL.
+    pub identity: String,
+    /// The namespace the right-hand side of the identity refers to.
+    pub target_namespace: String,
+    /// The distinct tuples selected on the left-hand side.
+    pub tuples: BTreeSet<Vec<T>>,
+}
+
+/// The file format used by [Pipeline::export_columns].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnFormat {
+    /// A header row with the column names, followed by one row per trace index.
+    Csv,
+    /// An object mapping each column name to the array of its values.
+    Json,
+}
+
+/// Version of the prover bundle format, to be increased on every change to [ProverBundle].
+const PROVER_BUNDLE_VERSION: u32 = 1;
+
+/// Everything a (remote) prover needs to compute a proof, see
+/// [Pipeline::export_prover_bundle] and [prove_from_bundle].
+#[derive(Serialize, Deserialize)]
+struct ProverBundle<T> {
+    version: u32,
+    pil: SerializedAnalyzed,
+    fixed_cols: VariablySizedColumns<T>,
+    witness: Columns<T>,
+    backend: String,
+    backend_options: BackendOptions,
+}
+
+/// Only the version of a [ProverBundle], used to check the version before
+/// deserializing the rest of the bundle.
+#[derive(Deserialize)]
+struct ProverBundleVersion {
+    version: u32,
+}
+
 #[derive(Default)]
 pub struct Artifacts<T: FieldElement> {
     /// The path to a single .asm file.
@@ -101,6 +156,8 @@ struct Arguments<T: FieldElement> {
     external_witness_values: Vec<(String, Vec<T>)>,
     /// Callback for queries for witness generation.
     query_callback: Option<Arc<dyn QueryCallback<T>>>,
+    /// Expected number of field elements per input channel, checked before witness generation.
+    input_schema: Option<BTreeMap<u32, usize>>,
     /// Backend to use for proving. If None, proving will fail.
     backend: Option<BackendType>,
     /// Backend options
@@ -123,6 +180,8 @@ struct Arguments<T: FieldElement> {
     vkey_app_file: Option<PathBuf>,
     /// The optional existing proof file to use for aggregation.
     existing_proof_file: Option<PathBuf>,
+    /// Whether warnings fail the stage that emitted them.
+    deny_warnings: bool,
 }
 
 #[derive(Clone)]
@@ -150,6 +209,8 @@ pub struct Pipeline<T: FieldElement> {
     host_context: HostContext,
     /// Initial memory given by the prover.
     initial_memory: Vec<Vec<u8>>,
+    /// The warnings emitted by all stages computed so far.
+    warnings: Vec<String>,
 }
 
 impl<T: FieldElement> Clone for Artifacts<T> {
@@ -196,6 +257,7 @@ where
             arguments: Arguments::default(),
             host_context: ctx,
             initial_memory: vec![],
+            warnings: vec![],
         }
         // We add the basic callback functionalities to support PrintChar and Hint.
         .add_query_callback(Arc::new(handle_simple_queries_callback()))
@@ -343,23 +405,65 @@ impl<T: FieldElement> Pipeline<T> {
     }
 
     pub fn add_data_vec<S: serde::Serialize + 'static>(self, data: &[(u32, S)]) -> Self {
-        data.iter()
-            .fold(self, |pipeline, data| pipeline.add_data(data.0, &data.1))
+        let channels = data
+            .iter()
+            .map(|(channel, data)| (*channel, serde_cbor::to_vec(data).unwrap()))
+            .collect();
+        self.add_query_callback(Arc::new(into_query_callback(
+            serde_channels_to_query_callback(channels),
+        )))
     }
 
     pub fn with_prover_inputs(self, inputs: Vec<T>) -> Self {
         self.add_query_callback(Arc::new(inputs_to_query_callback(inputs)))
     }
 
+    /// Provides the prover inputs of channel 0 through `receiver`, so that they can be
+    /// sent while witness generation is running. See [receiver_to_query_callback].
+    pub fn with_streamed_prover_inputs(self, receiver: Receiver<T>) -> Self {
+        self.add_query_callback(Arc::new(into_query_callback(receiver_to_query_callback(
+            receiver,
+        ))))
+    }
+
     pub fn with_prover_dict_inputs(self, inputs: BTreeMap<u32, Vec<T>>) -> Self {
         self.add_query_callback(Arc::new(dict_data_to_query_callback(inputs)))
     }
 
+    /// Declares how many field elements the prover is expected to provide on each
+    /// input channel. The inputs are validated against this schema before witness
+    /// generation, see [Pipeline::validate_inputs].
+    pub fn with_input_schema(mut self, schema: BTreeMap<u32, usize>) -> Self {
+        self.arguments.input_schema = Some(schema);
+        self
+    }
+
+    /// If set, any warning emitted by a stage of the pipeline is turned into an
+    /// error that fails the stage.
+    pub fn deny_warnings(mut self, deny: bool) -> Self {
+        self.arguments.deny_warnings = deny;
+        self
+    }
+
+    /// Returns the warnings emitted by all stages computed so far.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
     pub fn with_linker_params(mut self, linker_params: LinkerParams) -> Self {
         self.arguments.linker_params = linker_params;
         self
     }
 
+    /// Sets the degree of the main machine, overriding the degree declared in the source.
+    /// The degree has to be a power of two that fits into 32 bits.
+    pub fn with_degree(mut self, degree: DegreeType) -> Result<Self, String> {
+        let degree = u32::try_from(degree)
+            .map_err(|_| format!("The main degree {degree} does not fit into 32 bits"))?;
+        self.arguments.linker_params.main_degree = Some(degree);
+        Ok(self)
+    }
+
     pub fn with_backend(mut self, backend: BackendType, options: Option<BackendOptions>) -> Self {
         self.arguments.backend = Some(backend);
         self.arguments.backend_options = options.unwrap_or_default();
@@ -367,6 +471,20 @@ impl<T: FieldElement> Pipeline<T> {
         self
     }
 
+    /// Selects the backend called `name`, see [BackendType::from_name].
+    pub fn with_backend_name(
+        self,
+        name: &str,
+        options: Option<BackendOptions>,
+    ) -> Result<Self, BackendUnavailable> {
+        Ok(self.with_backend(BackendType::from_name(name)?, options))
+    }
+
+    /// Returns all backends enabled in this build.
+    pub fn available_backends() -> Vec<BackendType> {
+        BackendType::available()
+    }
+
     pub fn with_setup_file(mut self, setup_file: Option<PathBuf>) -> Self {
         self.arguments.setup_file = setup_file;
         self.artifact.backend = None;
@@ -562,6 +680,22 @@ impl<T: FieldElement> Pipeline<T> {
         log::log!(self.log_level, "{}", msg);
     }
 
+    /// Records the warnings emitted by a stage. Fails if warnings are denied
+    /// and there is at least one warning.
+    fn report_warnings(&mut self, warnings: Vec<String>) -> Result<(), Vec<String>> {
+        for warning in &warnings {
+            log::warn!("{warning}");
+        }
+        self.warnings.extend(warnings.iter().cloned());
+        if self.arguments.deny_warnings && !warnings.is_empty() {
+            return Err(warnings
+                .into_iter()
+                .map(|w| format!("Warning treated as error: {w}"))
+                .collect());
+        }
+        Ok(())
+    }
+
     /// Returns the path to the output file if the output directory is set.
     /// Fails if the file already exists and `force_overwrite` is false.
     fn path_if_should_write<F: FnOnce(&str) -> String>(
@@ -643,30 +777,7 @@ impl<T: FieldElement> Pipeline<T> {
             if let Some(path) =
                 self.path_if_should_write(|name| format!("{name}_all_columns.csv"))?
             {
-                // get the column size for each namespace. This assumes all witness columns of the same namespace have the same size.
-                let witness_sizes: HashMap<&str, u64> = witness
-                    .iter()
-                    .map(|(name, values)| {
-                        let namespace = name.split("::").next().unwrap();
-                        (namespace, values.len() as u64)
-                    })
-                    .collect();
-
-                // choose the fixed column of the correct size. This assumes any namespace with no witness columns has a unique size
-                let fixed_columns = fixed.iter().map(|(name, columns)| {
-                    let namespace = name.split("::").next().unwrap();
-                    let columns = witness_sizes
-                        .get(&namespace)
-                        // if we have witness columns, use their size
-                        .map(|size| columns.get_by_size(*size).unwrap())
-                        // otherwise, return the unique size
-                        .unwrap_or_else(|| columns.get_uniquely_sized().unwrap());
-                    (name, columns)
-                });
-
-                let columns = fixed_columns
-                    .chain(witness.iter().map(|(name, values)| (name, values.as_ref())))
-                    .collect::<Vec<_>>();
+                let columns = all_columns(fixed, witness);
 
                 let csv_file = fs::File::create(path).map_err(|e| vec![format!("{}", e)])?;
                 write_polys_csv_file(csv_file, self.arguments.csv_render_mode, &columns);
@@ -830,7 +941,7 @@ impl<T: FieldElement> Pipeline<T> {
             self.artifact.constrained_machine_collection = Some({
                 self.compute_optimized_asm()?;
                 let optimized_asm = self.artifact.optimized_asm.take().unwrap();
-                powdr_asm_to_pil::compile::<T>(optimized_asm)
+                powdr_asm_to_pil::compile::<T>(optimized_asm)?
             });
         }
 
@@ -877,8 +988,18 @@ impl<T: FieldElement> Pipeline<T> {
                 self.compute_linked_machine_graph()?;
                 let graph = self.artifact.linked_machine_graph.take().unwrap();
 
+                if let Some(main_degree) = self.arguments.linker_params.main_degree {
+                    let declared = &graph.objects[&graph.main.location].degree;
+                    if declared.min.is_some() || declared.max.is_some() {
+                        self.report_warnings(vec![format!(
+                            "The degree declared for the main machine is overridden by {main_degree}."
+                        )])?;
+                    }
+                }
+
                 self.log("Run linker");
-                let linked = powdr_linker::link(graph, self.arguments.linker_params)?;
+                let linked = powdr_linker::link(graph, self.arguments.linker_params.clone())
+                    .map_err(|errors| errors.iter().map(ToString::to_string).collect::<Vec<_>>())?;
                 log::trace!("{linked}");
                 self.maybe_write_pil(&linked, "")?;
 
@@ -898,38 +1019,41 @@ impl<T: FieldElement> Pipeline<T> {
         let linked = self.artifact.parsed_pil_file.take().unwrap();
 
         self.log("Analyzing PIL and computing constraints...");
-        let analyzed =
-            powdr_pil_analyzer::analyze_ast(linked).map_err(output_pil_analysis_errors)?;
+        let (analyzed, warnings) = powdr_pil_analyzer::analyze_ast_with_warnings(linked)
+            .map_err(output_pil_analysis_errors)?;
+        self.report_warnings(pil_analysis_warnings(warnings))?;
         self.maybe_write_pil(&analyzed, "_analyzed")?;
         self.log("done.");
 
         Ok(analyzed)
     }
 
-    fn compute_analyzed_pil_from_pil_file_path(&self) -> Result<Analyzed<T>, Vec<String>> {
+    fn compute_analyzed_pil_from_pil_file_path(&mut self) -> Result<Analyzed<T>, Vec<String>> {
         let pil_file = match self.artifact.pil_file_path {
             Some(ref path) => path,
             None => return Err(vec!["No pil file path available".to_string()]),
         };
 
         self.log("Analyzing PIL and computing constraints...");
-        let analyzed =
-            powdr_pil_analyzer::analyze_file(pil_file).map_err(output_pil_analysis_errors)?;
+        let (analyzed, warnings) = powdr_pil_analyzer::analyze_file_with_warnings(pil_file)
+            .map_err(output_pil_analysis_errors)?;
+        self.report_warnings(pil_analysis_warnings(warnings))?;
         self.maybe_write_pil(&analyzed, "_analyzed")?;
         self.log("done.");
 
         Ok(analyzed)
     }
 
-    fn compute_analyzed_pil_from_pil_string(&self) -> Result<Analyzed<T>, Vec<String>> {
+    fn compute_analyzed_pil_from_pil_string(&mut self) -> Result<Analyzed<T>, Vec<String>> {
         let pil_string = match self.artifact.pil_string {
             Some(ref s) => s,
             None => return Err(vec!["No pil string available".to_string()]),
         };
 
         self.log("Analyzing PIL and computing constraints...");
-        let analyzed =
-            powdr_pil_analyzer::analyze_string(pil_string).map_err(output_pil_analysis_errors)?;
+        let (analyzed, warnings) = powdr_pil_analyzer::analyze_string_with_warnings(pil_string)
+            .map_err(output_pil_analysis_errors)?;
+        self.report_warnings(pil_analysis_warnings(warnings))?;
         self.maybe_write_pil(&analyzed, "_analyzed")?;
         self.log("done.");
 
@@ -980,6 +1104,134 @@ impl<T: FieldElement> Pipeline<T> {
         Ok(self.artifact.optimized_pil.as_ref().unwrap().clone())
     }
 
+    /// Returns the fully linked and optimized PIL, serialized as CBOR.
+    /// The result can be loaded again using [Pipeline::import_pil_ast].
+    pub fn export_pil_ast(&mut self) -> Result<Vec<u8>, Vec<String>> {
+        let pil = self.compute_optimized_pil()?;
+        Analyzed::serialize(&pil).map_err(|e| vec![e])
+    }
+
+    /// Starts the pipeline from a PIL exported by [Pipeline::export_pil_ast].
+    pub fn import_pil_ast(self, bytes: &[u8]) -> Result<Self, Vec<String>> {
+        let analyzed = Analyzed::deserialize(bytes).map_err(|e| vec![e])?;
+        Ok(Pipeline {
+            artifact: Artifacts {
+                optimized_pil: Some(Arc::new(analyzed)),
+                ..Default::default()
+            },
+            ..self
+        })
+    }
+
+    /// Returns the asm construct each column of the optimized PIL originates from,
+    /// indexed by the absolute column name. Columns of machines without a pc are
+    /// reported as [ColumnOrigin::Other].
+    /// Only available if the pipeline was started from asm.
+    pub fn column_origins(&mut self) -> Result<BTreeMap<String, ColumnOrigin>, Vec<String>> {
+        // The asm artifacts are consumed by later stages, so analyze the asm again on the side.
+        let mut asm_pipeline = Pipeline::<T> {
+            artifact: Artifacts {
+                asm_file_path: self.artifact.asm_file_path.clone(),
+                asm_string: self.artifact.asm_string.clone(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let resolver =
+            ColumnOriginResolver::new(asm_pipeline.compute_optimized_asm()?, &Default::default());
+
+        let pil = self.compute_optimized_pil()?;
+        Ok(pil
+            .committed_polys_in_source_order()
+            .chain(pil.constant_polys_in_source_order())
+            .map(|(symbol, _)| {
+                let name = symbol.absolute_name.as_str();
+                let (namespace, column) = name.rsplit_once("::").unwrap_or(("", name));
+                (name.to_string(), resolver.origin(namespace, column))
+            })
+            .collect())
+    }
+
+    /// Returns the assumptions a proof of only `namespace` relies on: For every lookup
+    /// or permutation from `namespace` into a different namespace, the tuples selected
+    /// in `namespace`, evaluated on the witness. Such a proof cannot check these tuples
+    /// against the other namespace, so they have to be validated separately.
+    pub fn assumed_tuples(
+        &mut self,
+        namespace: &str,
+    ) -> Result<Vec<AssumedTuples<T>>, Vec<String>> {
+        let pil = self.compute_optimized_pil()?;
+        let fixed_cols = self.compute_fixed_cols()?;
+        let witness = self.compute_witness()?;
+
+        let in_namespace = |name: &str| name.rsplit_once("::").map(|(ns, _)| ns) == Some(namespace);
+        let witness = witness
+            .iter()
+            .filter(|(name, _)| in_namespace(name))
+            .cloned()
+            .collect::<Vec<_>>();
+        let Some(size) = witness.first().map(|(_, column)| column.len()) else {
+            return Err(vec![format!(
+                "Namespace {namespace} has no witness columns."
+            )]);
+        };
+        let fixed_cols = fixed_cols
+            .iter()
+            .filter(|(name, _)| in_namespace(name))
+            .filter_map(|(name, column)| {
+                let column = column.get_by_size(size as u64)?;
+                Some((name.clone(), column.to_vec()))
+            })
+            .collect();
+        let values = OwnedTerminalValues::new(&pil, witness, fixed_cols);
+        let intermediate_definitions = pil.intermediate_definitions();
+
+        Ok(pil
+            .identities
+            .iter()
+            .filter_map(|identity| {
+                let (left, right) = match identity {
+                    Identity::Lookup(LookupIdentity { left, right, .. })
+                    | Identity::PhantomLookup(PhantomLookupIdentity { left, right, .. })
+                    | Identity::Permutation(PermutationIdentity { left, right, .. })
+                    | Identity::PhantomPermutation(PhantomPermutationIdentity {
+                        left,
+                        right,
+                        ..
+                    }) => (left, right),
+                    _ => return None,
+                };
+                if referenced_namespaces_algebraic_expression(left)
+                    != BTreeSet::from([namespace.to_string()])
+                {
+                    return None;
+                }
+                let target_namespaces = referenced_namespaces_algebraic_expression(right);
+                let target_namespace = target_namespaces.into_iter().exactly_one().ok()?;
+                if target_namespace == namespace {
+                    return None;
+                }
+                let tuples = (0..size)
+                    .filter_map(|row| {
+                        let mut evaluator =
+                            ExpressionEvaluator::new(values.row(row), &intermediate_definitions);
+                        evaluator.evaluate(&left.selector).is_one().then(|| {
+                            left.expressions
+                                .iter()
+                                .map(|e| evaluator.evaluate(e))
+                                .collect()
+                        })
+                    })
+                    .collect();
+                Some(AssumedTuples {
+                    identity: identity.to_string(),
+                    target_namespace,
+                    tuples,
+                })
+            })
+            .collect())
+    }
+
     pub fn compute_fixed_cols(&mut self) -> Result<Arc<VariablySizedColumns<T>>, Vec<String>> {
         if let Some(ref fixed_cols) = self.artifact.fixed_cols {
             return Ok(fixed_cols.clone());
@@ -1005,6 +1257,48 @@ impl<T: FieldElement> Pipeline<T> {
         Ok(self.artifact.fixed_cols.as_ref().unwrap().clone())
     }
 
+    /// Checks the inputs provided through the query callback against the input schema
+    /// set via [Pipeline::with_input_schema], if any. For each channel, the length
+    /// is obtained through the `Input(channel, 0)` query.
+    pub fn validate_inputs(&self) -> Result<(), Vec<String>> {
+        let Some(schema) = &self.arguments.input_schema else {
+            return Ok(());
+        };
+        let query_callback = self.arguments.query_callback.as_ref();
+
+        let errors = schema
+            .iter()
+            .filter_map(|(&channel, &expected)| {
+                let Some(query_callback) = query_callback else {
+                    return Some(format!(
+                        "Input channel {channel}: expected {expected} elements, but no inputs were provided"
+                    ));
+                };
+                match query_callback(&format!("Input({channel}, 0)")) {
+                    Ok(Some(len)) if len == T::from(expected as u64) => None,
+                    Ok(Some(len)) => Some(format!(
+                        "Input channel {channel}: expected {expected} elements, but {len} were provided"
+                    )),
+                    Ok(None) => Some(format!(
+                        "Input channel {channel}: expected {expected} elements, but the length is unknown"
+                    )),
+                    Err(e) => Some(format!(
+                        "Input channel {channel}: expected {expected} elements, but the inputs could not be queried: {e}"
+                    )),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Runs witness generation using the configured query callback and returns the
+    /// named witness columns. No backend is set up, so this can be used to inspect
+    /// the witness without computing a proof.
     pub fn compute_witness(&mut self) -> Result<Arc<Columns<T>>, Vec<String>> {
         if let Some(ref witness) = self.artifact.witness {
             return Ok(witness.clone());
@@ -1012,27 +1306,18 @@ impl<T: FieldElement> Pipeline<T> {
 
         self.host_context.clear();
 
+        self.validate_inputs()?;
+
         let pil = self.compute_optimized_pil()?;
         let fixed_cols = self.compute_fixed_cols()?;
 
         assert_eq!(pil.constant_count(), fixed_cols.len());
 
-        let witness_cols: Vec<_> = pil
-            .committed_polys_in_source_order()
-            .flat_map(|(s, _)| s.array_elements().map(|(name, _)| name))
-            .collect();
+        let witness_cols = witness_column_names(&pil);
 
         let mut external_witness_values =
             std::mem::take(&mut self.arguments.external_witness_values);
-        // witgen needs external witness columns sorted by source order
-        external_witness_values.sort_by_key(|(name, _)| {
-            witness_cols
-                .iter()
-                .position(|n| n == name)
-                .unwrap_or_else(|| {
-                    panic!("external witness {name} does not exist in the optimized PIL")
-                })
-        });
+        sort_external_witness_values(&witness_cols, &mut external_witness_values);
 
         if witness_cols
             .iter()
@@ -1067,10 +1352,92 @@ impl<T: FieldElement> Pipeline<T> {
         Ok(self.artifact.witness.as_ref().unwrap().clone())
     }
 
+    /// Runs witness generation once for each of the given sets of prover inputs,
+    /// in parallel. The optimized PIL and the fixed columns are computed once and
+    /// shared between all runs. Each run uses the query callbacks of this pipeline,
+    /// extended by the respective inputs as in [Pipeline::with_prover_inputs].
+    /// Note that all runs share the [HostContext] of this pipeline.
+    ///
+    /// Returns the witness for each input set, in the same order, or the reason
+    /// why witness generation failed for that input set.
+    /// The witness stored in this pipeline is not modified.
+    pub fn witgen_batch(
+        &mut self,
+        input_sets: Vec<Vec<T>>,
+    ) -> Result<Vec<Result<Columns<T>, String>>, Vec<String>> {
+        let pil = self.compute_optimized_pil()?;
+        let fixed_cols = self.compute_fixed_cols()?;
+
+        let mut external_witness_values = self.arguments.external_witness_values.clone();
+        sort_external_witness_values(&witness_column_names(&pil), &mut external_witness_values);
+
+        self.log(&format!(
+            "Deducing witness columns for {} input sets...",
+            input_sets.len()
+        ));
+        let start = Instant::now();
+
+        let query_callback = self.arguments.query_callback.clone();
+        let witnesses = input_sets
+            .into_par_iter()
+            .map(|inputs| {
+                let inputs: Arc<dyn QueryCallback<T>> = Arc::new(inputs_to_query_callback(inputs));
+                let query_callback: Arc<dyn QueryCallback<T>> = match &query_callback {
+                    Some(callback) => Arc::new(chain_callbacks(callback.clone(), inputs)),
+                    None => inputs,
+                };
+                panic::catch_unwind(AssertUnwindSafe(|| {
+                    WitnessGenerator::new(&pil, &fixed_cols, query_callback.borrow())
+                        .with_external_witness_values(&external_witness_values)
+                        .generate()
+                }))
+                .map_err(|payload| panic_message(payload.as_ref()))
+            })
+            .collect();
+
+        self.log(&format!(
+            "Batch witness generation took {}s",
+            start.elapsed().as_secs_f32()
+        ));
+
+        Ok(witnesses)
+    }
+
     pub fn witness(&self) -> Result<Arc<Columns<T>>, Vec<String>> {
         Ok(self.artifact.witness.as_ref().unwrap().clone())
     }
 
+    /// Writes the fixed and witness columns to `path`, computing the witness if needed.
+    /// Values are rendered as unsigned decimal numbers.
+    pub fn export_columns(&mut self, path: &Path, format: ColumnFormat) -> Result<(), Vec<String>> {
+        let fixed = self.compute_fixed_cols()?;
+        let witness = self.compute_witness()?;
+        let columns = all_columns(&fixed, &witness);
+
+        let file = fs::File::create(path)
+            .map_err(|e| vec![format!("Error creating {}: {e}", path.display())])?;
+        match format {
+            ColumnFormat::Csv => {
+                write_polys_csv_file(file, CsvRenderMode::UnsignedBase10, &columns);
+            }
+            ColumnFormat::Json => {
+                let json = columns
+                    .into_iter()
+                    .map(|(name, values)| {
+                        let values = values
+                            .iter()
+                            .map(|v| v.to_arbitrary_integer().to_string().into())
+                            .collect();
+                        (name.clone(), serde_json::Value::Array(values))
+                    })
+                    .collect::<serde_json::Map<_, _>>();
+                serde_json::to_writer(BufWriter::new(file), &json)
+                    .map_err(|e| vec![format!("Error writing {}: {e}", path.display())])?;
+            }
+        }
+        Ok(())
+    }
+
     pub fn publics(&self) -> Result<Vec<(String, Option<T>)>, Vec<String>> {
         let pil = self.optimized_pil()?;
         let witness = self.witness()?;
@@ -1199,6 +1566,18 @@ impl<T: FieldElement> Pipeline<T> {
         Ok(self.artifact.proof.as_ref().unwrap())
     }
 
+    /// Writes the computed proof to `path`. Reading the file back yields a proof
+    /// which can be passed to [Pipeline::verify].
+    pub fn write_proof(&self, path: &Path) -> Result<(), Vec<String>> {
+        let proof = self
+            .artifact
+            .proof
+            .as_ref()
+            .ok_or_else(|| vec!["The proof has not been computed yet".to_string()])?;
+        fs::write(path, proof)
+            .map_err(|e| vec![format!("Error writing proof to {}: {e}", path.display())])
+    }
+
     pub fn output_dir(&self) -> &Option<PathBuf> {
         &self.output_dir
     }
@@ -1280,11 +1659,104 @@ impl<T: FieldElement> Pipeline<T> {
         })
     }
 
+    /// Serializes the optimized PIL, fixed columns, witness and backend choice into
+    /// a single bundle that can be proven elsewhere using [prove_from_bundle].
+    /// The witness and fixed columns need to have been computed before.
+    pub fn export_prover_bundle(&self) -> Result<Vec<u8>, String> {
+        let pil = self
+            .artifact
+            .optimized_pil
+            .as_ref()
+            .ok_or("Optimized PIL not computed yet")?;
+        let fixed_cols = self
+            .artifact
+            .fixed_cols
+            .as_ref()
+            .ok_or("Fixed columns not computed yet")?;
+        let witness = self
+            .artifact
+            .witness
+            .as_ref()
+            .ok_or("Witness not computed yet")?;
+        let backend = self.arguments.backend.ok_or("No backend selected")?;
+
+        let bundle = ProverBundle {
+            version: PROVER_BUNDLE_VERSION,
+            pil: SerializedAnalyzed::try_from(pil.as_ref())?,
+            fixed_cols: fixed_cols.as_ref().clone(),
+            witness: witness.as_ref().clone(),
+            backend: backend.to_string(),
+            backend_options: self.arguments.backend_options.clone(),
+        };
+        serde_cbor::to_vec(&bundle).map_err(|e| format!("Error serializing prover bundle: {e}"))
+    }
+
     pub fn host_context(&self) -> &HostContext {
         &self.host_context
     }
 }
 
+/// Computes a proof from a bundle created by [Pipeline::export_prover_bundle].
+pub fn prove_from_bundle<T: FieldElement>(bytes: &[u8]) -> Result<Proof, String> {
+    let ProverBundleVersion { version } = serde_cbor::from_slice(bytes)
+        .map_err(|e| format!("Error deserializing prover bundle: {e}"))?;
+    if version != PROVER_BUNDLE_VERSION {
+        return Err(format!(
+            "Invalid prover bundle version. Expected {PROVER_BUNDLE_VERSION} but got {version}"
+        ));
+    }
+    let bundle: ProverBundle<T> = serde_cbor::from_slice(bytes)
+        .map_err(|e| format!("Error deserializing prover bundle: {e}"))?;
+    let pil: Analyzed<T> = bundle.pil.try_into()?;
+    let backend = BackendType::from_name(&bundle.backend)
+        .map_err(|e| format!("Invalid backend in prover bundle: {e}"))?;
+
+    let mut pipeline = Pipeline {
+        artifact: Artifacts {
+            optimized_pil: Some(Arc::new(pil)),
+            fixed_cols: Some(Arc::new(bundle.fixed_cols)),
+            witness: Some(Arc::new(bundle.witness)),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+    .with_backend(backend, Some(bundle.backend_options));
+
+    pipeline.compute_proof().cloned().map_err(|e| e.join("\n"))
+}
+
+/// Returns the fixed columns followed by the witness columns. For each namespace, the fixed
+/// columns are taken in the size of the witness columns of that namespace.
+fn all_columns<'a, T: FieldElement>(
+    fixed: &'a VariablySizedColumns<T>,
+    witness: &'a Columns<T>,
+) -> Vec<(&'a String, &'a [T])> {
+    // get the column size for each namespace. This assumes all witness columns of the same namespace have the same size.
+    let witness_sizes: HashMap<&str, u64> = witness
+        .iter()
+        .map(|(name, values)| {
+            let namespace = name.split("::").next().unwrap();
+            (namespace, values.len() as u64)
+        })
+        .collect();
+
+    // choose the fixed column of the correct size. This assumes any namespace with no witness columns has a unique size
+    let fixed_columns = fixed.iter().map(|(name, columns)| {
+        let namespace = name.split("::").next().unwrap();
+        let columns = witness_sizes
+            .get(&namespace)
+            // if we have witness columns, use their size
+            .map(|size| columns.get_by_size(*size).unwrap())
+            // otherwise, return the unique size
+            .unwrap_or_else(|| columns.get_uniquely_sized().unwrap());
+        (name, columns)
+    });
+
+    fixed_columns
+        .chain(witness.iter().map(|(name, values)| (name, values.as_ref())))
+        .collect()
+}
+
 fn output_pil_analysis_errors(errors: Vec<powdr_parser_util::Error>) -> Vec<String> {
     eprintln!("Error analyzing PIL file:");
     errors
@@ -1295,3 +1767,29 @@ fn output_pil_analysis_errors(errors: Vec<powdr_parser_util::Error>) -> Vec<Stri
         })
         .collect()
 }
+
+fn pil_analysis_warnings(warnings: Vec<powdr_parser_util::Error>) -> Vec<String> {
+    warnings
+        .into_iter()
+        .map(|w| w.message().to_string())
+        .collect()
+}
+
+/// Returns the names of all witness columns of `pil`, in source order.
+fn witness_column_names<T>(pil: &Analyzed<T>) -> Vec<String> {
+    pil.committed_polys_in_source_order()
+        .flat_map(|(s, _)| s.array_elements().map(|(name, _)| name))
+        .collect()
+}
+
+/// Sorts the external witness values by source order, as required by witgen.
+fn sort_external_witness_values<T>(witness_cols: &[String], values: &mut [(String, Vec<T>)]) {
+    values.sort_by_key(|(name, _)| {
+        witness_cols
+            .iter()
+            .position(|n| n == name)
+            .unwrap_or_else(|| {
+                panic!("external witness {name} does not exist in the optimized PIL")
+            })
+    });
+}
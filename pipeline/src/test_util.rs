@@ -5,6 +5,7 @@ use powdr_number::{
 };
 use powdr_pil_analyzer::evaluator::{self, SymbolLookup};
 use std::env;
+use std::panic::{self, AssertUnwindSafe};
 use std::path::PathBuf;
 
 use std::sync::Arc;
@@ -30,6 +31,7 @@ pub fn make_simple_prepared_pipeline<T: FieldElement>(
     let linker_params = LinkerParams {
         mode: linker_mode,
         degree_mode: DegreeMode::Vadcop,
+        ..Default::default()
     };
     let mut pipeline = Pipeline::default()
         .with_tmp_output()
@@ -51,6 +53,7 @@ pub fn make_prepared_pipeline<T: FieldElement>(
     let linker_params = LinkerParams {
         mode: linker_mode,
         degree_mode: DegreeMode::Vadcop,
+        ..Default::default()
     };
     let mut pipeline = Pipeline::default()
         .with_tmp_output()
@@ -585,6 +588,65 @@ pub fn assert_proofs_fail_for_invalid_witnesses(file_name: &str, witness: &[(Str
     assert_proofs_fail_for_invalid_witnesses_halo2(file_name, witness);
 }
 
+/// Asserts that `backend` detects tampering with the proof of `file_name`:
+/// - Row 0 of the first witness column is incremented. Computing a proof from the
+///   modified witness then has to fail (by returning an error or panicking) or yield a
+///   proof the verifier rejects. For this to be meaningful, the cell has to be constrained.
+/// - Unless `backend` is the mock backend, which does not produce verifiable proofs,
+///   a byte in the middle of a valid proof is flipped and the verifier has to reject it
+///   (again, by returning an error or panicking).
+///
+/// Other backends whose proofs cannot be verified are not supported.
+pub fn assert_tamper_detected<T: FieldElement>(
+    file_name: &str,
+    backend: powdr_backend::BackendType,
+    inputs: Vec<T>,
+) {
+    let mut pipeline = Pipeline::default()
+        .with_tmp_output()
+        .from_file(resolve_test_file(file_name))
+        .with_prover_inputs(inputs)
+        .with_backend(backend, None);
+    let mut witness = pipeline.compute_witness().unwrap().as_ref().clone();
+    let verifies_proofs = !matches!(backend, powdr_backend::BackendType::Mock);
+
+    witness[0].1[0] += T::one();
+    let mut tampered = pipeline.clone().set_witness(witness);
+    let accepted = panic::catch_unwind(AssertUnwindSafe(|| {
+        let Ok(proof) = tampered.compute_proof().cloned() else {
+            return false;
+        };
+        !verifies_proofs || tampered.verify(&proof, &[publics_of(&tampered)]).is_ok()
+    }))
+    .unwrap_or(false);
+    assert!(
+        !accepted,
+        "Tampered cell in column {} was not detected by {backend}",
+        pipeline.witness().unwrap()[0].0
+    );
+
+    if verifies_proofs {
+        let mut proof = pipeline.compute_proof().cloned().unwrap();
+        let publics = publics_of(&pipeline);
+        let middle = proof.len() / 2;
+        proof[middle] ^= 1;
+        let accepted = panic::catch_unwind(AssertUnwindSafe(|| {
+            pipeline.verify(&proof, &[publics]).is_ok()
+        }))
+        .unwrap_or(false);
+        assert!(!accepted, "Tampered proof was accepted by {backend}");
+    }
+}
+
+fn publics_of<T: FieldElement>(pipeline: &Pipeline<T>) -> Vec<T> {
+    pipeline
+        .publics()
+        .unwrap()
+        .into_iter()
+        .map(|(_name, v)| v.expect("all publics should be known since we created a proof"))
+        .collect()
+}
+
 pub fn run_reparse_test(file: &str) {
     run_reparse_test_with_blacklist(file, &[]);
 }
@@ -1,4 +1,15 @@
-use std::{path::Path, process::Command};
+use std::{
+    fmt::{self, Display},
+    path::Path,
+    process::Command,
+    sync::Arc,
+};
+
+use powdr_ast::analyzed::Analyzed;
+use powdr_backend::{Backend, BackendOptions, BackendType};
+use powdr_number::FieldElement;
+
+use crate::pipeline::VariablySizedColumns;
 
 pub fn verify(temp_dir: &Path) -> Result<(), String> {
     let pilcom = std::env::var("PILCOM")
@@ -40,3 +51,104 @@ pub fn verify(temp_dir: &Path) -> Result<(), String> {
 
     result
 }
+
+/// The reasons why [verify_proof] can fail.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerificationError {
+    /// The backend could not be set up from the given artifacts.
+    Setup(String),
+    /// The backend cannot verify proofs.
+    Unsupported(String),
+    /// The proof was rejected.
+    Rejected(String),
+}
+
+impl Display for VerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerificationError::Setup(e) => write!(f, "Error setting up the backend: {e}"),
+            VerificationError::Unsupported(e) => write!(f, "Cannot verify the proof: {e}"),
+            VerificationError::Rejected(e) => write!(f, "The proof is invalid: {e}"),
+        }
+    }
+}
+
+/// Verifies `proof` with the given backend, without going through a [crate::Pipeline].
+/// Apart from the verification key `vkey`, the backend needs the optimized PIL and the
+/// fixed columns the proof was generated for.
+pub fn verify_proof<T: FieldElement>(
+    backend: BackendType,
+    backend_options: BackendOptions,
+    pil: Arc<Analyzed<T>>,
+    fixed_cols: Arc<VariablySizedColumns<T>>,
+    vkey: &[u8],
+    proof: &[u8],
+    publics: &[T],
+) -> Result<(), VerificationError> {
+    let backend = setup_verifier(backend, backend_options, pil, fixed_cols, vkey)?;
+    backend
+        .verify(proof, &[publics.to_vec()])
+        .map_err(into_verification_error)
+}
+
+/// Verifies `proof` against each of the `candidates` sets of public inputs in turn
+/// and returns the index of the first one that is accepted.
+/// Like [verify_proof], but the backend is only set up once for all candidates.
+pub fn verify_any_publics<T: FieldElement>(
+    backend: BackendType,
+    backend_options: BackendOptions,
+    pil: Arc<Analyzed<T>>,
+    fixed_cols: Arc<VariablySizedColumns<T>>,
+    vkey: &[u8],
+    candidates: &[Vec<T>],
+    proof: &[u8],
+) -> Result<usize, VerificationError> {
+    let backend = setup_verifier(backend, backend_options, pil, fixed_cols, vkey)?;
+    let mut rejections = vec![];
+    for (index, publics) in candidates.iter().enumerate() {
+        match backend
+            .verify(proof, std::slice::from_ref(publics))
+            .map_err(into_verification_error)
+        {
+            Ok(()) => return Ok(index),
+            Err(VerificationError::Rejected(e)) => {
+                rejections.push(format!("candidate {index}: {e}"))
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Err(VerificationError::Rejected(format!(
+        "None of the {} candidate public input sets verified the proof ({})",
+        candidates.len(),
+        rejections.join(", ")
+    )))
+}
+
+fn setup_verifier<T: FieldElement>(
+    backend: BackendType,
+    backend_options: BackendOptions,
+    pil: Arc<Analyzed<T>>,
+    fixed_cols: Arc<VariablySizedColumns<T>>,
+    vkey: &[u8],
+) -> Result<Box<dyn Backend<T>>, VerificationError> {
+    backend
+        .factory::<T>()
+        .create(
+            pil,
+            fixed_cols,
+            None,
+            None,
+            None,
+            Some(&mut &vkey[..]),
+            None,
+            backend_options,
+        )
+        .map_err(|e| VerificationError::Setup(e.to_string()))
+}
+
+fn into_verification_error(e: powdr_backend::Error) -> VerificationError {
+    match e {
+        powdr_backend::Error::BackendError(e) => VerificationError::Rejected(e),
+        e => VerificationError::Unsupported(e.to_string()),
+    }
+}
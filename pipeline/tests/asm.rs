@@ -1,5 +1,6 @@
 use std::collections::BTreeMap;
 
+use itertools::Itertools;
 use powdr_executor::constant_evaluator;
 use powdr_linker::{LinkerMode, LinkerParams};
 use powdr_number::{BabyBearField, FieldElement, GoldilocksField, Mersenne31Field};
@@ -9,7 +10,7 @@ use powdr_pipeline::{
         regular_test_all_fields, regular_test_gl, resolve_test_file, test_mock_backend,
         test_pilcom, test_plonky3_pipeline, BackendVariant,
     },
-    Pipeline,
+    ColumnFormat, ColumnOrigin, Pipeline,
 };
 use test_log::test;
 
@@ -50,6 +51,153 @@ fn simple_sum_asm() {
     regular_test_all_fields(f, &i);
 }
 
+#[test]
+fn simple_sum_witness_columns() {
+    let mut pipeline = Pipeline::<GoldilocksField>::default()
+        .from_file(resolve_test_file("asm/simple_sum.asm"))
+        .with_prover_inputs(slice_to_vec(&[16, 4, 1, 2, 8, 5]));
+    let witness = pipeline.compute_witness().unwrap();
+
+    let (_, cnt) = witness
+        .iter()
+        .find(|(name, _)| name == "main::CNT")
+        .unwrap();
+    // After the initial assignment, the counter is decremented once per loop iteration.
+    let start = cnt.iter().position(|v| *v == 4.into()).unwrap();
+    let countdown = cnt[start..]
+        .iter()
+        .copied()
+        .dedup()
+        .take_while(|v| *v != 0.into())
+        .collect::<Vec<_>>();
+    assert_eq!(countdown, vec![4.into(), 3.into(), 2.into(), 1.into()]);
+}
+
+#[test]
+fn simple_sum_with_degree() {
+    for degree in [32, 64] {
+        let mut pipeline = Pipeline::<GoldilocksField>::default()
+            .from_file(resolve_test_file("asm/simple_sum.asm"))
+            .with_degree(degree)
+            .unwrap();
+        let pil = pipeline.compute_optimized_pil().unwrap();
+        assert_eq!(pil.degree(), degree);
+        // simple_sum.asm declares a degree of 16.
+        assert!(pipeline.warnings().contains(&format!(
+            "The degree declared for the main machine is overridden by {degree}."
+        )));
+    }
+
+    let Err(error) = Pipeline::<GoldilocksField>::default().with_degree(1 << 32) else {
+        panic!("Expected the degree to be rejected");
+    };
+    assert_eq!(
+        error,
+        "The main degree 4294967296 does not fit into 32 bits"
+    );
+}
+
+#[test]
+fn simple_sum_export_columns() {
+    let tmp_dir = mktemp::Temp::new_dir().unwrap();
+    let mut pipeline = Pipeline::<GoldilocksField>::default()
+        .from_file(resolve_test_file("asm/simple_sum.asm"))
+        .with_prover_inputs(slice_to_vec(&[16, 4, 1, 2, 8, 5]));
+
+    let csv_path = tmp_dir.as_path().join("columns.csv");
+    pipeline
+        .export_columns(&csv_path, ColumnFormat::Csv)
+        .unwrap();
+    let csv = std::fs::read_to_string(&csv_path).unwrap();
+    let mut lines = csv.lines();
+    let header = lines.next().unwrap().split(',').collect::<Vec<_>>();
+    for column in ["main::pc", "main::A", "main::CNT"] {
+        assert!(header.contains(&column));
+    }
+    assert_eq!(lines.count(), 16);
+
+    let json_path = tmp_dir.as_path().join("columns.json");
+    pipeline
+        .export_columns(&json_path, ColumnFormat::Json)
+        .unwrap();
+    let json: BTreeMap<String, Vec<String>> =
+        serde_json::from_str(&std::fs::read_to_string(&json_path).unwrap()).unwrap();
+    assert_eq!(json["main::CNT"].len(), 16);
+    assert!(json["main::A"].contains(&"16".to_string()));
+}
+
+#[test]
+fn simple_sum_witgen_batch() {
+    let mut pipeline =
+        Pipeline::<GoldilocksField>::default().from_file(resolve_test_file("asm/simple_sum.asm"));
+    let input_sets = [&[16, 4, 1, 2, 8, 5][..], &[3, 2, 1, 2], &[0, 0]];
+    let witnesses = pipeline
+        .witgen_batch(input_sets.iter().map(|i| slice_to_vec(i)).collect())
+        .unwrap()
+        .into_iter()
+        .map(Result::unwrap)
+        .collect::<Vec<_>>();
+
+    // The largest value of the accumulator is the sum of the inputs.
+    let max_a = |witness: &Vec<(String, Vec<GoldilocksField>)>| {
+        let (_, values) = witness.iter().find(|(name, _)| name == "main::A").unwrap();
+        *values.iter().max().unwrap()
+    };
+    assert_eq!(
+        witnesses.iter().map(max_a).collect::<Vec<_>>(),
+        vec![16.into(), 3.into(), 0.into()]
+    );
+    assert_ne!(witnesses[0], witnesses[1]);
+    assert_ne!(witnesses[1], witnesses[2]);
+}
+
+#[test]
+fn simple_sum_pil_ast_round_trip() {
+    let mut pipeline =
+        Pipeline::<GoldilocksField>::default().from_file(resolve_test_file("asm/simple_sum.asm"));
+    let exported = pipeline.export_pil_ast().unwrap();
+
+    let imported = Pipeline::<GoldilocksField>::default()
+        .import_pil_ast(&exported)
+        .unwrap();
+    assert_eq!(
+        imported.optimized_pil().unwrap().to_string(),
+        pipeline.optimized_pil().unwrap().to_string()
+    );
+
+    assert!(Pipeline::<GoldilocksField>::default()
+        .import_pil_ast(&[])
+        .is_err());
+}
+
+#[test]
+fn simple_sum_column_origins() {
+    let mut pipeline =
+        Pipeline::<GoldilocksField>::default().from_file(resolve_test_file("asm/simple_sum.asm"));
+    let origins = pipeline.column_origins().unwrap();
+
+    assert_eq!(origins["main::A"], ColumnOrigin::Register("A".into()));
+    assert_eq!(
+        origins["main::instr_jmpz"],
+        ColumnOrigin::InstructionFlag("jmpz".into())
+    );
+    assert_eq!(
+        origins["main::instr_jmpz_param_l"],
+        ColumnOrigin::InstructionParam {
+            instruction: "jmpz".into(),
+            param: "l".into()
+        }
+    );
+    assert_eq!(
+        origins["main::X_free_value"],
+        ColumnOrigin::FreeValue("X".into())
+    );
+    assert_eq!(
+        origins["main__rom::p_instr_jmpz"],
+        ColumnOrigin::ProgramConstant("instr_jmpz".into())
+    );
+}
+
 #[test]
 #[should_panic = "Witness generation failed."]
 fn secondary_machine_plonk() {
@@ -429,6 +577,73 @@ fn read_poly_files() {
     }
 }
 
+#[cfg(feature = "estark-starky")]
+#[test]
+fn proof_file_round_trip() {
+    use powdr_backend::BackendType;
+
+    let tmp_dir = mktemp::Temp::new_dir().unwrap();
+    let mut pipeline = Pipeline::<GoldilocksField>::default()
+        .from_file(resolve_test_file("asm/simple_sum.asm"))
+        .with_prover_inputs(slice_to_vec(&[16, 4, 1, 2, 8, 5]))
+        .with_backend(BackendType::EStarkStarky, None);
+    pipeline.compute_proof().unwrap();
+
+    let proof_file = tmp_dir.as_path().join("proof.bin");
+    pipeline.write_proof(&proof_file).unwrap();
+    let proof = std::fs::read(&proof_file).unwrap();
+    assert_eq!(&proof, pipeline.proof().unwrap());
+
+    let publics = pipeline
+        .publics()
+        .unwrap()
+        .iter()
+        .map(|(_, v)| v.unwrap())
+        .collect();
+    pipeline.verify(&proof, &[publics]).unwrap();
+}
+
+#[cfg(feature = "estark-starky")]
+#[test]
+fn verify_proof_from_bytes() {
+    use powdr_backend::BackendType;
+    use powdr_pipeline::verify::verify_proof;
+
+    let (pil, fixed_cols, vkey, proof, publics) = {
+        let mut pipeline = Pipeline::<GoldilocksField>::default()
+            .from_file(resolve_test_file("asm/simple_sum.asm"))
+            .with_prover_inputs(slice_to_vec(&[16, 4, 1, 2, 8, 5]))
+            .with_backend(BackendType::EStarkStarky, None);
+        let proof = pipeline.compute_proof().unwrap().clone();
+        let mut vkey = vec![];
+        pipeline.export_verification_key(&mut vkey).unwrap();
+        let publics = pipeline
+            .publics()
+            .unwrap()
+            .iter()
+            .map(|(_, v)| v.unwrap())
+            .collect::<Vec<_>>();
+        (
+            pipeline.compute_optimized_pil().unwrap(),
+            pipeline.compute_fixed_cols().unwrap(),
+            vkey,
+            proof,
+            publics,
+        )
+    };
+
+    verify_proof(
+        BackendType::EStarkStarky,
+        Default::default(),
+        pil,
+        fixed_cols,
+        &vkey,
+        &proof,
+        &publics,
+    )
+    .unwrap();
+}
+
 #[test]
 fn enum_in_asm() {
     let f = "asm/enum_in_asm.asm";
@@ -33,3 +33,21 @@ fn two_machines_conflicting_public() {
     "#;
     run_witgen_pil::<GoldilocksField>(src);
 }
+
+#[test]
+fn too_short_inputs_rejected_by_schema() {
+    let src = r#"
+    namespace main(4);
+        col witness x;
+        x = 1;
+    "#;
+    let result = Pipeline::<GoldilocksField>::default()
+        .from_pil_string(src.to_string())
+        .with_prover_inputs(vec![1.into(), 2.into()])
+        .with_input_schema([(0, 4)].into())
+        .compute_witness();
+    assert_eq!(
+        result.err().unwrap(),
+        vec!["Input channel 0: expected 4 elements, but 2 were provided".to_string()]
+    );
+}
@@ -0,0 +1,109 @@
+use powdr_number::GoldilocksField;
+use powdr_pipeline::{try_compile_asm, CompileError};
+use test_log::test;
+
+#[test]
+fn valid_program_compiles() {
+    let src = r"
+machine Empty with degree: 4 {
+    col witness w;
+    w = w * w;
+}
+";
+    assert_eq!(try_compile_asm::<GoldilocksField>(src), Ok(()));
+}
+
+#[test]
+fn malformed_programs_return_errors() {
+    let programs = [
+        // syntax error
+        "machine Main {",
+        // unknown submachine type
+        "machine Main with degree: 4 { Unknown u; }",
+        // unknown instruction
+        r"
+machine Main with degree: 4 {
+    reg pc[@pc];
+    reg X[<=];
+    reg A;
+
+    function main {
+        A <=X= foo();
+    }
+}
+",
+    ];
+    for src in programs {
+        assert!(
+            matches!(
+                try_compile_asm::<GoldilocksField>(src),
+                Err(CompileError::Errors(_))
+            ),
+            "expected errors for {src}"
+        );
+    }
+}
+
+#[test]
+fn asm_to_pil_errors_are_returned() {
+    let programs = [
+        // user instruction with the name of a control instruction
+        (
+            r"
+machine Main with degree: 4 {
+    reg pc[@pc];
+    reg A;
+
+    instr _reset { A' = 0 }
+
+    function main {
+        return;
+    }
+}
+",
+            "Instruction name `_reset` is reserved for an internal control instruction",
+        ),
+        // negative number passed to an unsigned parameter
+        (
+            r"
+machine Main with degree: 4 {
+    reg pc[@pc];
+    reg A;
+
+    instr addu x: unsigned { A' = A + x }
+
+    function main {
+        addu 9223372034707292161;
+        return;
+    }
+}
+",
+            "Number passed to unsigned parameter is negative or too large",
+        ),
+        // match expression outside of a free input
+        (
+            r"
+machine Main with degree: 4 {
+    reg pc[@pc];
+    reg X[<=];
+    reg A;
+
+    function main {
+        A <=X= match 1 { 1 => 2, _ => 3 };
+        return;
+    }
+}
+",
+            "Match expressions in assignments are only supported inside free inputs",
+        ),
+    ];
+    for (src, expected) in programs {
+        match try_compile_asm::<GoldilocksField>(src) {
+            Err(CompileError::Errors(errors)) => assert!(
+                errors.iter().any(|e| e.starts_with(expected)),
+                "expected error starting with {expected:?}, got {errors:?}"
+            ),
+            result => panic!("expected errors for {src}, got {result:?}"),
+        }
+    }
+}
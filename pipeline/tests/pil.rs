@@ -5,15 +5,46 @@ use powdr_pipeline::{
         assert_proofs_fail_for_invalid_witnesses, assert_proofs_fail_for_invalid_witnesses_estark,
         assert_proofs_fail_for_invalid_witnesses_mock,
         assert_proofs_fail_for_invalid_witnesses_pilcom,
-        assert_proofs_fail_for_invalid_witnesses_stwo, make_prepared_pipeline,
-        make_simple_prepared_pipeline, regular_test_all_fields, regular_test_gl,
-        test_halo2_with_backend_variant, test_mock_backend, test_stwo, BackendVariant,
+        assert_proofs_fail_for_invalid_witnesses_stwo, assert_tamper_detected,
+        make_prepared_pipeline, make_simple_prepared_pipeline, regular_test_all_fields,
+        regular_test_gl, test_halo2_with_backend_variant, test_mock_backend, test_stwo,
+        BackendVariant,
     },
-    Pipeline,
+    BackendUnavailable, Pipeline,
 };
 
 use test_log::test;
 
+#[test]
+fn available_backends() {
+    let names = Pipeline::<GoldilocksField>::available_backends()
+        .iter()
+        .map(|b| b.to_string())
+        .collect::<Vec<_>>();
+    assert!(names.contains(&"mock".to_string()));
+    #[cfg(not(feature = "halo2"))]
+    assert!(!names.contains(&"halo2".to_string()));
+
+    let pipeline = Pipeline::<GoldilocksField>::default();
+    assert!(pipeline.with_backend_name("mock", None).is_ok());
+}
+
+#[test]
+#[cfg(not(feature = "halo2"))]
+fn unavailable_backend() {
+    let error = Pipeline::<GoldilocksField>::default()
+        .with_backend_name("halo2", None)
+        .err()
+        .unwrap();
+    assert_eq!(error, BackendUnavailable::NotEnabled("halo2".to_string()));
+
+    let error = Pipeline::<GoldilocksField>::default()
+        .with_backend_name("foo", None)
+        .err()
+        .unwrap();
+    assert_eq!(error, BackendUnavailable::Unknown("foo".to_string()));
+}
+
 #[test]
 fn invalid_witness() {
     let f = "pil/trivial.pil";
@@ -116,6 +147,14 @@ fn fibonacci_with_public() {
     pipeline.compute_witness().unwrap();
 }
 
+#[test]
+fn fibonacci_tamper_detected() {
+    let f = "pil/fibonacci.pil";
+    assert_tamper_detected::<GoldilocksField>(f, powdr_backend::BackendType::Mock, vec![]);
+    #[cfg(feature = "plonky3")]
+    assert_tamper_detected::<GoldilocksField>(f, powdr_backend::BackendType::Plonky3, vec![]);
+}
+
 #[test]
 fn fibonacci_invalid_witness() {
     let f = "pil/fibonacci.pil";
@@ -437,3 +476,139 @@ mod book {
 
     include!(concat!(env!("OUT_DIR"), "/pil_book_tests.rs"));
 }
+
+#[test]
+#[cfg(feature = "plonky3")]
+fn prover_bundle_round_trip() {
+    use powdr_backend::BackendType;
+    use powdr_pipeline::{pipeline::prove_from_bundle, test_util::resolve_test_file};
+
+    let mut pipeline = Pipeline::<GoldilocksField>::default()
+        .from_file(resolve_test_file("pil/fibonacci.pil"))
+        .with_backend(BackendType::Plonky3, None);
+    pipeline.compute_witness().unwrap();
+    pipeline.compute_fixed_cols().unwrap();
+
+    let bundle = pipeline.export_prover_bundle().unwrap();
+    let proof = prove_from_bundle::<GoldilocksField>(&bundle).unwrap();
+
+    let publics = pipeline
+        .publics()
+        .unwrap()
+        .into_iter()
+        .map(|(_name, v)| v.unwrap())
+        .collect();
+    pipeline.verify(&proof, &[publics]).unwrap();
+}
+
+#[test]
+#[cfg(feature = "plonky3")]
+fn verify_any_publics() {
+    use powdr_backend::BackendType;
+    use powdr_pipeline::{
+        test_util::resolve_test_file,
+        verify::{verify_any_publics, VerificationError},
+    };
+
+    let mut pipeline = Pipeline::<GoldilocksField>::default()
+        .from_file(resolve_test_file("pil/fibonacci.pil"))
+        .with_backend(BackendType::Plonky3, None);
+    let proof = pipeline.compute_proof().unwrap().clone();
+    let mut vkey = vec![];
+    pipeline.export_verification_key(&mut vkey).unwrap();
+    let pil = pipeline.compute_optimized_pil().unwrap();
+    let fixed_cols = pipeline.compute_fixed_cols().unwrap();
+
+    let publics: Vec<GoldilocksField> = pipeline
+        .publics()
+        .unwrap()
+        .into_iter()
+        .map(|(_name, v)| v.unwrap())
+        .collect();
+    let shifted = |offset: u64| {
+        publics
+            .iter()
+            .map(|v| *v + GoldilocksField::from(offset))
+            .collect::<Vec<_>>()
+    };
+    let verify = |candidates: &[Vec<GoldilocksField>]| {
+        verify_any_publics(
+            BackendType::Plonky3,
+            Default::default(),
+            pil.clone(),
+            fixed_cols.clone(),
+            &vkey,
+            candidates,
+            &proof,
+        )
+    };
+
+    let candidates = [shifted(1), publics.clone(), shifted(2)];
+    assert_eq!(verify(&candidates), Ok(1));
+
+    let Err(VerificationError::Rejected(message)) = verify(&[shifted(1), shifted(2)]) else {
+        panic!("Expected the proof to be rejected");
+    };
+    assert!(message.starts_with("None of the 2 candidate public input sets verified the proof"));
+}
+
+#[test]
+fn deny_warnings() {
+    let pil = r#"
+namespace main(4);
+    let<T> unused: int = 1;
+    col witness w;
+    w = 0;
+"#;
+    let mut pipeline = Pipeline::<GoldilocksField>::default().from_pil_string(pil.to_string());
+    pipeline.compute_analyzed_pil().unwrap();
+    assert_eq!(pipeline.warnings().len(), 1);
+    assert!(pipeline.warnings()[0].starts_with("Unused type variable(s) in declaration: T"));
+
+    let errors = Pipeline::<GoldilocksField>::default()
+        .from_pil_string(pil.to_string())
+        .deny_warnings(true)
+        .compute_analyzed_pil()
+        .map(|_| ())
+        .unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0]
+        .starts_with("Warning treated as error: Unused type variable(s) in declaration: T"));
+}
+
+#[test]
+fn assumed_tuples() {
+    let pil = r#"
+namespace main(4);
+    col witness w;
+    col fixed s_w = [1, 0]*;
+    col fixed t = [2, 4]*;
+    s_w $ [w] in [table::t];
+    // Lookups within the namespace are checked by its proof.
+    s_w $ [w] in [t];
+namespace table(4);
+    col fixed t = [1, 2, 3, 4]*;
+"#;
+    let witness = [2, 42, 4, 17]
+        .into_iter()
+        .map(GoldilocksField::from)
+        .collect();
+    let mut pipeline = Pipeline::<GoldilocksField>::default()
+        .from_pil_string(pil.to_string())
+        .set_witness(vec![("main::w".to_string(), witness)]);
+
+    let assumed = pipeline.assumed_tuples("main").unwrap();
+    assert_eq!(assumed.len(), 1);
+    assert_eq!(assumed[0].target_namespace, "table");
+    assert_eq!(
+        assumed[0].tuples,
+        [
+            vec![GoldilocksField::from(2)],
+            vec![GoldilocksField::from(4)]
+        ]
+        .into_iter()
+        .collect()
+    );
+
+    assert!(pipeline.assumed_tuples("table").is_err());
+}
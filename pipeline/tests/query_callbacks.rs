@@ -0,0 +1,547 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+
+use mktemp::Temp;
+use powdr_executor::witgen::QueryCallback;
+use powdr_number::{FieldElement, GoldilocksField};
+use powdr_pipeline::{
+    chain_query_callbacks, channeled_inputs_to_query_callback, dict_data_to_query_callback,
+    handle_simple_queries_callback, inputs_to_query_callback, inputs_with_default_query_callback,
+    into_query_callback, json_query_callback, matrix_query_callback, memoizing_query_callback,
+    mmap_inputs_to_query_callback, output_writers_callback, parse_query,
+    receiver_to_query_callback, sequential_inputs_to_query_callback,
+    serde_channels_to_query_callback, serde_data_to_query_callback, subprocess_query_callback,
+    HostContext, HostStore, Pipeline, QueryError, QueryParseError, QueryRouter,
+};
+use test_log::test;
+
+#[test]
+fn matrix_callback() {
+    let data = (1..=6).map(GoldilocksField::from).collect();
+    let cb = matrix_query_callback(2, 3, data);
+
+    assert_eq!(cb("Matrix(0, 0)"), Ok(Some(2.into())));
+    assert_eq!(cb("Matrix(0, 1)"), Ok(Some(3.into())));
+    assert_eq!(cb("Matrix(1, 1)"), Ok(Some(1.into())));
+    assert_eq!(cb("Matrix(1, 3)"), Ok(Some(3.into())));
+    assert_eq!(cb("std::prelude::Query::Matrix(2, 1)"), Ok(Some(4.into())));
+    assert_eq!(cb("Matrix(2, 3)"), Ok(Some(6.into())));
+
+    assert_eq!(
+        cb("Matrix(3, 1)"),
+        Err(QueryError::Failed(
+            "Row index 3 out of bounds (matrix has 2 rows)".to_string()
+        ))
+    );
+    assert_eq!(
+        cb("Matrix(1, 4)"),
+        Err(QueryError::Failed(
+            "Column index 4 out of bounds (matrix has 3 columns)".to_string()
+        ))
+    );
+    assert_eq!(
+        cb("Matrix(1, 0)"),
+        Err(QueryError::Failed(
+            "Column index 0 out of bounds (matrix has 3 columns)".to_string()
+        ))
+    );
+}
+
+#[test]
+fn json_callback() {
+    let doc = serde_json::json!({
+        "config": { "limits": { "max": 42, "offsets": [3, -1] } },
+        "name": "test"
+    });
+    let cb = json_query_callback::<GoldilocksField>(doc);
+
+    assert_eq!(cb("JsonGet(\"config.limits.max\")"), Ok(Some(42.into())));
+    assert_eq!(
+        cb("std::prelude::Query::JsonGet(\"config.limits.offsets.1\")"),
+        Ok(Some(-GoldilocksField::from(1)))
+    );
+    assert_eq!(
+        cb("JsonGet(\"config.limits.min\")"),
+        Err(QueryError::Failed(
+            "JSON path \"config.limits.min\" not found".to_string()
+        ))
+    );
+    assert_eq!(
+        cb("JsonGet(\"name\")"),
+        Err(QueryError::Failed(
+            "Value at JSON path \"name\" is not a number: \"test\"".to_string()
+        ))
+    );
+}
+
+#[test]
+fn memoizing_callback() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let inner_calls = calls.clone();
+    let cb = memoizing_query_callback(move |query: &str| {
+        inner_calls.fetch_add(1, Ordering::SeqCst);
+        match query {
+            "Length" => Ok(Some(GoldilocksField::from(42))),
+            "None" => Ok(None),
+            _ => Err(QueryError::UnsupportedQuery(query.to_string())),
+        }
+    });
+
+    assert_eq!(cb("Length"), Ok(Some(42.into())));
+    assert_eq!(cb("Length"), Ok(Some(42.into())));
+    assert_eq!(cb("None"), Ok(None));
+    assert_eq!(cb("None"), Ok(None));
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+    // Errors are not cached.
+    assert!(cb("Other").is_err());
+    assert!(cb("Other").is_err());
+    assert_eq!(calls.load(Ordering::SeqCst), 4);
+}
+
+#[test]
+fn query_router() {
+    let cb = QueryRouter::<GoldilocksField>::default()
+        .on("Input", |args| {
+            let index = args[1].parse::<u64>().map_err(|e| e.to_string())?;
+            Ok(Some((index * 10).into()))
+        })
+        .on("None", |_| Ok(None))
+        .into_callback();
+
+    assert_eq!(cb("Input(0, 4)"), Ok(Some(40.into())));
+    assert_eq!(cb("std::prelude::Query::Input(0, 2)"), Ok(Some(20.into())));
+    assert_eq!(cb("None"), Ok(None));
+    assert_eq!(
+        cb("Output(1, 65)"),
+        Err(QueryError::UnsupportedQuery("Output(1, 65)".to_string()))
+    );
+}
+
+#[test]
+fn chained_callbacks() {
+    let (ctx, _) = HostContext::new::<GoldilocksField>();
+    let callbacks: Vec<Box<dyn QueryCallback<GoldilocksField>>> = vec![
+        Box::new(inputs_to_query_callback(vec![7.into()])),
+        Box::new(into_query_callback(ctx.query_callback())),
+        Box::new(into_query_callback(
+            QueryRouter::default()
+                .on("Input", |_| Ok(Some(42.into())))
+                .into_callback(),
+        )),
+    ];
+    let cb = chain_query_callbacks(callbacks);
+
+    assert_eq!(cb("Input(0, 1)"), Ok(Some(7.into())));
+    // Channel 5 is not served by the first two callbacks.
+    assert_eq!(cb("Input(5, 1)"), Ok(Some(42.into())));
+    // Genuine errors are not passed on to the following callbacks.
+    assert!(cb("Input(0, x)")
+        .unwrap_err()
+        .starts_with("Error parsing index"));
+
+    assert_eq!(cb("Output(1, 65)"), Ok(Some(0.into())));
+    assert_eq!(ctx.file_data.lock().unwrap()[&1], b"A".to_vec());
+
+    assert_eq!(cb("Hint(1)"), Err("Unsupported query: Hint(1)".to_string()));
+}
+
+#[test]
+fn channeled_inputs() {
+    let cb = channeled_inputs_to_query_callback(BTreeMap::from([
+        (0, vec![GoldilocksField::from(7), 8.into()]),
+        (2, vec![9.into()]),
+    ]));
+    assert_eq!(cb("Input(0, 0)"), Ok(Some(2.into())));
+    assert_eq!(cb("Input(0, 2)"), Ok(Some(8.into())));
+    assert_eq!(cb("Input(2, 0)"), Ok(Some(1.into())));
+    assert_eq!(cb("Input(2, 1)"), Ok(Some(9.into())));
+    // The single-argument form reads from channel 0.
+    assert_eq!(cb("Input(1)"), Ok(Some(7.into())));
+
+    assert_eq!(
+        cb("Input(2, 2)"),
+        Err(QueryError::Failed(
+            "Input index 2 out of bounds for channel 2 (there are 1 inputs)".to_string()
+        ))
+    );
+    assert_eq!(cb("Input(1, 1)"), Err(QueryError::ChannelMismatch(1)));
+}
+
+#[test]
+fn serde_channels() {
+    #[derive(serde::Serialize)]
+    struct Proof {
+        a: Vec<u8>,
+        b: u64,
+    }
+    #[derive(serde::Serialize)]
+    struct Key {
+        name: String,
+    }
+
+    let proof = serde_cbor::to_vec(&Proof {
+        a: vec![1, 2, 3],
+        b: 7,
+    })
+    .unwrap();
+    let key = serde_cbor::to_vec(&Key {
+        name: "pvk".to_string(),
+    })
+    .unwrap();
+    let cb = serde_channels_to_query_callback::<GoldilocksField>(BTreeMap::from([
+        (666, proof.clone()),
+        (667, key.clone()),
+    ]));
+
+    assert_eq!(cb("Input(666, 0)"), Ok(Some((proof.len() as u64).into())));
+    assert_eq!(cb("Input(667, 0)"), Ok(Some((key.len() as u64).into())));
+    assert_eq!(cb("Input(667, 1)"), Ok(Some((key[0] as u64).into())));
+    assert_eq!(cb("Input(668, 0)"), Err(QueryError::ChannelMismatch(668)));
+}
+
+#[test]
+fn input_index_out_of_bounds() {
+    let cb = serde_data_to_query_callback::<GoldilocksField>(666, vec![1, 2]);
+    assert_eq!(cb("Input(666, 2)"), Ok(Some(2.into())));
+    assert_eq!(
+        cb("Input(666, 3)"),
+        Err("Input index 3 out of bounds for channel 666 (there are 2 bytes)".to_string())
+    );
+
+    let cb = dict_data_to_query_callback(BTreeMap::from([(1, vec![GoldilocksField::from(7)])]));
+    assert_eq!(cb("Input(1, 1)"), Ok(Some(7.into())));
+    assert_eq!(
+        cb("Input(1, 2)"),
+        Err("Input index 2 out of bounds for channel 1 (there are 1 elements)".to_string())
+    );
+}
+
+#[test]
+fn hint_query() {
+    let cb = handle_simple_queries_callback::<GoldilocksField>();
+    assert_eq!(cb("Hint(42)"), Ok(Some(42.into())));
+    assert_eq!(cb("std::prelude::Query::Hint(0x2a)"), Ok(Some(42.into())));
+    assert_eq!(
+        cb("Hint(0xffffffffffffffff)"),
+        Err("Invalid hint value 0xffffffffffffffff: Number \"ffffffffffffffff\" too large for field.".to_string())
+    );
+    assert!(cb("Hint(abc)").is_err());
+    assert!(cb("Hint(1, 2)").is_err());
+}
+
+#[test]
+fn sequential_inputs() {
+    let inputs = vec![GoldilocksField::from(7), 8.into(), 9.into()];
+    let cb = sequential_inputs_to_query_callback(inputs);
+    assert_eq!(cb("Input(0, 0)"), Ok(Some(3.into())));
+    assert_eq!(cb("Input(0, 1)"), Ok(Some(7.into())));
+    assert_eq!(cb("Input(0, 0)"), Ok(Some(3.into())));
+    assert_eq!(cb("Input(0, 2)"), Ok(Some(8.into())));
+    assert_eq!(cb("Input(0, 3)"), Ok(Some(9.into())));
+}
+
+#[test]
+fn streamed_inputs() {
+    let (sender, receiver) = mpsc::channel();
+    let cb = receiver_to_query_callback::<GoldilocksField>(receiver);
+    sender.send(7.into()).unwrap();
+    sender.send(8.into()).unwrap();
+    assert_eq!(cb("Input(0, 2)"), Ok(Some(8.into())));
+    assert_eq!(cb("Input(1)"), Ok(Some(7.into())));
+    assert_eq!(cb("Input(1, 1)"), Err(QueryError::ChannelMismatch(1)));
+    assert!(cb("Input(0, 0)").is_err());
+
+    drop(sender);
+    assert_eq!(
+        cb("Input(0, 3)"),
+        Err(QueryError::Failed(
+            "Input channel closed before input 3 was received (received 2 inputs)".to_string()
+        ))
+    );
+}
+
+#[test]
+fn streamed_inputs_in_pipeline() {
+    let code = r#"
+machine Main with degree: 8 {
+    reg pc[@pc];
+    reg X[<=];
+    reg A;
+    reg B;
+
+    instr assert_zero X { X = 0 }
+
+    function main {
+        A <=X= ${ std::prelude::Query::Input(0, 1) };
+        B <=X= ${ std::prelude::Query::Input(0, 2) };
+        assert_zero A + B - 10;
+        return;
+    }
+}
+"#;
+    // A rendezvous channel: each send blocks until witness generation asks for the input.
+    let (sender, receiver) = mpsc::sync_channel(0);
+    let producer = thread::spawn(move || {
+        for value in [3, 7] {
+            sender.send(GoldilocksField::from(value)).unwrap();
+        }
+    });
+    let mut pipeline = Pipeline::<GoldilocksField>::default()
+        .from_asm_string(code.to_string(), None)
+        .with_streamed_prover_inputs(receiver);
+    let witness = pipeline.compute_witness().unwrap();
+    producer.join().unwrap();
+
+    let (_, b) = witness.iter().find(|(name, _)| name == "main::B").unwrap();
+    assert!(b.contains(&7.into()));
+}
+
+#[test]
+fn inputs_with_default() {
+    let inputs = vec![GoldilocksField::from(7), 8.into()];
+    let cb = inputs_with_default_query_callback(inputs, 0.into());
+    assert_eq!(cb("Input(0, 0)"), Ok(Some(2.into())));
+    assert_eq!(cb("Input(0, 2)"), Ok(Some(8.into())));
+    assert_eq!(cb("Input(0, 3)"), Ok(Some(0.into())));
+    assert_eq!(cb("Input(0, 100)"), Ok(Some(0.into())));
+}
+
+#[test]
+fn mmap_inputs() {
+    let file = Temp::new_file().unwrap();
+    let bytes = [7u64, 8, 1 << 40]
+        .into_iter()
+        .flat_map(|v| GoldilocksField::from(v).to_bytes_le())
+        .collect::<Vec<_>>();
+    std::fs::write(file.as_path(), bytes).unwrap();
+
+    let cb = mmap_inputs_to_query_callback::<GoldilocksField>(file.as_path(), 8).unwrap();
+    assert_eq!(cb("Input(0, 0)"), Ok(Some(3.into())));
+    assert_eq!(cb("Input(0, 3)"), Ok(Some((1u64 << 40).into())));
+    assert_eq!(cb("Input(0, 1)"), Ok(Some(7.into())));
+    assert_eq!(
+        cb("Input(0, 4)"),
+        Err(QueryError::Failed(
+            "Input index 4 out of bounds (there are 3 inputs)".to_string()
+        ))
+    );
+
+    assert!(mmap_inputs_to_query_callback::<GoldilocksField>(file.as_path(), 5).is_err());
+}
+
+struct FixedStore;
+
+impl HostStore for FixedStore {
+    fn get(&self, channel: u32) -> Option<&[u8]> {
+        (channel == 3).then_some(&[7, 8, 9][..])
+    }
+}
+
+#[test]
+fn host_store() {
+    let (_, cb) = HostContext::with_store::<GoldilocksField>(FixedStore);
+
+    assert_eq!(cb("Input(3, 0)"), Ok(Some(3.into())));
+    assert_eq!(cb("Input(3, 1)"), Ok(Some(7.into())));
+    assert_eq!(cb("Input(3, 3)"), Ok(Some(9.into())));
+    assert_eq!(
+        cb("Input(3, 4)"),
+        Err("Index 4 out of bounds for channel 3".to_string())
+    );
+    assert_eq!(
+        cb("Input(0, 1)"),
+        Err("Callback channel mismatch: 0".to_string())
+    );
+}
+
+#[test]
+fn host_context_data() {
+    let data = BTreeMap::from([(1u32, b"hi".to_vec())]);
+    let (ctx, cb) = HostContext::with_store::<GoldilocksField>(data);
+
+    // Reads as issued by `io::read` in the guest: the length first, then the bytes.
+    assert_eq!(cb("std::prelude::Query::Input(1, 0)"), Ok(Some(2.into())));
+    assert_eq!(cb("Input(1, 1)"), Ok(Some(u64::from(b'h').into())));
+    assert_eq!(cb("Input(1, 2)"), Ok(Some(u64::from(b'i').into())));
+
+    // Writes by the guest end up in the file data, separately from the store.
+    assert_eq!(cb("Output(1, 120)"), Ok(Some(0.into())));
+    assert_eq!(ctx.file_data.lock().unwrap()[&1], b"x".to_vec());
+    assert_eq!(cb("Input(1, 0)"), Ok(Some(2.into())));
+
+    assert_eq!(cb("Hint(1)"), Err("Unsupported query: Hint(1)".to_string()));
+}
+
+#[test]
+fn captured_guest_output() {
+    let code = r#"
+machine Main with degree: 8 {
+    reg pc[@pc];
+    reg X[<=];
+    reg A;
+
+    function main {
+        A <=X= ${ std::prelude::Query::Output(1, 111) };
+        A <=X= ${ std::prelude::Query::Output(1, 107) };
+        A <=X= ${ std::prelude::Query::Output(1, 10) };
+        A <=X= ${ std::prelude::Query::Output(2, 33) };
+        return;
+    }
+}
+"#;
+    let mut pipeline =
+        Pipeline::<GoldilocksField>::default().from_asm_string(code.to_string(), None);
+    pipeline.compute_witness().unwrap();
+
+    assert_eq!(pipeline.host_context().output(1), b"ok\n".to_vec());
+    assert_eq!(pipeline.host_context().output(2), b"!".to_vec());
+    assert!(pipeline.host_context().output(3).is_empty());
+}
+
+/// A writer appending to a buffer which can be inspected afterwards.
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn output_writers() {
+    let events = SharedBuffer::default();
+    let writers: BTreeMap<u32, Box<dyn Write + Send>> =
+        BTreeMap::from([(3, Box::new(events.clone()) as Box<dyn Write + Send>)]);
+    let cb = output_writers_callback::<GoldilocksField>(writers);
+
+    assert_eq!(cb("Output(3, 2, 104, 105)"), Ok(Some(0.into())));
+    assert_eq!(cb("Output(3, 33)"), Ok(Some(0.into())));
+    assert_eq!(*events.0.lock().unwrap(), b"hi!".to_vec());
+
+    // Without a registered writer, stdout and stderr are used.
+    assert_eq!(cb("Output(1, 10)"), Ok(Some(0.into())));
+    assert_eq!(cb("Output(2, 10)"), Ok(Some(0.into())));
+    assert_eq!(cb("Output(4, 33)"), Err(QueryError::ChannelMismatch(4)));
+}
+
+#[test]
+fn multi_byte_output() {
+    let (ctx, cb) = HostContext::new::<GoldilocksField>();
+
+    assert_eq!(
+        cb("Output(1, 5, 104, 101, 108, 108, 111)"),
+        Ok(Some(0.into()))
+    );
+    assert_eq!(cb("Output(1, 33)"), Ok(Some(0.into())));
+    assert_eq!(ctx.file_data.lock().unwrap()[&1], b"hello!".to_vec());
+
+    assert_eq!(
+        cb("Output(1, 3, 104, 105)"),
+        Err("Output query announces 3 bytes but contains 2".to_string())
+    );
+}
+
+#[test]
+fn malformed_queries() {
+    assert_eq!(
+        parse_query("foo(1,2"),
+        Err(QueryParseError::MissingClosingParen {
+            query: "foo(1,2".to_string()
+        })
+    );
+    assert_eq!(
+        parse_query("std::prelude::Query::(1)"),
+        Err(QueryParseError::EmptyName {
+            query: "(1)".to_string()
+        })
+    );
+    assert_eq!(parse_query("Input(0, 1)"), Ok(("Input", vec!["0", "1"])));
+
+    let cb = inputs_to_query_callback::<GoldilocksField>(vec![]);
+    assert_eq!(
+        cb("Input(0, 1"),
+        Err(
+            "Error parsing query input \"Input(0, 1\". Could not find closing ')' in enum data."
+                .to_string()
+        )
+    );
+}
+
+#[test]
+fn sequential_inputs_out_of_order() {
+    let inputs = vec![GoldilocksField::from(7), 8.into(), 9.into()];
+    let cb = sequential_inputs_to_query_callback(inputs);
+    assert_eq!(cb("Input(0, 1)"), Ok(Some(7.into())));
+    assert_eq!(
+        cb("Input(0, 1)"),
+        Err(QueryError::Failed(
+            "Input index 1 requested out of order, expected index 2".to_string()
+        ))
+    );
+    assert_eq!(
+        cb("Input(0, 3)"),
+        Err(QueryError::Failed(
+            "Input index 3 requested out of order, expected index 2".to_string()
+        ))
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn subprocess_callback() {
+    let script = r#"while read -r query; do
+        case "$query" in
+            "Input(0, 1)") echo 42;;
+            "Input(0, 2)") echo 7;;
+            "Input(0, 3)") echo abc;;
+            *) echo None;;
+        esac
+    done"#;
+    let cb = subprocess_query_callback::<GoldilocksField>(vec![
+        "sh".to_string(),
+        "-c".to_string(),
+        script.to_string(),
+    ]);
+
+    assert_eq!(cb("Input(0, 1)"), Ok(Some(42.into())));
+    assert_eq!(cb("Input(0, 2)"), Ok(Some(7.into())));
+    assert_eq!(cb("Hint(1)"), Ok(None));
+    assert!(cb("Input(0, 3)")
+        .unwrap_err()
+        .to_string()
+        .starts_with("Invalid response of external query process to query Input(0, 3)"));
+    // The process is still alive after an invalid response.
+    assert_eq!(cb("Input(0, 1)"), Ok(Some(42.into())));
+}
+
+#[cfg(unix)]
+#[test]
+fn subprocess_callback_process_died() {
+    let cb = subprocess_query_callback::<GoldilocksField>(vec![
+        "sh".to_string(),
+        "-c".to_string(),
+        "read -r query; echo 1; exit 3".to_string(),
+    ]);
+
+    assert_eq!(cb("Input(0, 1)"), Ok(Some(1.into())));
+    assert!(cb("Input(0, 2)")
+        .unwrap_err()
+        .to_string()
+        .starts_with("External query process"));
+
+    let cb = subprocess_query_callback::<GoldilocksField>(vec!["/nonexistent/binary".to_string()]);
+    assert!(cb("Input(0, 1)")
+        .unwrap_err()
+        .to_string()
+        .starts_with("Could not start external query process `/nonexistent/binary`"));
+}
@@ -27,7 +27,8 @@ use powdr_ast::{
     asm_analysis::{AnalysisASMFile, CallableSymbol, FunctionStatement, LabelStatement, Machine},
     parsed::{
         asm::{parse_absolute_path, AssignmentRegister, DebugDirective},
-        BinaryOperation, Expression, FunctionCall, Number, UnaryOperation,
+        types::Type,
+        BinaryOperation, CastExpression, Expression, FunctionCall, Number, UnaryOperation,
     },
 };
 use tiny_keccak::keccakf;
@@ -2819,6 +2820,31 @@ impl<F: FieldElement> Executor<'_, '_, F> {
             Expression::BlockExpression(_, _) => panic!(),
             Expression::IndexAccess(_, _) => todo!(),
             Expression::StructExpression(_, _) => todo!(),
+            Expression::Cast(_, CastExpression { expr, ty }) => {
+                let value = self.eval_expression(expr).unwrap();
+                let result = match (ty, value) {
+                    (Type::Int, Elem::Field(f)) => f
+                        .to_integer()
+                        .try_into_u64()
+                        .and_then(|v| i64::try_from(v).ok())
+                        .map(Elem::Binary)
+                        .unwrap_or_else(|| panic!("cannot cast field element {f} to int")),
+                    // Like in the PIL evaluator, negative integers cannot be cast to fe.
+                    (Type::Fe, Elem::Binary(b)) if b < 0 => {
+                        panic!("cannot cast negative integer {b} to fe")
+                    }
+                    (Type::Fe, Elem::Binary(b)) => Elem::Field(F::from(b)),
+                    (Type::Int, value @ Elem::Binary(_)) | (Type::Fe, value @ Elem::Field(_)) => {
+                        value
+                    }
+                    (ty, _) => {
+                        panic!(
+                            "unsupported cast to {ty}, only casts between int and fe are supported"
+                        )
+                    }
+                };
+                Some(result)
+            }
         }
     }
 
@@ -161,6 +161,10 @@ pub fn read_bytes() -> &'static [u8] {
 }
 
 /// Reads and deserializes a serialized value of type T from the file descriptor fd.
+///
+/// The value is expected in the framing used by `serde_data_to_query_callback` on
+/// the host: index 0 holds the number of bytes of the CBOR encoding, the following
+/// indices hold one byte each.
 pub fn read_fd<T: DeserializeOwned>(fd: u32) -> T {
     let l = read_data_len(fd);
     let mut data = vec![0; l];
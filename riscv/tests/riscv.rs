@@ -448,6 +448,28 @@ fn two_sums_serde() {
     verify_riscv_crate_gl_with_data(case, vec![], vec![(42, data1), (43, data2)], true);
 }
 
+#[ignore = "Too slow"]
+#[test]
+fn struct_serde() {
+    let case = "struct_serde";
+
+    // Has to match the struct read by the guest.
+    #[derive(serde::Serialize)]
+    struct Claim {
+        name: String,
+        values: Vec<u32>,
+        sum: u32,
+    }
+    let claim = || Claim {
+        name: "claim".to_string(),
+        values: vec![1, 2, 8, 5],
+        sum: 16,
+    };
+
+    verify_riscv_crate_bb_with_data(case, vec![], vec![(42, claim())]);
+    verify_riscv_crate_gl_with_data(case, vec![], vec![(42, claim())], true);
+}
+
 const DISPATCH_TABLE_S: &str = "tests/riscv_data/dispatch_table/dispatch_table.s";
 
 /// Tests that the dispatch table is correctly relocated when PIE is enabled.
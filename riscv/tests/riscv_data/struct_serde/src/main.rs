@@ -0,0 +1,23 @@
+#![no_main]
+#![no_std]
+
+extern crate alloc;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use powdr_riscv_runtime::io::read_fd;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct Claim {
+    name: String,
+    values: Vec<u32>,
+    sum: u32,
+}
+
+#[no_mangle]
+pub fn main() {
+    let claim: Claim = read_fd(42);
+    assert_eq!(claim.name, "claim");
+    assert_eq!(claim.values.iter().sum::<u32>(), claim.sum);
+}
@@ -10,6 +10,11 @@ let make_conditional: Constr, expr -> Constr = |constraint, condition| match con
     Constr::Connection(_) => std::check::panic("Connection constraints cannot be conditional"),
 };
 
+/// Guards the instruction whose body contains this statement by a boolean condition:
+/// its register updates and constraints only apply if the condition is one.
+/// It is removed when the instruction is converted to constraints and cannot be used elsewhere.
+let guard: expr -> Constr = |condition| std::check::panic("std::constraints::guard can only be used in instruction bodies");
+
 /// Either one constraint or the other, depending on a boolean condition.
 let if_else: expr, Constr, Constr -> Constr = |condition, if_true, if_false| match (if_true, if_false) {
     (Constr::Identity(l_t, r_t), Constr::Identity(l_f, r_f)) =>